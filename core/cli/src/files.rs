@@ -0,0 +1,153 @@
+//! Local files read by `data upload` and written by `data download`.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use bytes::Bytes;
+use futures::Stream;
+use posemesh_domain_http::domain_data::DomainDataMetadata;
+use posemesh_domain_http::errors::DomainError;
+use posemesh_domain_http::sync::sanitize;
+use tokio::io::AsyncReadExt;
+
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Expands upload arguments into files, in the order given and without duplicates. An argument
+/// is a file, a directory (uploaded recursively, skipping hidden entries) or a glob pattern.
+pub fn collect_files(args: &[String]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for arg in args {
+        let path = Path::new(arg);
+        if path.is_dir() {
+            walk(path, &mut files)?;
+        } else if path.is_file() {
+            files.push(path.to_path_buf());
+        } else {
+            let mut matched = false;
+            for entry in glob::glob(arg).with_context(|| format!("Invalid pattern {arg}"))? {
+                let entry = entry?;
+                if entry.is_file() {
+                    files.push(entry);
+                    matched = true;
+                }
+            }
+            if !matched {
+                bail!("No files match {arg}");
+            }
+        }
+    }
+    let mut seen = HashSet::new();
+    files.retain(|file| seen.insert(file.clone()));
+    Ok(files)
+}
+
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let mut entries = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        if path.is_dir() {
+            walk(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Reads `path` chunk by chunk. Returns its size too.
+pub async fn file_chunks(
+    path: &Path,
+) -> Result<(u64, impl Stream<Item = Result<Bytes, DomainError>> + use<>)> {
+    let file = tokio::fs::File::open(path)
+        .await
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let size = file.metadata().await?.len();
+    let chunks = futures::stream::try_unfold(file, |mut file| async move {
+        let mut chunk = vec![0; CHUNK_SIZE];
+        let read = file.read(&mut chunk).await?;
+        if read == 0 {
+            return Ok(None);
+        }
+        chunk.truncate(read);
+        Ok(Some((Bytes::from(chunk), file)))
+    });
+    Ok((size, chunks))
+}
+
+/// Where a downloaded item goes: `<dir>/<data_type>/<name>`, the same layout as
+/// `DomainClient::sync_to_dir`. Items whose path is taken already get their id appended.
+pub fn download_path(
+    dir: &Path,
+    metadata: &DomainDataMetadata,
+    taken: &mut HashSet<PathBuf>,
+) -> PathBuf {
+    let mut path = dir
+        .join(sanitize(&metadata.data_type))
+        .join(sanitize(&metadata.name));
+    if !taken.insert(path.clone()) {
+        path.as_mut_os_string()
+            .push(format!(".{}", sanitize(&metadata.id)));
+        taken.insert(path.clone());
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_directories_and_globs() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("scans/nested")).unwrap();
+        std::fs::create_dir_all(root.join("scans/.cache")).unwrap();
+        for file in [
+            "a.ply",
+            "b.ply",
+            "notes.txt",
+            "scans/c.ply",
+            "scans/nested/d.ply",
+            "scans/.cache/e.ply",
+        ] {
+            std::fs::write(root.join(file), file).unwrap();
+        }
+        let arg = |p: &str| root.join(p).to_string_lossy().into_owned();
+
+        let files = collect_files(&[arg("*.ply"), arg("scans"), arg("a.ply")]).unwrap();
+
+        let names: Vec<_> = files
+            .iter()
+            .map(|f| f.strip_prefix(root).unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(
+            names,
+            ["a.ply", "b.ply", "scans/c.ply", "scans/nested/d.ply"]
+        );
+        assert!(collect_files(&[arg("*.obj")]).is_err());
+    }
+
+    #[tokio::test]
+    async fn reads_files_in_chunks() {
+        use futures::TryStreamExt;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("big.bin");
+        std::fs::write(&path, vec![7u8; CHUNK_SIZE + 10]).unwrap();
+
+        let (size, chunks) = file_chunks(&path).await.unwrap();
+        let chunks: Vec<Bytes> = chunks.try_collect().await.unwrap();
+
+        assert_eq!(size, CHUNK_SIZE as u64 + 10);
+        assert_eq!(
+            chunks.iter().map(Bytes::len).collect::<Vec<_>>(),
+            [CHUNK_SIZE, 10]
+        );
+    }
+}
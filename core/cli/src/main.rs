@@ -0,0 +1,658 @@
+//! `posemesh`: manage domains, domain data and reconstruction jobs from the command line.
+//!
+//! Endpoints come from `API_URL`, `DDS_URL` and `CLIENT_ID` (see [`Config::from_env`]). Log in
+//! once with `posemesh login`; later commands reuse the saved session. Pass `--json` to get
+//! machine-readable output.
+
+mod files;
+mod session;
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use clap::{Args, Parser, Subcommand};
+use futures::StreamExt;
+use posemesh_domain_http::bulk_delete::{DEFAULT_DELETE_CONCURRENCY, DeleteOptions};
+use posemesh_domain_http::config::Config;
+use posemesh_domain_http::domain_client::{DomainClient, ListDomainsQuery};
+use posemesh_domain_http::domain_data::{DomainAction, DomainDataMetadata, DownloadQuery};
+use posemesh_domain_http::progress::TransferControl;
+use posemesh_domain_http::reconstruction::{Job, JobRequest, JobStatus};
+use serde::Serialize;
+
+use crate::files::{collect_files, download_path, file_chunks};
+use crate::session::{Login, SessionDir, connect};
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "posemesh",
+    version,
+    about = "Manage posemesh domains, domain data and reconstruction jobs"
+)]
+struct Cli {
+    /// Print results as JSON.
+    #[arg(long, global = true)]
+    json: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Log in and keep the session for later commands.
+    #[command(subcommand)]
+    Login(LoginCommand),
+    /// Revoke and forget the saved session.
+    Logout,
+    /// List, create and delete domains.
+    #[command(subcommand)]
+    Domains(DomainsCommand),
+    /// List, download, upload and delete domain data.
+    #[command(subcommand)]
+    Data(DataCommand),
+    /// Reconstruction jobs.
+    #[command(subcommand)]
+    Jobs(JobsCommand),
+}
+
+#[derive(Debug, Subcommand)]
+enum LoginCommand {
+    /// With an Auki account. The password is taken from POSEMESH_PASSWORD.
+    User {
+        /// Defaults to POSEMESH_EMAIL.
+        #[arg(long)]
+        email: Option<String>,
+    },
+    /// With app credentials.
+    App {
+        /// Defaults to APP_KEY.
+        #[arg(long)]
+        app_key: Option<String>,
+        /// Defaults to APP_SECRET.
+        #[arg(long)]
+        app_secret: Option<String>,
+    },
+    /// With an OIDC access token, read from stdin when not given.
+    Oidc {
+        #[arg(long)]
+        token: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum DomainsCommand {
+    List {
+        /// `own`, `all` or an organization id.
+        #[arg(long, default_value = "own")]
+        org: String,
+        #[arg(long)]
+        domain_server_id: Option<String>,
+    },
+    Create {
+        name: String,
+        #[arg(long)]
+        domain_server_id: Option<String>,
+        #[arg(long)]
+        domain_server_url: Option<String>,
+        #[arg(long)]
+        redirect_url: Option<String>,
+    },
+    Delete {
+        domain_id: String,
+    },
+}
+
+#[derive(Debug, Args)]
+struct DataFilter {
+    /// Only the item with this id; may be repeated.
+    #[arg(long = "id")]
+    ids: Vec<String>,
+    #[arg(long)]
+    name: Option<String>,
+    #[arg(long)]
+    data_type: Option<String>,
+}
+
+impl From<DataFilter> for DownloadQuery {
+    fn from(filter: DataFilter) -> Self {
+        DownloadQuery {
+            ids: filter.ids,
+            name: filter.name,
+            data_type: filter.data_type,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum DataCommand {
+    /// List the metadata of domain data.
+    List {
+        domain_id: String,
+        #[command(flatten)]
+        filter: DataFilter,
+    },
+    /// Download domain data into `<output>/<data_type>/<name>`.
+    Download {
+        domain_id: String,
+        #[command(flatten)]
+        filter: DataFilter,
+        #[arg(short, long, default_value = ".")]
+        output: PathBuf,
+    },
+    /// Upload files, directories (recursively) or glob patterns as new domain data.
+    Upload {
+        domain_id: String,
+        #[arg(required = true)]
+        paths: Vec<String>,
+        #[arg(long)]
+        data_type: String,
+        /// Name of the uploaded item, when uploading a single file. Defaults to the file name.
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Delete domain data by id, name or data type.
+    Delete {
+        domain_id: String,
+        #[command(flatten)]
+        filter: DataFilter,
+        /// Only show what would be deleted.
+        #[arg(long)]
+        dry_run: bool,
+        #[arg(long, default_value_t = DEFAULT_DELETE_CONCURRENCY)]
+        concurrency: usize,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum JobsCommand {
+    /// Submit a reconstruction job.
+    Submit {
+        domain_id: String,
+        /// Input data id; may be repeated.
+        #[arg(long = "data-id", required = true)]
+        data_ids: Vec<String>,
+        #[arg(long, default_value = "local_and_global_refinement")]
+        processing_type: String,
+        #[arg(long)]
+        server_url: String,
+        #[arg(long)]
+        server_api_key: String,
+        /// Wait until the job has finished.
+        #[arg(long)]
+        watch: bool,
+        /// Seconds between status checks while watching.
+        #[arg(long, default_value_t = 5)]
+        interval: u64,
+    },
+    /// Follow a job until it has finished.
+    Watch {
+        domain_id: String,
+        job_id: String,
+        /// Seconds between status checks.
+        #[arg(long, default_value_t = 5)]
+        interval: u64,
+    },
+}
+
+/// Prints results as text or, with `--json`, as JSON.
+#[derive(Debug, Clone, Copy)]
+struct Output {
+    json: bool,
+}
+
+impl Output {
+    fn print<T: Serialize>(&self, value: &T, text: impl FnOnce(&T) -> String) -> Result<()> {
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(value)?);
+        } else {
+            let text = text(value);
+            if !text.is_empty() {
+                println!("{text}");
+            }
+        }
+        Ok(())
+    }
+
+    /// Prints one line per update, so `--json` output can be read as it arrives.
+    fn event<T: Serialize>(&self, value: &T, text: impl FnOnce(&T) -> String) {
+        if self.json {
+            if let Ok(line) = serde_json::to_string(value) {
+                println!("{line}");
+            }
+        } else {
+            println!("{}", text(value));
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e:#}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let out = Output { json: cli.json };
+    let config = Config::from_env().context("API_URL, DDS_URL and CLIENT_ID must be set")?;
+    let session = SessionDir::locate()?;
+    match cli.command {
+        Command::Login(command) => login(command, &config, &session, out).await,
+        Command::Logout => logout(&config, &session, out).await,
+        Command::Domains(command) => {
+            domains(&connect(&config, &session).await?, command, out).await
+        }
+        Command::Data(command) => data(&connect(&config, &session).await?, command, out).await,
+        Command::Jobs(command) => jobs(&connect(&config, &session).await?, command, out).await,
+    }
+}
+
+fn prompt(label: &str) -> Result<String> {
+    eprint!("{label}: ");
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    let line = line.trim_end_matches(['\r', '\n']).to_string();
+    if line.is_empty() {
+        bail!("No {} given", label.to_lowercase());
+    }
+    Ok(line)
+}
+
+#[derive(Debug, Serialize)]
+struct LoggedIn {
+    method: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    app_key: Option<String>,
+}
+
+async fn login(
+    command: LoginCommand,
+    config: &Config,
+    session: &SessionDir,
+    out: Output,
+) -> Result<()> {
+    let mut client = DomainClient::new(&config.api_url, &config.dds_url, &config.client_id);
+    let login = match command {
+        LoginCommand::User { email } => {
+            let email = match email.or_else(|| config.email.clone()) {
+                Some(email) => email,
+                None => prompt("Email")?,
+            };
+            // Not prompted for, as the terminal would echo it.
+            let password = config
+                .password
+                .clone()
+                .context("Set POSEMESH_PASSWORD to log in with an Auki account")?;
+            client
+                .sign_in_with_user_credential(&email, &password, false)
+                .await?;
+            // Only replace the saved login once the new one works.
+            session.clear()?;
+            client.set_token_store(session.token_store()?).await;
+            Login::User { email }
+        }
+        LoginCommand::App {
+            app_key,
+            app_secret,
+        } => {
+            let app_key = app_key
+                .or_else(|| config.app_key.clone())
+                .context("Pass --app-key or set APP_KEY")?;
+            let app_secret = app_secret
+                .or_else(|| config.app_secret.clone())
+                .context("Pass --app-secret or set APP_SECRET")?;
+            client
+                .sign_in_with_app_credential(&app_key, &app_secret)
+                .await?;
+            session.clear()?;
+            session.save_secret(&app_secret)?;
+            Login::App { app_key }
+        }
+        LoginCommand::Oidc { token } => {
+            let access_token = match token {
+                Some(token) => token,
+                None => prompt("OIDC access token")?,
+            };
+            session.clear()?;
+            session.save_secret(&access_token)?;
+            Login::Oidc
+        }
+    };
+    session.save(&login)?;
+
+    let logged_in = match login {
+        Login::User { email } => LoggedIn {
+            method: "user",
+            email: Some(email),
+            app_key: None,
+        },
+        Login::App { app_key, .. } => LoggedIn {
+            method: "app",
+            email: None,
+            app_key: Some(app_key),
+        },
+        Login::Oidc => LoggedIn {
+            method: "oidc",
+            email: None,
+            app_key: None,
+        },
+    };
+    out.print(&logged_in, |l| match (&l.email, &l.app_key) {
+        (Some(email), _) => format!("Logged in as {email}"),
+        (_, Some(app_key)) => format!("Logged in with app {app_key}"),
+        _ => "Logged in with an OIDC access token".to_string(),
+    })
+}
+
+async fn logout(config: &Config, session: &SessionDir, out: Output) -> Result<()> {
+    if let Some(Login::User { .. }) = session.load()? {
+        let mut client = DomainClient::new(&config.api_url, &config.dds_url, &config.client_id);
+        let revoked = match session.token_store() {
+            Ok(store) => {
+                client.set_token_store(store).await;
+                match client.restore_session().await {
+                    Ok(true) => client.logout().await.map_err(Into::into),
+                    Ok(false) => Ok(()),
+                    Err(e) => Err(e.into()),
+                }
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = revoked {
+            eprintln!("warning: the session could not be revoked: {e}");
+        }
+    }
+    session.clear()?;
+    out.print(&serde_json::json!({ "logged_out": true }), |_| {
+        "Logged out".to_string()
+    })
+}
+
+async fn domains(client: &DomainClient, command: DomainsCommand, out: Output) -> Result<()> {
+    match command {
+        DomainsCommand::List {
+            org,
+            domain_server_id,
+        } => {
+            let query = ListDomainsQuery {
+                portal_id: None,
+                portal_short_id: None,
+                org,
+                domain_server_id,
+            };
+            let domains = client.list_domains(&query).await?.domains;
+            out.print(&domains, |domains| {
+                domains
+                    .iter()
+                    .map(|d| format!("{}\t{}\t{}", d.id, d.name, d.domain_server.url))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+        }
+        DomainsCommand::Create {
+            name,
+            domain_server_id,
+            domain_server_url,
+            redirect_url,
+        } => {
+            let domain = client
+                .create_domain(&name, domain_server_id, domain_server_url, redirect_url)
+                .await?
+                .domain;
+            out.print(&domain, |d| format!("Created domain {} ({})", d.id, d.name))
+        }
+        DomainsCommand::Delete { domain_id } => {
+            client.delete_domain(&domain_id).await?;
+            out.print(&serde_json::json!({ "deleted": domain_id }), |_| {
+                format!("Deleted domain {domain_id}")
+            })
+        }
+    }
+}
+
+fn metadata_rows(metadata: &[DomainDataMetadata]) -> String {
+    metadata
+        .iter()
+        .map(|m| format!("{}\t{}\t{}\t{}", m.id, m.data_type, m.size, m.name))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[derive(Debug, Serialize)]
+struct Downloaded {
+    #[serde(flatten)]
+    metadata: DomainDataMetadata,
+    path: PathBuf,
+}
+
+async fn data(client: &DomainClient, command: DataCommand, out: Output) -> Result<()> {
+    match command {
+        DataCommand::List { domain_id, filter } => {
+            let metadata = client.download_metadata(&domain_id, &filter.into()).await?;
+            out.print(&metadata, |metadata| metadata_rows(metadata))
+        }
+        DataCommand::Download {
+            domain_id,
+            filter,
+            output,
+        } => {
+            let mut items = client
+                .download_domain_data_stream(&domain_id, &filter.into())
+                .await?;
+            let mut taken = HashSet::new();
+            let mut downloaded = Vec::new();
+            while let Some(item) = items.next().await {
+                let item = item?;
+                let path = download_path(&output, &item.metadata, &mut taken);
+                if let Some(parent) = path.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                tokio::fs::write(&path, &item.data)
+                    .await
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                downloaded.push(Downloaded {
+                    metadata: item.metadata,
+                    path,
+                });
+            }
+            out.print(&downloaded, |downloaded| {
+                downloaded
+                    .iter()
+                    .map(|d| format!("{}\t{}", d.metadata.id, d.path.display()))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+        }
+        DataCommand::Upload {
+            domain_id,
+            paths,
+            data_type,
+            name,
+        } => {
+            let files = collect_files(&paths)?;
+            if name.is_some() && files.len() != 1 {
+                bail!("--name needs exactly one file, got {}", files.len());
+            }
+            let mut uploaded = Vec::new();
+            for file in files {
+                let name = match &name {
+                    Some(name) => name.clone(),
+                    None => file
+                        .file_name()
+                        .map(|n| n.to_string_lossy().into_owned())
+                        .context("Files need a name")?,
+                };
+                let action = DomainAction::Create {
+                    name,
+                    data_type: data_type.clone(),
+                };
+                let (size, chunks) = file_chunks(&file).await?;
+                let metadata = client
+                    .upload_domain_data_from_stream(
+                        &domain_id,
+                        action,
+                        Some(size),
+                        chunks,
+                        &TransferControl::new(),
+                    )
+                    .await
+                    .with_context(|| format!("Failed to upload {}", file.display()))?;
+                uploaded.push(metadata);
+            }
+            out.print(&uploaded, |uploaded| metadata_rows(uploaded))
+        }
+        DataCommand::Delete {
+            domain_id,
+            filter,
+            dry_run,
+            concurrency,
+        } => {
+            let options = DeleteOptions {
+                dry_run,
+                concurrency,
+            };
+            let report =
+                if filter.name.is_none() && filter.data_type.is_none() && !filter.ids.is_empty() {
+                    client
+                        .delete_domain_data(&domain_id, &filter.ids, &options)
+                        .await?
+                } else {
+                    client
+                        .delete_domain_data_by_query(&domain_id, &filter.into(), &options)
+                        .await?
+                };
+            out.print(&report, |report| {
+                let verb = if report.dry_run {
+                    "Would delete"
+                } else {
+                    "Deleted"
+                };
+                let mut lines = vec![format!("{verb} {} items", report.deleted.len())];
+                lines.extend(
+                    report
+                        .deleted
+                        .iter()
+                        .map(|m| format!("{}\t{}\t{}", m.id, m.data_type, m.name)),
+                );
+                lines.extend(
+                    report
+                        .failed
+                        .iter()
+                        .map(|f| format!("failed\t{}\t{}", f.metadata.id, f.error)),
+                );
+                lines.extend(report.missing.iter().map(|id| format!("missing\t{id}")));
+                lines.join("\n")
+            })?;
+            if !report.failed.is_empty() {
+                bail!("{} items could not be deleted", report.failed.len());
+            }
+            Ok(())
+        }
+    }
+}
+
+fn job_line(job: &Job) -> String {
+    let status = serde_json::to_value(job.status)
+        .ok()
+        .and_then(|s| s.as_str().map(str::to_string))
+        .unwrap_or_default();
+    match job.progress {
+        Some(progress) => format!("{}\t{status}\t{:.0}%", job.job_id, progress * 100.0),
+        None => format!("{}\t{status}", job.job_id),
+    }
+}
+
+async fn watch_job(
+    client: &DomainClient,
+    domain_id: &str,
+    job_id: &str,
+    interval: u64,
+    out: Output,
+) -> Result<()> {
+    let job = client
+        .wait_for_job(domain_id, job_id, Duration::from_secs(interval), |job| {
+            out.event(job, job_line)
+        })
+        .await?;
+    match job.status {
+        JobStatus::Succeeded => Ok(()),
+        _ => bail!(
+            "Job {} did not succeed{}",
+            job.job_id,
+            job.error.map(|e| format!(": {e}")).unwrap_or_default()
+        ),
+    }
+}
+
+async fn jobs(client: &DomainClient, command: JobsCommand, out: Output) -> Result<()> {
+    match command {
+        JobsCommand::Submit {
+            domain_id,
+            data_ids,
+            processing_type,
+            server_url,
+            server_api_key,
+            watch,
+            interval,
+        } => {
+            let request = JobRequest {
+                data_ids,
+                processing_type,
+                server_api_key,
+                server_url,
+            };
+            let job = client.submit_job_request_v1(&domain_id, &request).await?;
+            if watch {
+                watch_job(client, &domain_id, &job.job_id, interval, out).await
+            } else {
+                out.print(&job, job_line)
+            }
+        }
+        JobsCommand::Watch {
+            domain_id,
+            job_id,
+            interval,
+        } => watch_job(client, &domain_id, &job_id, interval, out).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn parses_commands() {
+        Cli::command().debug_assert();
+
+        let cli = Cli::parse_from([
+            "posemesh",
+            "data",
+            "delete",
+            "dom",
+            "--data-type",
+            "runner_output",
+            "--dry-run",
+            "--json",
+        ]);
+        assert!(cli.json);
+        let Command::Data(DataCommand::Delete {
+            filter, dry_run, ..
+        }) = cli.command
+        else {
+            panic!("parsed {:?}", cli.command);
+        };
+        assert!(dry_run);
+        assert_eq!(filter.data_type.as_deref(), Some("runner_output"));
+    }
+}
@@ -0,0 +1,298 @@
+//! Login state kept between runs.
+//!
+//! `login.json` records how the user logged in. User sessions keep their tokens in an
+//! [`EncryptedFileTokenStore`] next to it, so later runs sign in again from the refresh token
+//! without the password, and app and OIDC logins keep their secret in `login.secret`. Both are
+//! encrypted with the key in `$POSEMESH_SESSION_KEY`, which is never written to disk. All files
+//! are only readable by the owner.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result, bail};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use posemesh_domain_http::config::Config;
+use posemesh_domain_http::domain_client::DomainClient;
+use posemesh_domain_http::token_store::EncryptedFileTokenStore;
+use serde::{Deserialize, Serialize};
+
+const NONCE_LEN: usize = 12;
+const KEY_VAR: &str = "POSEMESH_SESSION_KEY";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Login {
+    User {
+        email: String,
+    },
+    /// The secret is kept encrypted, see [`SessionDir::save_secret`].
+    App {
+        app_key: String,
+    },
+    /// The access token is kept encrypted, see [`SessionDir::save_secret`].
+    Oidc,
+}
+
+#[derive(Clone)]
+pub struct SessionDir {
+    dir: PathBuf,
+    key: Option<[u8; 32]>,
+}
+
+impl SessionDir {
+    /// Session files in `dir`, encrypted with `key`. Without a key, logins cannot be saved or
+    /// used.
+    pub fn new(dir: impl Into<PathBuf>, key: Option<[u8; 32]>) -> Self {
+        Self {
+            dir: dir.into(),
+            key,
+        }
+    }
+
+    /// `$POSEMESH_CONFIG_DIR`, or `posemesh` in the user's config directory, with the key given
+    /// as 64 hex digits in `$POSEMESH_SESSION_KEY`.
+    pub fn locate() -> Result<Self> {
+        let key = match std::env::var(KEY_VAR) {
+            Ok(hex) => Some(parse_key(&hex)?),
+            Err(_) => None,
+        };
+        if let Some(dir) = std::env::var_os("POSEMESH_CONFIG_DIR") {
+            return Ok(Self::new(dir, key));
+        }
+        let config = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+            .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+            .context("No config directory found; set POSEMESH_CONFIG_DIR")?;
+        Ok(Self::new(config.join("posemesh"), key))
+    }
+
+    pub fn load(&self) -> Result<Option<Login>> {
+        let path = self.dir.join("login.json");
+        match std::fs::read(&path) {
+            Ok(bytes) => {
+                Ok(Some(serde_json::from_slice(&bytes).with_context(|| {
+                    format!("Invalid login file {}", path.display())
+                })?))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    pub fn save(&self, login: &Login) -> Result<()> {
+        self.write_private("login.json", &serde_json::to_vec_pretty(login)?)
+    }
+
+    /// Token store for user sessions, encrypted with a key created on first use.
+    pub fn token_store(&self) -> Result<Arc<EncryptedFileTokenStore>> {
+        Ok(Arc::new(EncryptedFileTokenStore::new(
+            self.dir.join("session"),
+            &self.key()?,
+        )))
+    }
+
+    /// Saves the app secret or OIDC access token of a login, encrypted with the session key.
+    pub fn save_secret(&self, secret: &str) -> Result<()> {
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&self.key()?));
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, secret.as_bytes())
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt the login secret"))?;
+        self.write_private("login.secret", &[nonce.as_slice(), &ciphertext].concat())
+    }
+
+    /// The secret saved by [`Self::save_secret`], if any.
+    pub fn secret(&self) -> Result<Option<String>> {
+        let path = self.dir.join("login.secret");
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        let invalid = || anyhow::anyhow!("Cannot decrypt {}", path.display());
+        if bytes.len() < NONCE_LEN {
+            return Err(invalid());
+        }
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&self.key()?));
+        let secret = cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| invalid())?;
+        Ok(Some(String::from_utf8(secret).map_err(|_| invalid())?))
+    }
+
+    /// Removes the login and any saved user session or secret.
+    pub fn clear(&self) -> Result<()> {
+        for name in ["login.json", "session", "login.secret"] {
+            match std::fs::remove_file(self.dir.join(name)) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// The key encrypting saved secrets.
+    fn key(&self) -> Result<[u8; 32]> {
+        self.key.with_context(|| {
+            format!("Set {KEY_VAR} to 64 hex digits (e.g. `openssl rand -hex 32`) to keep logins")
+        })
+    }
+
+    fn write_private(&self, name: &str, bytes: &[u8]) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        let path = self.dir.join(name);
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options
+            .open(&path)
+            .and_then(|mut file| file.write_all(bytes))
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Parses a session key given as 64 hex digits.
+fn parse_key(hex: &str) -> Result<[u8; 32]> {
+    let hex = hex.trim();
+    if hex.len() != 64 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        bail!("{KEY_VAR} must be 64 hex digits");
+    }
+    let mut key = [0; 32];
+    for (i, byte) in key.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16)?;
+    }
+    Ok(key)
+}
+
+/// Returns a client signed in with the saved login, or with credentials from the environment
+/// when there is none.
+pub async fn connect(config: &Config, session: &SessionDir) -> Result<DomainClient> {
+    let mut client = DomainClient::new(&config.api_url, &config.dds_url, &config.client_id);
+    match session.load()? {
+        Some(Login::User { .. }) => {
+            client.set_token_store(session.token_store()?).await;
+            if !client.restore_session().await? {
+                bail!("The saved session has expired; run `posemesh login user` again");
+            }
+        }
+        Some(Login::App { app_key }) => {
+            let app_secret = session
+                .secret()?
+                .context("The saved app secret is missing; run `posemesh login app` again")?;
+            client
+                .sign_in_with_app_credential(&app_key, &app_secret)
+                .await?
+        }
+        Some(Login::Oidc) => {
+            let access_token = session
+                .secret()?
+                .context("The saved access token is missing; run `posemesh login oidc` again")?;
+            client = client.with_oidc_access_token(&access_token);
+        }
+        None => match config {
+            Config {
+                app_key: Some(app_key),
+                app_secret: Some(app_secret),
+                ..
+            } => {
+                client
+                    .sign_in_with_app_credential(app_key, app_secret)
+                    .await?
+            }
+            Config {
+                email: Some(email),
+                password: Some(password),
+                ..
+            } => {
+                client
+                    .sign_in_with_user_credential(email, password, false)
+                    .await?
+            }
+            _ => bail!(
+                "Not logged in; run `posemesh login` or set APP_KEY and APP_SECRET, or POSEMESH_EMAIL and POSEMESH_PASSWORD"
+            ),
+        },
+    }
+    Ok(client)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_login_between_runs() {
+        let dir = tempfile::tempdir().unwrap();
+        let session = SessionDir::new(dir.path().join("posemesh"), Some([7; 32]));
+        assert_eq!(session.load().unwrap(), None);
+
+        let login = Login::App {
+            app_key: "key".into(),
+        };
+        session.save(&login).unwrap();
+        session.save_secret("secret").unwrap();
+        session.token_store().unwrap();
+        let saved = std::fs::read(dir.path().join("posemesh/login.secret")).unwrap();
+        assert!(!saved.windows(6).any(|w| w == b"secret"));
+        let mut files: Vec<_> = std::fs::read_dir(dir.path().join("posemesh"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        files.sort();
+        assert_eq!(files, ["login.json", "login.secret"]);
+
+        let session = SessionDir::new(dir.path().join("posemesh"), Some([7; 32]));
+        assert_eq!(session.load().unwrap(), Some(login));
+        assert_eq!(session.secret().unwrap().as_deref(), Some("secret"));
+        assert!(
+            SessionDir::new(dir.path().join("posemesh"), Some([8; 32]))
+                .secret()
+                .is_err()
+        );
+        assert!(
+            SessionDir::new(dir.path().join("posemesh"), None)
+                .secret()
+                .is_err()
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(dir.path().join("posemesh/login.json"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        session.clear().unwrap();
+        assert_eq!(session.load().unwrap(), None);
+        assert_eq!(session.secret().unwrap(), None);
+    }
+
+    #[test]
+    fn keeps_oidc_token_encrypted() {
+        let dir = tempfile::tempdir().unwrap();
+        let session = SessionDir::new(dir.path(), Some([7; 32]));
+        session.save(&Login::Oidc).unwrap();
+        session.save_secret("oidc-token").unwrap();
+
+        let saved = std::fs::read_to_string(dir.path().join("login.json")).unwrap();
+        assert!(!saved.contains("oidc-token"));
+        assert_eq!(session.load().unwrap(), Some(Login::Oidc));
+        assert_eq!(session.secret().unwrap().as_deref(), Some("oidc-token"));
+    }
+
+    #[test]
+    fn parses_hex_keys() {
+        let hex = "00ff".repeat(16);
+        assert_eq!(parse_key(&hex).unwrap()[..2], [0x00, 0xff]);
+        assert!(parse_key("00ff").is_err());
+        assert!(parse_key(&"+f".repeat(32)).is_err());
+    }
+}
@@ -1,6 +1,7 @@
 use crate::types::LeaseEnvelope;
 use anyhow::Result;
 use async_trait::async_trait;
+use futures::StreamExt;
 use std::path::{Path, PathBuf};
 
 /// Result of materializing a CID, including discovered metadata from the domain server.
@@ -51,6 +52,45 @@ pub trait InputSource: Send + Sync {
         let path = self.materialize_cid_to_temp(cid).await?;
         Ok(MaterializedInput::new(cid, path))
     }
+
+    /// Materialize every CID with at most `concurrency` downloads in flight, reporting
+    /// progress through `ctrl` as inputs land. Results keep the order of `cids`.
+    ///
+    /// The default fans out `materialize_cid_with_meta`; implementations may batch requests.
+    async fn materialize_all(
+        &self,
+        cids: &[String],
+        concurrency: usize,
+        ctrl: &dyn ControlPlane,
+    ) -> Result<Vec<MaterializedInput>> {
+        let total = cids.len();
+        let pending: Vec<_> = cids
+            .iter()
+            .map(|cid| self.materialize_cid_with_meta(cid))
+            .collect();
+        let mut downloads = futures::stream::iter(pending).buffered(concurrency.max(1));
+        let mut inputs = Vec::with_capacity(total);
+        while let Some(input) = downloads.next().await {
+            inputs.push(input?);
+            if ctrl.is_cancelled().await {
+                return Err(anyhow::anyhow!("input prefetch cancelled"));
+            }
+            report_input_progress(ctrl, inputs.len(), total).await?;
+        }
+        Ok(inputs)
+    }
+}
+
+/// Report input prefetch progress as `{"inputs": {"completed": n, "total": m}}`.
+pub async fn report_input_progress(
+    ctrl: &dyn ControlPlane,
+    completed: usize,
+    total: usize,
+) -> Result<()> {
+    ctrl.progress(serde_json::json!({
+        "inputs": { "completed": completed, "total": total }
+    }))
+    .await
 }
 
 /// Destination for task output artifacts.
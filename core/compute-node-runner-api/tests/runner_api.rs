@@ -157,3 +157,34 @@ fn task_spec_priority_allows_negative_values() {
 
     assert_eq!(spec.priority, Some(-3));
 }
+
+#[tokio::test]
+async fn materialize_all_keeps_order_and_reports_progress() {
+    struct RecordingCtrl(std::sync::Mutex<Vec<serde_json::Value>>);
+    #[async_trait::async_trait]
+    impl ControlPlane for RecordingCtrl {
+        async fn is_cancelled(&self) -> bool {
+            false
+        }
+        async fn progress(&self, value: serde_json::Value) -> anyhow::Result<()> {
+            self.0.lock().unwrap().push(value);
+            Ok(())
+        }
+        async fn log_event(&self, _fields: serde_json::Value) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    let cids: Vec<String> = (0..5).map(|i| format!("c{i}")).collect();
+    let ctrl = RecordingCtrl(Default::default());
+    let inputs = DummyInput.materialize_all(&cids, 2, &ctrl).await.unwrap();
+
+    let got: Vec<_> = inputs.iter().map(|m| m.cid.as_str()).collect();
+    assert_eq!(got, ["c0", "c1", "c2", "c3", "c4"]);
+    let progress = ctrl.0.into_inner().unwrap();
+    assert_eq!(progress.len(), 5);
+    assert_eq!(
+        progress.last().unwrap(),
+        &json!({"inputs": {"completed": 5, "total": 5}})
+    );
+}
@@ -0,0 +1,270 @@
+//! Opt-in local admin API for inspecting and steering a running node.
+//!
+//! Enabled by setting `ADMIN_BIND` to a loopback address. The engine publishes the active
+//! task into [`AdminState`] for the duration of a lease; handlers only read that state or
+//! flip the controls it exposes (runner cancellation, leasing pause, token refresh).
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use axum::{extract::State, http::StatusCode, routing::get, routing::post, Json, Router};
+use serde_json::{json, Value};
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::auth::token_manager::TokenProvider;
+use crate::engine::ControlState;
+use crate::session::{SessionManager, SessionSnapshot, SessionStatus};
+use crate::storage::output::UploadedArtifact;
+use crate::storage::Ports;
+
+type UploadLog = Arc<parking_lot::Mutex<HashMap<String, UploadedArtifact>>>;
+
+/// Handles to the lease currently being executed.
+#[derive(Clone)]
+pub struct ActiveTask {
+    session: SessionManager,
+    control: Arc<tokio::sync::Mutex<ControlState>>,
+    runner_cancel: CancellationToken,
+    uploads: UploadLog,
+}
+
+impl ActiveTask {
+    pub fn new(
+        session: SessionManager,
+        control: Arc<tokio::sync::Mutex<ControlState>>,
+        runner_cancel: CancellationToken,
+        ports: &Ports,
+    ) -> Self {
+        Self {
+            session,
+            control,
+            runner_cancel,
+            uploads: ports.uploads(),
+        }
+    }
+}
+
+/// Shared state behind the admin API. Cheap to clone.
+#[derive(Clone, Default)]
+pub struct AdminState {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    paused: AtomicBool,
+    resumed: Notify,
+    active: parking_lot::Mutex<Option<ActiveTask>>,
+    token: parking_lot::Mutex<Option<Arc<dyn TokenProvider>>>,
+}
+
+/// Clears the published task when the engine finishes with the lease.
+pub struct ActiveTaskGuard {
+    state: AdminState,
+}
+
+impl Drop for ActiveTaskGuard {
+    fn drop(&mut self) {
+        self.state.inner.active.lock().take();
+    }
+}
+
+impl AdminState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the node's DDS token provider for expiry reporting and forced refresh.
+    pub fn set_token_provider(&self, provider: Arc<dyn TokenProvider>) {
+        *self.inner.token.lock() = Some(provider);
+    }
+
+    /// Publish the active task until the returned guard is dropped.
+    pub fn begin_task(&self, task: ActiveTask) -> ActiveTaskGuard {
+        *self.inner.active.lock() = Some(task);
+        ActiveTaskGuard {
+            state: self.clone(),
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.inner.paused.load(Ordering::SeqCst)
+    }
+
+    /// Stop leasing new tasks; the current task (if any) keeps running.
+    pub fn pause(&self) {
+        self.inner.paused.store(true, Ordering::SeqCst);
+    }
+
+    pub fn resume(&self) {
+        self.inner.paused.store(false, Ordering::SeqCst);
+        self.inner.resumed.notify_waiters();
+    }
+
+    /// Wait until leasing is resumed. Returns immediately when not paused.
+    pub async fn wait_resumed(&self) {
+        loop {
+            let notified = self.inner.resumed.notified();
+            if !self.is_paused() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Cancel the running task. Returns `false` when no task is active.
+    pub fn cancel_task(&self) -> bool {
+        match self.inner.active.lock().as_ref() {
+            Some(task) => {
+                task.runner_cancel.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Force the token provider to refresh on its next use. Returns `false` when none is set.
+    pub async fn refresh_token(&self) -> bool {
+        let provider = self.inner.token.lock().clone();
+        match provider {
+            Some(provider) => {
+                provider.on_unauthorized().await;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// JSON view of the node for `GET /status`.
+    pub async fn status(&self) -> Value {
+        let provider = self.inner.token.lock().clone();
+        let token_expires_at = match provider {
+            Some(provider) => provider.expires_at().await,
+            None => None,
+        };
+        let registration = match posemesh_node_registration::state::read_state() {
+            Ok(state) => json!({
+                "status": state.status,
+                "last_healthcheck": state.last_healthcheck,
+            }),
+            Err(err) => json!({ "error": err.to_string() }),
+        };
+        let active = self.inner.active.lock().clone();
+        let task = match active {
+            Some(task) => Some(task_status(&task).await),
+            None => None,
+        };
+        json!({
+            "paused": self.is_paused(),
+            "registration": registration,
+            "token_expires_at": token_expires_at,
+            "task": task,
+        })
+    }
+}
+
+async fn task_status(task: &ActiveTask) -> Value {
+    let session = task.session.snapshot().await.map(|s| session_json(&s));
+    let (progress, events, last_heartbeat) = {
+        let control = task.control.lock().await;
+        (
+            control.progress().clone(),
+            control.events().to_vec(),
+            control.last_heartbeat().cloned(),
+        )
+    };
+    let artifacts: Vec<UploadedArtifact> = task.uploads.lock().values().cloned().collect();
+    json!({
+        "session": session,
+        "cancel_requested": task.runner_cancel.is_cancelled(),
+        "progress": progress,
+        "events": events,
+        "last_heartbeat": last_heartbeat,
+        "uploaded_artifacts": artifacts,
+    })
+}
+
+// Hand-built so the lease access token never leaves the process.
+fn session_json(snapshot: &SessionSnapshot) -> Value {
+    let status = match snapshot.status() {
+        SessionStatus::Pending => "pending",
+        SessionStatus::Running => "running",
+    };
+    json!({
+        "task_id": snapshot.task_id(),
+        "job_id": snapshot.job_id(),
+        "capability": snapshot.capability(),
+        "domain_id": snapshot.domain_id(),
+        "domain_server_url": snapshot.domain_server_url().map(|u| u.as_str()),
+        "lease_expires_at": snapshot.lease_expires_at(),
+        "access_token_expires_at": snapshot.access_token_expires_at(),
+        "next_heartbeat_in_ms": snapshot
+            .next_heartbeat_due()
+            .map(|due| due.saturating_duration_since(Instant::now()).as_millis() as u64),
+        "status": status,
+        "cancel": snapshot.cancel(),
+    })
+}
+
+/// Build the admin router.
+pub fn router(state: AdminState) -> Router {
+    Router::new()
+        .route("/status", get(get_status))
+        .route("/task/cancel", post(cancel_task))
+        .route("/leasing/pause", post(pause_leasing))
+        .route("/leasing/resume", post(resume_leasing))
+        .route("/token/refresh", post(refresh_token))
+        .with_state(state)
+}
+
+/// Serve the admin API on `addr` until `shutdown` is cancelled.
+pub async fn serve(addr: SocketAddr, state: AdminState, shutdown: CancellationToken) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("bind admin API on {addr}"))?;
+    info!(addr = %addr, "Admin API listening");
+    axum::serve(listener, router(state))
+        .with_graceful_shutdown(async move { shutdown.cancelled().await })
+        .await
+        .context("admin API server")
+}
+
+async fn get_status(State(state): State<AdminState>) -> Json<Value> {
+    Json(state.status().await)
+}
+
+async fn cancel_task(State(state): State<AdminState>) -> StatusCode {
+    if state.cancel_task() {
+        warn!("Admin API requested cancellation of the active task");
+        StatusCode::ACCEPTED
+    } else {
+        StatusCode::CONFLICT
+    }
+}
+
+async fn pause_leasing(State(state): State<AdminState>) -> Json<Value> {
+    state.pause();
+    info!("Admin API paused leasing");
+    Json(json!({ "paused": true }))
+}
+
+async fn resume_leasing(State(state): State<AdminState>) -> Json<Value> {
+    state.resume();
+    info!("Admin API resumed leasing");
+    Json(json!({ "paused": false }))
+}
+
+async fn refresh_token(State(state): State<AdminState>) -> StatusCode {
+    if state.refresh_token().await {
+        info!("Admin API forced token refresh");
+        StatusCode::ACCEPTED
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
@@ -227,6 +227,10 @@ impl TokenProvider for SiweHandle {
         // Force early refresh on next bearer() call
         self.manager.on_unauthorized_retry().await;
     }
+
+    async fn expires_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.manager.token_expires_at().await
+    }
 }
 
 fn derive_eth_address(priv_hex: &str) -> Result<String> {
@@ -289,6 +293,7 @@ mod tests {
             log_format: crate::config::LogFormat::Json,
             enable_noop: true,
             noop_sleep_secs: 1,
+            admin_bind: None,
         }
     }
 
@@ -95,6 +95,10 @@ pub type TokenProviderResult<T> = std::result::Result<T, TokenProviderError>;
 pub trait TokenProvider: Send + Sync {
     async fn bearer(&self) -> TokenProviderResult<String>;
     async fn on_unauthorized(&self);
+    /// Expiry of the currently cached token, when the provider tracks one.
+    async fn expires_at(&self) -> Option<DateTime<Utc>> {
+        None
+    }
 }
 
 pub struct TokenManager<A: AccessAuthenticator, C: Clock> {
@@ -141,6 +145,10 @@ where
     async fn on_unauthorized(&self) {
         self.on_unauthorized_retry().await;
     }
+
+    async fn expires_at(&self) -> Option<DateTime<Utc>> {
+        self.token_expires_at().await
+    }
 }
 
 struct State {
@@ -152,6 +160,7 @@ struct State {
 struct TokenEntry {
     value: String,
     refresh_at: Instant,
+    expires_at: DateTime<Utc>,
 }
 
 impl TokenEntry {
@@ -239,6 +248,12 @@ where
         }
     }
 
+    /// Expiry reported by DDS for the cached token, if one is held.
+    pub async fn token_expires_at(&self) -> Option<DateTime<Utc>> {
+        let state = self.state.lock().await;
+        state.token.as_ref().map(|entry| entry.expires_at)
+    }
+
     pub async fn clear(&self) {
         let mut state = self.state.lock().await;
         state.token = None;
@@ -394,6 +409,7 @@ where
         Ok(TokenEntry {
             value: bundle.token().to_string(),
             refresh_at,
+            expires_at,
         })
     }
 
@@ -1,6 +1,7 @@
 use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
 use std::env;
+use std::net::SocketAddr;
 use url::Url;
 
 const DEFAULT_DMS_BASE_URL: &str = "https://dms.auki.network/v1";
@@ -48,6 +49,8 @@ pub struct NodeConfig {
     pub log_format: LogFormat,
     pub enable_noop: bool,
     pub noop_sleep_secs: u64,
+    /// Loopback address for the local admin API; disabled when unset.
+    pub admin_bind: Option<SocketAddr>,
 }
 
 impl NodeConfig {
@@ -102,6 +105,7 @@ impl NodeConfig {
         let log_format = parse_log_format("LOG_FORMAT").unwrap_or_default();
         let enable_noop = parse_bool_opt("ENABLE_NOOP", false)?;
         let noop_sleep_secs = parse_u64_opt("NOOP_SLEEP_SECS", 5)?;
+        let admin_bind = parse_loopback_addr_opt("ADMIN_BIND")?;
 
         Ok(Self {
             dms_base_url,
@@ -124,6 +128,7 @@ impl NodeConfig {
             log_format,
             enable_noop,
             noop_sleep_secs,
+            admin_bind,
         })
     }
 }
@@ -209,6 +214,19 @@ fn parse_bool_opt(key: &str, default: bool) -> Result<bool> {
     }
 }
 
+fn parse_loopback_addr_opt(key: &str) -> Result<Option<SocketAddr>> {
+    let Some(value) = env_var_trimmed(key) else {
+        return Ok(None);
+    };
+    let addr: SocketAddr = value
+        .parse()
+        .with_context(|| format!("invalid socket address in {key}"))?;
+    if !addr.ip().is_loopback() {
+        bail!("{key} must be a loopback address, got {addr}");
+    }
+    Ok(Some(addr))
+}
+
 fn parse_log_format(key: &str) -> Option<LogFormat> {
     match env::var(key).ok()?.to_lowercase().as_str() {
         "json" => Some(LogFormat::Json),
@@ -176,6 +176,7 @@ mod tests {
             log_format: LogFormat::Json,
             enable_noop: true,
             noop_sleep_secs: 1,
+            admin_bind: None,
         }
     }
 
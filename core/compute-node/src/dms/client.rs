@@ -45,6 +45,7 @@ impl DmsClient {
             .unwrap_or_else(|_| HeaderValue::from_static("Bearer INVALID"));
         v.set_sensitive(true);
         h.insert(AUTHORIZATION, v);
+        crate::telemetry::inject_trace_context(&mut h);
         Ok(h)
     }
 
@@ -59,6 +60,7 @@ impl DmsClient {
     /// Lease a task: GET /tasks
     ///
     /// `capability` is accepted for optional filter but not implemented yet.
+    #[tracing::instrument(name = "dms.lease", skip_all)]
     pub async fn lease_by_capability(&self, _capability: &str) -> Result<Option<LeaseResponse>> {
         let url = self.join_segments(&["tasks"]).context("join /tasks")?;
         if tracing::enabled!(Level::DEBUG) {
@@ -142,6 +144,7 @@ impl DmsClient {
     }
 
     /// Complete task: POST /tasks/{id}/complete
+    #[tracing::instrument(name = "dms.complete", skip_all, fields(task_id = %task_id))]
     pub async fn complete(&self, task_id: Uuid, body: &CompleteTaskRequest) -> Result<()> {
         let url = self
             .join_segments(&["tasks", &task_id.to_string(), "complete"])
@@ -218,6 +221,7 @@ impl DmsClient {
     }
 
     /// Fail task: POST /tasks/{id}/fail
+    #[tracing::instrument(name = "dms.fail", skip_all, fields(task_id = %task_id))]
     pub async fn fail(&self, task_id: Uuid, body: &FailTaskRequest) -> Result<()> {
         let url = self
             .join_segments(&["tasks", &task_id.to_string(), "fail"])
@@ -295,6 +299,7 @@ impl DmsClient {
 
     /// Heartbeat: POST /tasks/{id}/heartbeat with progress payload.
     /// Returns potential new access token for storage.
+    #[tracing::instrument(name = "dms.heartbeat", skip_all, fields(task_id = %task_id))]
     pub async fn heartbeat(
         &self,
         task_id: Uuid,
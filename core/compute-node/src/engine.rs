@@ -10,14 +10,16 @@ use std::time::{Duration as StdDuration, Instant};
 use tokio::sync::Mutex;
 use tokio::time::sleep;
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, warn, Instrument};
 use uuid::Uuid;
 
 use crate::{
+    admin::{ActiveTask, AdminState},
     dms::client::DmsClient,
     heartbeat::{progress_channel, ProgressReceiver, ProgressSender},
     poller::{jittered_delay_ms, PollerConfig},
     session::{CapabilitySelector, HeartbeatPolicy, SessionManager},
+    storage::ArtifactManifest,
 };
 
 /// Registry mapping capability strings to runner instances.
@@ -94,6 +96,7 @@ pub async fn run_node(cfg: crate::config::NodeConfig, runners: RunnerRegistry) -
 
     shutdown.cancel();
     let _ = signal_task.await;
+    crate::telemetry::flush();
 
     result
 }
@@ -113,11 +116,31 @@ pub async fn run_node_with_shutdown(
         backoff_ms_max: cfg.poll_backoff_ms_max,
     };
 
+    let admin = AdminState::new();
+    admin.set_token_provider(Arc::new(siwe_handle.clone()));
+    let admin_task = cfg.admin_bind.map(|addr| {
+        let admin = admin.clone();
+        let shutdown = shutdown.clone();
+        tokio::spawn(async move {
+            if let Err(err) = crate::admin::serve(addr, admin, shutdown).await {
+                warn!(error = %err, "Admin API stopped");
+            }
+        })
+    });
+
     loop {
         if shutdown.is_cancelled() {
             break;
         }
 
+        if admin.is_paused() {
+            info!("Leasing paused via admin API; waiting for resume");
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = admin.wait_resumed() => continue,
+            }
+        }
+
         // Ensure SIWE token is available before attempting DMS operations
         if let Err(err) = siwe_handle.bearer().await {
             warn!(error = %err, "Failed to obtain SIWE bearer token; backing off");
@@ -145,7 +168,7 @@ pub async fn run_node_with_shutdown(
             }
         };
 
-        match run_cycle_with_dms(&cfg, &dms_client, &runners).await {
+        match run_cycle_with_admin(&cfg, &dms_client, &runners, &admin).await {
             Ok(true) => {
                 // Successful task execution; immediately attempt next poll.
                 continue;
@@ -170,6 +193,9 @@ pub async fn run_node_with_shutdown(
     }
 
     siwe_handle.shutdown().await;
+    if let Some(handle) = admin_task {
+        let _ = handle.await;
+    }
     info!("Shutdown signal received; exiting run_node loop");
 
     Ok(())
@@ -247,9 +273,16 @@ pub async fn run_cycle_with_dms(
     dms: &DmsClient,
     reg: &RunnerRegistry,
 ) -> Result<bool> {
-    use crate::dms::types::{CompleteTaskRequest, FailTaskRequest, HeartbeatRequest};
-    use serde_json::json;
+    run_cycle_with_admin(cfg, dms, reg, &AdminState::new()).await
+}
 
+/// Same as [`run_cycle_with_dms`], publishing the active task into `admin` while it runs.
+pub async fn run_cycle_with_admin(
+    cfg: &crate::config::NodeConfig,
+    dms: &DmsClient,
+    reg: &RunnerRegistry,
+    admin: &AdminState,
+) -> Result<bool> {
     let capabilities = reg.capabilities();
     let capability = capabilities
         .first()
@@ -257,12 +290,38 @@ pub async fn run_cycle_with_dms(
         .ok_or_else(|| anyhow!("no runners registered"))?;
 
     // Lease a task from DMS
-    let mut lease = match dms.lease_by_capability(&capability).await? {
+    let lease = match dms.lease_by_capability(&capability).await? {
         Some(lease) => lease,
         None => {
             return Ok(false);
         }
     };
+
+    let span = crate::telemetry::task_span(
+        lease.task.id,
+        lease.task.job_id.unwrap_or_default(),
+        &lease.task.capability,
+        lease.domain_id.unwrap_or_default(),
+    );
+    run_leased_task(cfg, dms, reg, admin, capabilities, lease)
+        .instrument(span)
+        .await
+}
+
+/// Execute a leased task end to end: session setup, heartbeats, runner dispatch and
+/// completion/failure reporting. Runs inside the task span so DMS/domain calls share a trace.
+async fn run_leased_task(
+    cfg: &crate::config::NodeConfig,
+    dms: &DmsClient,
+    reg: &RunnerRegistry,
+    admin: &AdminState,
+    capabilities: Vec<String>,
+    mut lease: LeaseEnvelope,
+) -> Result<bool> {
+    use crate::dms::types::{CompleteTaskRequest, FailTaskRequest, HeartbeatRequest};
+    use serde_json::json;
+
+    let meter = crate::usage::UsageMeter::start();
     if lease.access_token.is_none() {
         tracing::warn!(
             "Lease missing access token; storage client will fall back to legacy token flow"
@@ -270,7 +329,7 @@ pub async fn run_cycle_with_dms(
     }
 
     // Initialise session state for heartbeats and token rotation.
-    let selector = CapabilitySelector::new(capabilities.clone());
+    let selector = CapabilitySelector::new(capabilities);
     let session = SessionManager::new(selector);
     let policy = HeartbeatPolicy::new(cfg.heartbeat_min_ratio, cfg.heartbeat_max_ratio);
     let mut rng = StdRng::from_entropy();
@@ -385,6 +444,13 @@ pub async fn run_cycle_with_dms(
         control_state.clone(),
     );
 
+    let _active_task = admin.begin_task(ActiveTask::new(
+        session.clone(),
+        control_state.clone(),
+        runner_cancel.clone(),
+        &ports,
+    ));
+
     // Trigger an immediate heartbeat once the loop starts to refresh tokens.
     progress_tx.update(json!({}), Vec::new());
 
@@ -402,7 +468,8 @@ pub async fn run_cycle_with_dms(
             task_id: lease.task.id,
         },
     );
-    let heartbeat_handle = tokio::spawn(async move { heartbeat_driver.run().await });
+    let heartbeat_handle =
+        tokio::spawn(async move { heartbeat_driver.run().await }.in_current_span());
 
     let run_res = reg
         .run_for_lease(&lease, &*ports.input, &*ports.output, &ctrl, &token_ref)
@@ -456,9 +523,12 @@ pub async fn run_cycle_with_dms(
                 "name": artifact.name,
                 "data_type": artifact.data_type,
                 "id": artifact.id,
+                "sha256": artifact.sha256,
+                "size": artifact.size,
             })
         })
         .collect();
+    let manifest = ArtifactManifest::from_artifacts(&uploaded_artifacts);
     let output_cids: Vec<String> = uploaded_artifacts
         .iter()
         .filter_map(|artifact| artifact.id.clone())
@@ -469,6 +539,19 @@ pub async fn run_cycle_with_dms(
         "domain_id": lease.domain_id,
         "capability": lease.task.capability,
     });
+    let usage = meter.finish(ports.transfer_stats());
+    info!(
+        task_id = %lease.task.id,
+        wall_ms = usage.wall_ms,
+        cpu_user_ms = ?usage.cpu_user_ms,
+        cpu_system_ms = ?usage.cpu_system_ms,
+        peak_rss_bytes = ?usage.peak_rss_bytes,
+        bytes_downloaded = usage.bytes_downloaded,
+        bytes_uploaded = usage.bytes_uploaded,
+        billing_units = ?lease.task.billing_units,
+        estimated_credit_cost = ?lease.task.estimated_credit_cost,
+        "Task resource usage"
+    );
 
     // Complete or fail the task depending on runner outcome.
     match run_res {
@@ -478,6 +561,8 @@ pub async fn run_cycle_with_dms(
                 meta: json!({
                     "job": job_info,
                     "artifacts": artifacts_json,
+                    "manifest": manifest,
+                    "usage": usage,
                 }),
             };
             dms.complete(lease.task.id, &body).await?;
@@ -496,6 +581,8 @@ pub async fn run_cycle_with_dms(
                 details: json!({
                     "job": job_info,
                     "artifacts": artifacts_json,
+                    "manifest": manifest,
+                    "usage": usage,
                 }),
             };
             dms.fail(lease.task.id, &body)
@@ -511,6 +598,32 @@ pub async fn run_cycle_with_dms(
 pub struct ControlState {
     progress: Value,
     events: Vec<Value>,
+    last_heartbeat: Option<HeartbeatOutcome>,
+}
+
+impl ControlState {
+    /// Latest progress reported by the runner.
+    pub fn progress(&self) -> &Value {
+        &self.progress
+    }
+
+    /// Events buffered for the next heartbeat.
+    pub fn events(&self) -> &[Value] {
+        &self.events
+    }
+
+    /// Result of the most recent heartbeat attempt.
+    pub fn last_heartbeat(&self) -> Option<&HeartbeatOutcome> {
+        self.last_heartbeat.as_ref()
+    }
+}
+
+/// Outcome of a single heartbeat POST, kept for operator inspection.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct HeartbeatOutcome {
+    pub at: chrono::DateTime<chrono::Utc>,
+    pub ok: bool,
+    pub error: Option<String>,
 }
 
 struct EngineControlPlane {
@@ -714,7 +827,16 @@ where
             events: events.clone(),
         };
 
-        match self.transport.post_heartbeat(self.task_id, &request).await {
+        let result = self.transport.post_heartbeat(self.task_id, &request).await;
+        {
+            let mut state = self.state.lock().await;
+            state.last_heartbeat = Some(HeartbeatOutcome {
+                at: chrono::Utc::now(),
+                ok: result.is_ok(),
+                error: result.as_ref().err().map(|err| err.to_string()),
+            });
+        }
+        match result {
             Ok(update) => {
                 if !events.is_empty() {
                     let mut state = self.state.lock().await;
@@ -44,6 +44,8 @@ pub enum StorageError {
     Server(u16),
     #[error("network error: {0}")]
     Network(String),
+    #[error("integrity check failed: {0}")]
+    Integrity(String),
     #[error("other storage error: {0}")]
     Other(String),
 }
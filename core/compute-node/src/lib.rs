@@ -3,6 +3,7 @@
 /// Public crate identifier used by workspace smoke tests.
 pub const CRATE_NAME: &str = "posemesh-compute-node";
 
+pub mod admin;
 pub mod auth;
 pub mod config;
 pub mod dds;
@@ -15,3 +16,4 @@ pub mod poller;
 pub mod session;
 pub mod storage;
 pub mod telemetry;
+pub mod usage;
@@ -2,10 +2,14 @@ use crate::errors::StorageError;
 use crate::storage::token::TokenRef;
 use anyhow::Result;
 use futures::StreamExt;
+use posemesh_domain_http::transport::Transport;
 use regex::Regex;
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::fs;
 use url::Url;
@@ -79,6 +83,52 @@ struct CompleteMultipartRequestV1 {
 #[derive(Debug, Deserialize)]
 struct DomainDataMetadataV1 {
     id: String,
+    #[serde(default)]
+    hash: Option<String>,
+}
+
+impl From<DomainDataMetadataV1> for UploadReceipt {
+    fn from(meta: DomainDataMetadataV1) -> Self {
+        Self {
+            id: Some(meta.id),
+            hash: meta.hash,
+        }
+    }
+}
+
+/// What the domain server reported back for a completed upload.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UploadReceipt {
+    pub id: Option<String>,
+    /// Content hash, when the server reports it (hex SHA-256, optionally `sha256:`-prefixed).
+    pub hash: Option<String>,
+}
+
+/// Byte counters shared by every clone of a [`DomainClient`].
+#[derive(Clone, Debug, Default)]
+pub struct TransferStats {
+    downloaded: Arc<AtomicU64>,
+    uploaded: Arc<AtomicU64>,
+}
+
+impl TransferStats {
+    /// Payload bytes received from the domain server.
+    pub fn bytes_downloaded(&self) -> u64 {
+        self.downloaded.load(Ordering::Relaxed)
+    }
+
+    /// Payload bytes successfully uploaded to the domain server.
+    pub fn bytes_uploaded(&self) -> u64 {
+        self.uploaded.load(Ordering::Relaxed)
+    }
+
+    fn add_downloaded(&self, bytes: u64) {
+        self.downloaded.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    fn add_uploaded(&self, bytes: u64) {
+        self.uploaded.fetch_add(bytes, Ordering::Relaxed);
+    }
 }
 
 /// Domain server HTTP client (skeleton; HTTP added later).
@@ -87,24 +137,36 @@ pub struct DomainClient {
     pub base: Url,
     pub token: TokenRef,
     client_id: String,
+    transport: Transport,
+    stats: TransferStats,
 }
 impl DomainClient {
     pub fn new(base: Url, token: TokenRef) -> Result<Self> {
-        let client_id = env_client_id();
-        Ok(Self {
-            base,
-            token,
-            client_id,
-        })
+        Ok(Self::with_transport(base, token, Transport::default()))
     }
 
-    pub fn with_timeout(base: Url, token: TokenRef, _timeout: Duration) -> Result<Self> {
-        let client_id = env_client_id();
-        Ok(Self {
+    pub fn with_timeout(base: Url, token: TokenRef, timeout: Duration) -> Result<Self> {
+        let transport = Transport::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| anyhow::anyhow!("build domain transport: {e}"))?;
+        Ok(Self::with_transport(base, token, transport))
+    }
+
+    /// Share `transport` (and its connection pool) for every request to the domain server.
+    pub fn with_transport(base: Url, token: TokenRef, transport: Transport) -> Self {
+        Self {
             base,
             token,
-            client_id,
-        })
+            client_id: env_client_id(),
+            transport,
+            stats: TransferStats::default(),
+        }
+    }
+
+    /// Transfer counters for this client and all of its clones.
+    pub fn stats(&self) -> &TransferStats {
+        &self.stats
     }
 
     /// Download a Domain data item referenced by an absolute URI, persisting each multipart
@@ -149,6 +211,25 @@ impl DomainClient {
             .await
     }
 
+    /// Download several domain data items by ID in a single request. Every returned part
+    /// carries its `id`, so callers can map parts back to the requested IDs.
+    pub async fn download_ids(
+        &self,
+        domain_id: &str,
+        ids: &[String],
+    ) -> std::result::Result<Vec<DownloadedPart>, StorageError> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let query = posemesh_domain_http::domain_data::DownloadQuery {
+            ids: ids.to_vec(),
+            name: None,
+            data_type: None,
+        };
+        self.download_domain_data(&self.base, domain_id, query)
+            .await
+    }
+
     async fn download_domain_data(
         &self,
         url_for_log: &Url,
@@ -173,6 +254,7 @@ impl DomainClient {
 
         let base = self.base.as_str().trim_end_matches('/');
         let mut rx = posemesh_domain_http::domain_data::download_v1_stream(
+            &self.transport,
             base,
             self.client_id.as_str(),
             self.token.get().as_str(),
@@ -192,9 +274,12 @@ impl DomainClient {
             .map_err(|e| StorageError::Other(format!("create datasets root: {}", e)))?;
 
         let mut parts = Vec::new();
+        // Paths taken by earlier items of this download; the root is fresh, so nothing else is.
+        let mut claimed = HashSet::new();
 
         while let Some(item) = rx.next().await {
             let domain_item = item.map_err(map_domain_error)?;
+            self.stats.add_downloaded(domain_item.data.len() as u64);
             let name = domain_item.metadata.name.clone();
             let data_type = domain_item.metadata.data_type.clone();
 
@@ -207,7 +292,13 @@ impl DomainClient {
                 .map_err(|e| StorageError::Other(format!("create scan dir: {}", e)))?;
 
             let file_name = map_filename(&data_type, &name);
-            let file_path = scan_dir.join(&file_name);
+            let mut file_path = scan_dir.join(&file_name);
+            if !claimed.insert(file_path.clone()) {
+                // Batched downloads can return distinct items sharing a name and data type.
+                file_path = scan_dir
+                    .join(sanitize_component(&domain_item.metadata.id))
+                    .join(&file_name);
+            }
             if let Some(parent) = file_path.parent() {
                 fs::create_dir_all(parent)
                     .await
@@ -247,6 +338,28 @@ impl DomainClient {
         &self,
         request: UploadRequest<'_>,
     ) -> std::result::Result<Option<String>, StorageError> {
+        self.upload_artifact_with_receipt(request)
+            .await
+            .map(|receipt| receipt.id)
+    }
+
+    /// Upload bytes and return the server's view of the stored artifact.
+    pub async fn upload_artifact_with_receipt(
+        &self,
+        request: UploadRequest<'_>,
+    ) -> std::result::Result<UploadReceipt, StorageError> {
+        let len = request.bytes.len() as u64;
+        let res = self.upload_artifact_bytes(request).await;
+        if res.is_ok() {
+            self.stats.add_uploaded(len);
+        }
+        res
+    }
+
+    async fn upload_artifact_bytes(
+        &self,
+        request: UploadRequest<'_>,
+    ) -> std::result::Result<UploadReceipt, StorageError> {
         let domain_id = request.domain_id.trim();
         if domain_id.is_empty() {
             return Err(StorageError::Other(
@@ -255,7 +368,9 @@ impl DomainClient {
         }
 
         let base = self.base.as_str().trim_end_matches('/');
-        if let Some(info) = posemesh_domain_http::domain_data::get_upload_info_v1(base).await {
+        if let Some(info) =
+            posemesh_domain_http::domain_data::get_upload_info_v1(&self.transport, base).await
+        {
             if info.multipart_enabled && info.request_max_bytes > 0 {
                 let fits_alone = fits_single_upload_request(
                     info.request_max_bytes,
@@ -302,6 +417,7 @@ impl DomainClient {
         );
 
         let items = posemesh_domain_http::domain_data::upload_v1(
+            &self.transport,
             base,
             self.token.get().as_str(),
             domain_id,
@@ -310,13 +426,30 @@ impl DomainClient {
         .await
         .map_err(map_domain_error)?;
 
-        Ok(items.into_iter().next().map(|d| d.id))
+        Ok(items
+            .into_iter()
+            .next()
+            .map(|d| UploadReceipt {
+                id: Some(d.id),
+                hash: d.hash,
+            })
+            .unwrap_or_default())
     }
 
     pub async fn upload_artifact_file(
         &self,
         request: UploadFileRequest<'_>,
     ) -> std::result::Result<Option<String>, StorageError> {
+        self.upload_artifact_file_with_receipt(request)
+            .await
+            .map(|receipt| receipt.id)
+    }
+
+    /// Upload a file and return the server's view of the stored artifact.
+    pub async fn upload_artifact_file_with_receipt(
+        &self,
+        request: UploadFileRequest<'_>,
+    ) -> std::result::Result<UploadReceipt, StorageError> {
         let domain_id = request.domain_id.trim();
         if domain_id.is_empty() {
             return Err(StorageError::Other(
@@ -344,7 +477,12 @@ impl DomainClient {
             .upload_artifact_v1_multipart_file(base, domain_id, &request, &mut file, file_size)
             .await
         {
-            Ok(v) => Ok(v),
+            Ok(v) => {
+                if let Some(size) = file_size {
+                    self.stats.add_uploaded(size as u64);
+                }
+                Ok(v)
+            }
             Err(UploadFileFallback::UnsupportedEndpoint) => {
                 // Fall back to legacy multipart/form-data upload (in-memory) for older servers.
                 let bytes_owned = fs::read(request.path)
@@ -353,7 +491,7 @@ impl DomainClient {
                 if bytes_owned.is_empty() {
                     return Err(StorageError::BadRequest);
                 }
-                self.upload_artifact(UploadRequest {
+                self.upload_artifact_with_receipt(UploadRequest {
                     domain_id: request.domain_id,
                     name: request.name,
                     data_type: request.data_type,
@@ -372,12 +510,12 @@ impl DomainClient {
         base: &str,
         domain_id: &str,
         request: UploadRequest<'_>,
-    ) -> std::result::Result<Option<String>, StorageError> {
+    ) -> std::result::Result<UploadReceipt, StorageError> {
         if request.bytes.is_empty() {
             return Err(StorageError::BadRequest);
         }
 
-        let client = reqwest::Client::new();
+        let client = self.transport.client().clone();
         let initiate_endpoint = format!(
             "{}/api/v1/domains/{}/data/multipart?uploads",
             base, domain_id
@@ -406,6 +544,7 @@ impl DomainClient {
             .post(&initiate_endpoint)
             .bearer_auth(self.token.get())
             .header("posemesh-client-id", self.client_id.as_str())
+            .headers(trace_context())
             .header("Content-Type", "application/json")
             .json(&init_req)
             .send()
@@ -465,6 +604,7 @@ impl DomainClient {
                     .put(&part_endpoint)
                     .bearer_auth(self.token.get())
                     .header("posemesh-client-id", self.client_id.as_str())
+                    .headers(trace_context())
                     .header("Content-Type", "application/octet-stream")
                     .body(chunk)
                     .send()
@@ -518,6 +658,7 @@ impl DomainClient {
                 .post(&complete_endpoint)
                 .bearer_auth(self.token.get())
                 .header("posemesh-client-id", self.client_id.as_str())
+                .headers(trace_context())
                 .header("Content-Type", "application/json")
                 .json(&CompleteMultipartRequestV1 {
                     parts: completed_parts,
@@ -556,11 +697,12 @@ impl DomainClient {
                 .delete(&abort_endpoint)
                 .bearer_auth(self.token.get())
                 .header("posemesh-client-id", self.client_id.as_str())
+                .headers(trace_context())
                 .send()
                 .await;
         }
 
-        upload_res.map(|meta| Some(meta.id))
+        upload_res.map(UploadReceipt::from)
     }
 
     async fn upload_artifact_v1_multipart_file(
@@ -570,10 +712,10 @@ impl DomainClient {
         request: &UploadFileRequest<'_>,
         file: &mut fs::File,
         file_size: Option<i64>,
-    ) -> std::result::Result<Option<String>, UploadFileFallback> {
+    ) -> std::result::Result<UploadReceipt, UploadFileFallback> {
         use tokio::io::AsyncReadExt;
 
-        let client = reqwest::Client::new();
+        let client = self.transport.client().clone();
         let initiate_endpoint = format!(
             "{}/api/v1/domains/{}/data/multipart?uploads",
             base, domain_id
@@ -602,6 +744,7 @@ impl DomainClient {
             .post(&initiate_endpoint)
             .bearer_auth(self.token.get())
             .header("posemesh-client-id", self.client_id.as_str())
+            .headers(trace_context())
             .header("Content-Type", "application/json")
             .json(&init_req)
             .send()
@@ -682,6 +825,7 @@ impl DomainClient {
                     .put(&part_endpoint)
                     .bearer_auth(self.token.get())
                     .header("posemesh-client-id", self.client_id.as_str())
+                    .headers(trace_context())
                     .header("Content-Type", "application/octet-stream")
                     .body(chunk)
                     .send()
@@ -738,6 +882,7 @@ impl DomainClient {
                 .post(&complete_endpoint)
                 .bearer_auth(self.token.get())
                 .header("posemesh-client-id", self.client_id.as_str())
+                .headers(trace_context())
                 .header("Content-Type", "application/json")
                 .json(&CompleteMultipartRequestV1 {
                     parts: completed_parts,
@@ -776,12 +921,13 @@ impl DomainClient {
                 .delete(&abort_endpoint)
                 .bearer_auth(self.token.get())
                 .header("posemesh-client-id", self.client_id.as_str())
+                .headers(trace_context())
                 .send()
                 .await;
         }
 
         upload_res
-            .map(|meta| Some(meta.id))
+            .map(UploadReceipt::from)
             .map_err(UploadFileFallback::Error)
     }
 
@@ -816,6 +962,7 @@ impl DomainClient {
         );
 
         let results = posemesh_domain_http::domain_data::download_metadata_v1(
+            &self.transport,
             base,
             self.client_id.as_str(),
             self.token.get().as_str(),
@@ -827,10 +974,8 @@ impl DomainClient {
         let results = match results {
             Ok(items) => items,
             Err(err) => {
-                if let posemesh_domain_http::errors::DomainError::AukiErrorResponse(resp) = &err {
-                    if resp.status == reqwest::StatusCode::NOT_FOUND {
-                        return Ok(None);
-                    }
+                if matches!(err, posemesh_domain_http::errors::DomainError::NotFound(_)) {
+                    return Ok(None);
                 }
                 return Err(map_domain_error(err));
             }
@@ -892,14 +1037,23 @@ enum UploadFileFallback {
 fn map_domain_error(err: posemesh_domain_http::errors::DomainError) -> StorageError {
     use posemesh_domain_http::errors::{AuthError, DomainError};
 
+    if let Some(resp) = err.response() {
+        return map_status(resp.status);
+    }
     match err {
-        DomainError::AukiErrorResponse(resp) => map_status(resp.status),
         DomainError::ReqwestError(e) => StorageError::Network(e.to_string()),
         DomainError::AuthError(AuthError::Unauthorized(_)) => StorageError::Unauthorized,
         other => StorageError::Other(other.to_string()),
     }
 }
 
+/// W3C trace-context headers for the current span (empty unless OTLP export is enabled).
+fn trace_context() -> reqwest::header::HeaderMap {
+    let mut headers = reqwest::header::HeaderMap::new();
+    crate::telemetry::inject_trace_context(&mut headers);
+    headers
+}
+
 fn env_client_id() -> String {
     std::env::var("CLIENT_ID")
         .ok()
@@ -955,16 +1109,8 @@ fn parse_download_target(
                     .filter(|s| !s.is_empty())
                     .map(|s| s.to_string()),
             ),
-            "name" => {
-                if name.is_none() {
-                    name = Some(value.to_string());
-                }
-            }
-            "data_type" => {
-                if data_type.is_none() {
-                    data_type = Some(value.to_string());
-                }
-            }
+            "name" if name.is_none() => name = Some(value.to_string()),
+            "data_type" if data_type.is_none() => data_type = Some(value.to_string()),
             _ => {}
         }
     }
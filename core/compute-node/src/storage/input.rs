@@ -1,17 +1,121 @@
-use super::client::DomainClient;
+use super::client::{DomainClient, DownloadedPart};
+use super::manifest::{self, ArtifactManifest};
+use crate::errors::StorageError;
 use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
+use compute_runner_api::runner::report_input_progress;
+use compute_runner_api::{ControlPlane, MaterializedInput};
+use futures::StreamExt;
+use std::collections::HashMap;
+use std::sync::Arc;
 use tokio::fs;
 
+/// Upper bound on IDs sent in one batched download request, keeping the query string short.
+const MAX_IDS_PER_REQUEST: usize = 50;
+
+/// One unit of prefetch work: either a batch of bare IDs or a single URL-style CID.
+enum PrefetchJob<'a> {
+    Ids(Vec<(usize, &'a str)>),
+    Single(usize, &'a str),
+}
+
 /// Domain InputSource implementation (skeleton).
 #[derive(Clone)]
 pub struct DomainInput {
     client: DomainClient,
     domain_id: String,
+    manifest: Option<Arc<ArtifactManifest>>,
 }
 impl DomainInput {
     pub fn new(client: DomainClient, domain_id: String) -> Self {
-        Self { client, domain_id }
+        Self {
+            client,
+            domain_id,
+            manifest: None,
+        }
+    }
+
+    /// Verify downloaded parts whose id appears in `manifest` against its checksums.
+    pub fn with_manifest(mut self, manifest: ArtifactManifest) -> Self {
+        self.manifest = Some(Arc::new(manifest));
+        self
+    }
+
+    async fn verify(&self, part: &DownloadedPart) -> Result<()> {
+        let (Some(manifest), Some(id)) = (self.manifest.as_deref(), part.id.as_deref()) else {
+            return Ok(());
+        };
+        let Some(entry) = manifest.entry_for_id(id) else {
+            return Ok(());
+        };
+        let (sha256, size) = manifest::sha256_file(&part.path).await?;
+        if size != entry.size || !manifest::hash_matches(&sha256, &entry.sha256) {
+            return Err(StorageError::Integrity(format!(
+                "input {id} ({}): expected sha256 {} ({} bytes), got {sha256} ({size} bytes)",
+                entry.logical_path, entry.sha256, entry.size
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
+    async fn run_prefetch_job(
+        &self,
+        job: PrefetchJob<'_>,
+    ) -> Result<Vec<(usize, MaterializedInput)>> {
+        use compute_runner_api::InputSource;
+
+        match job {
+            PrefetchJob::Single(idx, cid) => {
+                Ok(vec![(idx, self.materialize_cid_with_meta(cid).await?)])
+            }
+            PrefetchJob::Ids(batch) => {
+                let ids: Vec<String> = batch.iter().map(|(_, id)| id.to_string()).collect();
+                let parts = self
+                    .client
+                    .download_ids(&self.domain_id, &ids)
+                    .await
+                    .map_err(|e| anyhow!(e))?;
+                let mut by_id: HashMap<String, Vec<DownloadedPart>> = HashMap::new();
+                for part in parts {
+                    self.verify(&part).await?;
+                    if let Some(id) = part.id.clone() {
+                        by_id.entry(id).or_default().push(part);
+                    }
+                }
+                batch
+                    .into_iter()
+                    .map(|(idx, id)| {
+                        let parts = by_id
+                            .get(id)
+                            .cloned()
+                            .ok_or_else(|| anyhow!("domain response missing data for {}", id))?;
+                        Ok((idx, materialized(id, parts)))
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+fn is_bare_id(cid: &str) -> bool {
+    !(cid.contains("://") || cid.starts_with('/'))
+}
+
+/// First part is the primary input; any further parts become related files.
+fn materialized(cid: &str, mut parts: Vec<DownloadedPart>) -> MaterializedInput {
+    let primary = parts.remove(0);
+    let related_files = parts.into_iter().map(|p| p.path).collect();
+    MaterializedInput {
+        cid: cid.to_string(),
+        path: primary.path,
+        data_id: primary.id,
+        name: primary.name,
+        data_type: primary.data_type,
+        domain_id: primary.domain_id,
+        root_dir: primary.root,
+        related_files,
+        extracted_paths: primary.extracted_paths,
     }
 }
 
@@ -39,7 +143,7 @@ impl compute_runner_api::InputSource for DomainInput {
         &self,
         cid: &str,
     ) -> Result<compute_runner_api::MaterializedInput> {
-        let mut parts = self
+        let parts = self
             .client
             .download_cid(&self.domain_id, cid)
             .await
@@ -47,21 +151,63 @@ impl compute_runner_api::InputSource for DomainInput {
         if parts.is_empty() {
             return Err(anyhow!("domain response missing data for {}", cid));
         }
+        for part in &parts {
+            self.verify(part).await?;
+        }
         // Choose the first part as primary. Runners can interpret
         // additional parts or data_type as needed.
-        let primary = parts.remove(0);
-        let related_files = parts.into_iter().map(|p| p.path).collect();
+        Ok(materialized(cid, parts))
+    }
+
+    /// Bare IDs are grouped into batched `DownloadQuery` requests; URL-style CIDs are
+    /// fetched individually. Batches and single downloads share the concurrency limit.
+    async fn materialize_all(
+        &self,
+        cids: &[String],
+        concurrency: usize,
+        ctrl: &dyn ControlPlane,
+    ) -> Result<Vec<MaterializedInput>> {
+        let concurrency = concurrency.max(1);
+        let total = cids.len();
+        let mut ids = Vec::new();
+        let mut jobs = Vec::new();
+        for (idx, cid) in cids.iter().enumerate() {
+            let cid = cid.trim();
+            if cid.is_empty() {
+                return Err(anyhow!("empty cid at position {}", idx));
+            }
+            if is_bare_id(cid) {
+                ids.push((idx, cid));
+            } else {
+                jobs.push(PrefetchJob::Single(idx, cid));
+            }
+        }
+        // Spread IDs over the available slots before capping the batch size.
+        let batch_size = ids
+            .len()
+            .div_ceil(concurrency)
+            .clamp(1, MAX_IDS_PER_REQUEST);
+        for chunk in ids.chunks(batch_size) {
+            jobs.push(PrefetchJob::Ids(chunk.to_vec()));
+        }
 
-        Ok(compute_runner_api::MaterializedInput {
-            cid: cid.to_string(),
-            path: primary.path,
-            data_id: primary.id,
-            name: primary.name,
-            data_type: primary.data_type,
-            domain_id: primary.domain_id,
-            root_dir: primary.root,
-            related_files,
-            extracted_paths: primary.extracted_paths,
-        })
+        let pending: Vec<_> = jobs
+            .into_iter()
+            .map(|job| self.run_prefetch_job(job))
+            .collect();
+        let mut downloads = futures::stream::iter(pending).buffer_unordered(concurrency);
+        let mut slots: Vec<Option<MaterializedInput>> = vec![None; total];
+        let mut completed = 0;
+        while let Some(batch) = downloads.next().await {
+            for (idx, input) in batch? {
+                slots[idx] = Some(input);
+                completed += 1;
+            }
+            if ctrl.is_cancelled().await {
+                return Err(anyhow!("input prefetch cancelled"));
+            }
+            report_input_progress(ctrl, completed, total).await?;
+        }
+        Ok(slots.into_iter().flatten().collect())
     }
 }
@@ -0,0 +1,154 @@
+//! SHA-256 artifact manifest published with task completion and used to verify inputs.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use tokio::io::AsyncReadExt;
+
+use super::output::UploadedArtifact;
+
+pub const MANIFEST_ALGORITHM: &str = "sha256";
+
+/// Checksums for a set of domain artifacts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactManifest {
+    pub algorithm: String,
+    pub entries: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub logical_path: String,
+    pub id: Option<String>,
+    pub name: String,
+    pub data_type: String,
+    pub sha256: String,
+    pub size: u64,
+}
+
+impl ArtifactManifest {
+    /// Build a manifest from uploads that carry a checksum; seeded records without one are skipped.
+    pub fn from_artifacts(artifacts: &[UploadedArtifact]) -> Self {
+        let mut entries: Vec<ManifestEntry> = artifacts
+            .iter()
+            .filter_map(|artifact| {
+                Some(ManifestEntry {
+                    logical_path: artifact.logical_path.clone(),
+                    id: artifact.id.clone(),
+                    name: artifact.name.clone(),
+                    data_type: artifact.data_type.clone(),
+                    sha256: artifact.sha256.clone()?,
+                    size: artifact.size?,
+                })
+            })
+            .collect();
+        entries.sort_by(|a, b| a.logical_path.cmp(&b.logical_path));
+        Self {
+            algorithm: MANIFEST_ALGORITHM.to_string(),
+            entries,
+        }
+    }
+
+    /// Look up the entry for a domain data id.
+    pub fn entry_for_id(&self, id: &str) -> Option<&ManifestEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.id.as_deref() == Some(id))
+    }
+}
+
+/// Hex-encoded SHA-256 of `bytes`.
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+/// Hex-encoded SHA-256 and byte length of the file at `path`, read in chunks.
+pub async fn sha256_file(path: &Path) -> Result<(String, u64)> {
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .with_context(|| format!("open {} for hashing", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let n = file
+            .read(&mut buf)
+            .await
+            .with_context(|| format!("read {} for hashing", path.display()))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        size += n as u64;
+    }
+    Ok((hex::encode(hasher.finalize()), size))
+}
+
+/// Compare a locally computed hex digest with a server-reported hash
+/// (case-insensitive, tolerating a `sha256:` prefix).
+pub fn hash_matches(local_hex: &str, reported: &str) -> bool {
+    let reported = reported.trim();
+    let reported = reported
+        .strip_prefix("sha256:")
+        .or_else(|| reported.strip_prefix("SHA256:"))
+        .unwrap_or(reported);
+    reported.eq_ignore_ascii_case(local_hex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn artifact(path: &str, sha: Option<&str>) -> UploadedArtifact {
+        UploadedArtifact {
+            logical_path: path.into(),
+            name: format!("{path}_name"),
+            data_type: "json".into(),
+            id: Some(format!("{path}-id")),
+            sha256: sha.map(str::to_string),
+            size: sha.map(|_| 3),
+        }
+    }
+
+    #[test]
+    fn sha256_hex_known_vector() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn manifest_skips_unhashed_and_sorts() {
+        let manifest = ArtifactManifest::from_artifacts(&[
+            artifact("b", Some("bb")),
+            artifact("seeded", None),
+            artifact("a", Some("aa")),
+        ]);
+        let paths: Vec<_> = manifest
+            .entries
+            .iter()
+            .map(|e| e.logical_path.as_str())
+            .collect();
+        assert_eq!(paths, ["a", "b"]);
+        assert_eq!(manifest.entry_for_id("b-id").unwrap().sha256, "bb");
+    }
+
+    #[test]
+    fn hash_matches_accepts_prefix_and_case() {
+        assert!(hash_matches("abcd", "sha256:ABCD"));
+        assert!(hash_matches("abcd", "abcd"));
+        assert!(!hash_matches("abcd", "abce"));
+    }
+
+    #[tokio::test]
+    async fn sha256_file_matches_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("f.bin");
+        tokio::fs::write(&path, b"abc").await.unwrap();
+        let (hash, size) = sha256_file(&path).await.unwrap();
+        assert_eq!(hash, sha256_hex(b"abc"));
+        assert_eq!(size, 3);
+    }
+}
@@ -7,9 +7,12 @@ use std::{collections::HashMap, sync::Arc};
 
 pub mod client;
 pub mod input;
+pub mod manifest;
 pub mod output;
 pub mod token;
 
+pub use client::TransferStats;
+pub use manifest::ArtifactManifest;
 use output::{DomainOutput, UploadedArtifact};
 pub use token::TokenRef;
 
@@ -18,13 +21,24 @@ pub struct Ports {
     pub input: Box<dyn compute_runner_api::InputSource>,
     pub output: Box<dyn compute_runner_api::ArtifactSink>,
     uploads: Arc<Mutex<HashMap<String, UploadedArtifact>>>,
+    stats: TransferStats,
 }
 
 impl Ports {
+    /// Bytes moved through these ports so far.
+    pub fn transfer_stats(&self) -> &TransferStats {
+        &self.stats
+    }
+
     pub fn uploaded_artifacts(&self) -> Vec<UploadedArtifact> {
         let guard = self.uploads.lock();
         guard.values().cloned().collect()
     }
+
+    /// Shared handle to the upload log so other components can observe uploads as they happen.
+    pub(crate) fn uploads(&self) -> Arc<Mutex<HashMap<String, UploadedArtifact>>> {
+        Arc::clone(&self.uploads)
+    }
 }
 /// Build storage ports from a lease and a TokenRef.
 pub fn build_ports(lease: &LeaseEnvelope, token: TokenRef) -> Result<Ports> {
@@ -45,6 +59,14 @@ pub fn build_ports(lease: &LeaseEnvelope, token: TokenRef) -> Result<Ports> {
         .ok_or_else(|| anyhow!("lease missing domain_id"))?;
     let task_id = lease.task.id.to_string();
 
+    let input_manifest = match lease.task.meta.get("input_manifest") {
+        Some(value) if !value.is_null() => Some(
+            serde_json::from_value::<ArtifactManifest>(value.clone())
+                .map_err(|e| anyhow!("invalid input_manifest in task meta: {e}"))?,
+        ),
+        _ => None,
+    };
+
     let client = client::DomainClient::new(base, token)?;
     let uploads = Arc::new(Mutex::new(HashMap::new()));
     let output = DomainOutput::with_store(
@@ -54,9 +76,14 @@ pub fn build_ports(lease: &LeaseEnvelope, token: TokenRef) -> Result<Ports> {
         task_id,
         Arc::clone(&uploads),
     );
+    let mut input = input::DomainInput::new(client.clone(), domain_id);
+    if let Some(manifest) = input_manifest {
+        input = input.with_manifest(manifest);
+    }
     Ok(Ports {
-        input: Box::new(input::DomainInput::new(client.clone(), domain_id)),
+        input: Box::new(input),
         output: Box::new(output),
         uploads,
+        stats: client.stats().clone(),
     })
 }
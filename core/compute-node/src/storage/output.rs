@@ -1,4 +1,6 @@
-use super::client::{DomainClient, UploadFileRequest, UploadRequest};
+use super::client::{DomainClient, UploadFileRequest, UploadReceipt, UploadRequest};
+use super::manifest;
+use crate::errors::StorageError;
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use parking_lot::Mutex;
@@ -21,6 +23,10 @@ pub struct UploadedArtifact {
     pub name: String,
     pub data_type: String,
     pub id: Option<String>,
+    /// Hex SHA-256 of the uploaded content; `None` for seeded records.
+    pub sha256: Option<String>,
+    /// Uploaded size in bytes; `None` for seeded records.
+    pub size: Option<u64>,
 }
 
 /// Domain ArtifactSink implementation (skeleton).
@@ -150,8 +156,9 @@ impl compute_runner_api::ArtifactSink for DomainOutput {
                 .map_err(|e| anyhow!(e))?;
         }
 
-        let maybe_id = match request.content {
+        let (receipt, sha256, size) = match request.content {
             DomainArtifactContent::Bytes(bytes) => {
+                let sha256 = manifest::sha256_hex(bytes);
                 let upload_req = UploadRequest {
                     domain_id: &self.domain_id,
                     name: request.name,
@@ -160,12 +167,15 @@ impl compute_runner_api::ArtifactSink for DomainOutput {
                     bytes,
                     existing_id: existing_id.as_deref(),
                 };
-                self.client
-                    .upload_artifact(upload_req)
+                let receipt = self
+                    .client
+                    .upload_artifact_with_receipt(upload_req)
                     .await
-                    .map_err(|e| anyhow!(e))?
+                    .map_err(|e| anyhow!(e))?;
+                (receipt, sha256, bytes.len() as u64)
             }
             DomainArtifactContent::File(path) => {
+                let (sha256, size) = manifest::sha256_file(path).await?;
                 let upload_req = UploadFileRequest {
                     domain_id: &self.domain_id,
                     name: request.name,
@@ -174,13 +184,16 @@ impl compute_runner_api::ArtifactSink for DomainOutput {
                     path,
                     existing_id: existing_id.as_deref(),
                 };
-                self.client
-                    .upload_artifact_file(upload_req)
+                let receipt = self
+                    .client
+                    .upload_artifact_file_with_receipt(upload_req)
                     .await
-                    .map_err(|e| anyhow!(e))?
+                    .map_err(|e| anyhow!(e))?;
+                (receipt, sha256, size)
             }
         };
-        let final_id = maybe_id.or(existing_id);
+        verify_receipt(&logical_path, &receipt, &sha256)?;
+        let final_id = receipt.id.or(existing_id);
 
         let mut uploads = self.uploads.lock();
         uploads.insert(
@@ -190,6 +203,8 @@ impl compute_runner_api::ArtifactSink for DomainOutput {
                 name: request.name.to_string(),
                 data_type: request.data_type.to_string(),
                 id: final_id.clone(),
+                sha256: Some(sha256),
+                size: Some(size),
             },
         );
 
@@ -213,11 +228,29 @@ impl DomainOutput {
                 name: descriptor.name,
                 data_type: descriptor.data_type,
                 id: Some(id.into()),
+                sha256: None,
+                size: None,
             },
         );
     }
 }
 
+/// Fail the upload when the server reports a content hash that disagrees with what was sent.
+fn verify_receipt(
+    logical_path: &str,
+    receipt: &UploadReceipt,
+    sha256: &str,
+) -> Result<(), StorageError> {
+    if let Some(reported) = receipt.hash.as_deref() {
+        if !manifest::hash_matches(sha256, reported) {
+            return Err(StorageError::Integrity(format!(
+                "{logical_path}: server hash {reported} does not match local sha256 {sha256}"
+            )));
+        }
+    }
+    Ok(())
+}
+
 fn infer_data_type(rel_path: &str) -> String {
     let ext = Path::new(rel_path)
         .extension()
@@ -1,35 +1,138 @@
 use crate::config::LogFormat;
-use std::sync::Once;
+use anyhow::Result;
+use std::sync::{Mutex, Once};
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
 static INIT: Once = Once::new();
 
+const DEFAULT_SERVICE_NAME: &str = "posemesh-compute-node";
+
+/// OTLP trace export settings. Only honoured when the crate is built with the `otel` feature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OtlpConfig {
+    /// Collector base URL (e.g. `http://localhost:4318`); spans are posted to `{endpoint}/v1/traces`.
+    pub endpoint: String,
+    /// Value reported as the `service.name` resource attribute.
+    pub service_name: String,
+}
+
+impl OtlpConfig {
+    /// Read `OTEL_EXPORTER_OTLP_ENDPOINT` and `OTEL_SERVICE_NAME`. Returns `None` when no endpoint is set.
+    pub fn from_env() -> Option<Self> {
+        let endpoint = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())?;
+        let service_name = std::env::var("OTEL_SERVICE_NAME")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| DEFAULT_SERVICE_NAME.to_string());
+        Some(Self {
+            endpoint,
+            service_name,
+        })
+    }
+
+    /// Full URL of the OTLP/HTTP traces endpoint.
+    pub fn traces_endpoint(&self) -> String {
+        format!("{}/v1/traces", self.endpoint.trim_end_matches('/'))
+    }
+}
+
 /// Initialize global tracing subscriber with the given log format.
 /// Safe to call multiple times; only the first call installs a subscriber.
 pub fn init_with_format(fmt_mode: LogFormat) -> anyhow::Result<()> {
+    init_with_otlp(fmt_mode, None)
+}
+
+/// Initialize global tracing subscriber and, when `otlp` is set, export spans to an OTLP
+/// collector and propagate W3C trace context on outgoing DMS and domain requests.
+/// Safe to call multiple times; only the first call installs a subscriber.
+pub fn init_with_otlp(fmt_mode: LogFormat, otlp: Option<OtlpConfig>) -> anyhow::Result<()> {
+    let result = Mutex::new(Ok(()));
     INIT.call_once(|| {
-        let env_filter =
-            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
-        let fmt_layer = match fmt_mode {
-            LogFormat::Json => fmt::layer().json().boxed(),
-            LogFormat::Text => fmt::layer().boxed(),
+        *result.lock().unwrap() = install(fmt_mode, otlp);
+    });
+    result.into_inner().unwrap()
+}
+
+fn install(fmt_mode: LogFormat, otlp: Option<OtlpConfig>) -> Result<()> {
+    let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let fmt_layer = match fmt_mode {
+        LogFormat::Json => fmt::layer().json().boxed(),
+        LogFormat::Text => fmt::layer().boxed(),
+    };
+
+    #[cfg(feature = "otel")]
+    {
+        let otel_layer = match otlp.as_ref() {
+            Some(cfg) => Some(tracing_opentelemetry::layer().with_tracer(otel::install(cfg)?)),
+            None => None,
         };
+        tracing_subscriber::registry()
+            .with(env_filter)
+            .with(fmt_layer)
+            .with(otel_layer)
+            .init();
+    }
 
+    #[cfg(not(feature = "otel"))]
+    {
         tracing_subscriber::registry()
             .with(env_filter)
             .with(fmt_layer)
             .init();
-    });
+        if let Some(cfg) = otlp {
+            tracing::warn!(
+                endpoint = %cfg.endpoint,
+                "OTLP endpoint configured but the `otel` feature is disabled; spans will not be exported"
+            );
+        }
+    }
+
     Ok(())
 }
 
-/// Initialize tracing using `LOG_FORMAT` env var ("json" or "text", default json).
+/// Initialize tracing using `LOG_FORMAT` env var ("json" or "text", default json) and
+/// `OTEL_EXPORTER_OTLP_ENDPOINT` / `OTEL_SERVICE_NAME` for optional OTLP export.
 pub fn init_from_env() -> anyhow::Result<()> {
     let mode = match std::env::var("LOG_FORMAT").ok().as_deref() {
         Some("text") => LogFormat::Text,
         _ => LogFormat::Json,
     };
-    init_with_format(mode)
+    init_with_otlp(mode, OtlpConfig::from_env())
+}
+
+/// Flush spans buffered by the OTLP exporter. No-op when export is not configured.
+pub fn flush() {
+    #[cfg(feature = "otel")]
+    otel::flush();
+}
+
+/// W3C trace-context headers (`traceparent`, optionally `tracestate`) for the current span.
+/// Empty unless the `otel` feature is enabled and OTLP export has been initialized.
+pub fn trace_context_headers() -> Vec<(String, String)> {
+    #[cfg(feature = "otel")]
+    {
+        otel::current_headers()
+    }
+    #[cfg(not(feature = "otel"))]
+    {
+        Vec::new()
+    }
+}
+
+/// Insert the current span's W3C trace-context headers into an outgoing request.
+pub fn inject_trace_context(headers: &mut reqwest::header::HeaderMap) {
+    for (key, value) in trace_context_headers() {
+        if let (Ok(name), Ok(value)) = (
+            reqwest::header::HeaderName::from_bytes(key.as_bytes()),
+            reqwest::header::HeaderValue::from_str(&value),
+        ) {
+            headers.insert(name, value);
+        }
+    }
 }
 
 /// Create a span for a task with common fields as per the spec.
@@ -48,6 +151,65 @@ pub fn task_span(
     )
 }
 
+#[cfg(feature = "otel")]
+mod otel {
+    use super::OtlpConfig;
+    use anyhow::{Context, Result};
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use opentelemetry_sdk::trace::{SdkTracerProvider, Tracer};
+    use opentelemetry_sdk::Resource;
+    use std::collections::HashMap;
+    use std::sync::OnceLock;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    static PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+
+    /// Build the batch OTLP/HTTP exporter, register the W3C propagator, and hook trace
+    /// context into `posemesh-domain-http` requests.
+    pub(super) fn install(cfg: &OtlpConfig) -> Result<Tracer> {
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_http()
+            .with_endpoint(cfg.traces_endpoint())
+            .build()
+            .context("build OTLP span exporter")?;
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(
+                Resource::builder()
+                    .with_service_name(cfg.service_name.clone())
+                    .build(),
+            )
+            .build();
+        let tracer = provider.tracer(super::DEFAULT_SERVICE_NAME);
+
+        opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+        opentelemetry::global::set_tracer_provider(provider.clone());
+        let _ = PROVIDER.set(provider);
+        posemesh_domain_http::propagation::set_header_injector(current_headers);
+
+        Ok(tracer)
+    }
+
+    pub(super) fn current_headers() -> Vec<(String, String)> {
+        let cx = tracing::Span::current().context();
+        let mut carrier: HashMap<String, String> = HashMap::new();
+        opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.inject_context(&cx, &mut carrier)
+        });
+        carrier.into_iter().collect()
+    }
+
+    pub(super) fn flush() {
+        if let Some(provider) = PROVIDER.get() {
+            if let Err(err) = provider.force_flush() {
+                tracing::warn!(error = %err, "failed to flush OTLP spans");
+            }
+        }
+    }
+}
+
 #[cfg(feature = "metrics")]
 pub mod metrics {
     /// Metric names as per §10 Telemetry.
@@ -0,0 +1,126 @@
+//! Per-task resource accounting attached to DMS completion/failure payloads.
+//!
+//! Wall time is always measured. CPU time and peak RSS come from `getrusage(RUSAGE_CHILDREN)`
+//! and therefore only cover child processes the runner spawned and waited for; in-process
+//! runners report `None` for those fields.
+
+use serde::Serialize;
+use std::time::Instant;
+
+use crate::storage::TransferStats;
+
+/// Resource usage of a single task.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ResourceUsage {
+    pub wall_ms: u64,
+    /// User CPU time consumed by reaped child processes during the task.
+    pub cpu_user_ms: Option<u64>,
+    /// System CPU time consumed by reaped child processes during the task.
+    pub cpu_system_ms: Option<u64>,
+    /// Largest resident set size of any reaped child process. The OS tracks this per
+    /// process lifetime, so it is an upper bound when earlier tasks spawned larger children.
+    pub peak_rss_bytes: Option<u64>,
+    pub bytes_downloaded: u64,
+    pub bytes_uploaded: u64,
+}
+
+/// Started at lease time and finished once the runner returns.
+#[derive(Debug)]
+pub struct UsageMeter {
+    started: Instant,
+    children: Option<ChildUsage>,
+}
+
+impl UsageMeter {
+    pub fn start() -> Self {
+        Self {
+            started: Instant::now(),
+            children: ChildUsage::current(),
+        }
+    }
+
+    /// Snapshot usage since [`UsageMeter::start`], including storage transfer counters.
+    pub fn finish(&self, transfer: &TransferStats) -> ResourceUsage {
+        let mut usage = ResourceUsage {
+            wall_ms: self.started.elapsed().as_millis() as u64,
+            bytes_downloaded: transfer.bytes_downloaded(),
+            bytes_uploaded: transfer.bytes_uploaded(),
+            ..ResourceUsage::default()
+        };
+        if let (Some(before), Some(after)) = (self.children, ChildUsage::current()) {
+            let user_ms = after.user_ms.saturating_sub(before.user_ms);
+            let system_ms = after.system_ms.saturating_sub(before.system_ms);
+            // No child CPU since start means no process-based work to attribute.
+            if user_ms > 0 || system_ms > 0 || after.max_rss_bytes != before.max_rss_bytes {
+                usage.cpu_user_ms = Some(user_ms);
+                usage.cpu_system_ms = Some(system_ms);
+                usage.peak_rss_bytes = Some(after.max_rss_bytes);
+            }
+        }
+        usage
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ChildUsage {
+    user_ms: u64,
+    system_ms: u64,
+    max_rss_bytes: u64,
+}
+
+impl ChildUsage {
+    #[cfg(unix)]
+    fn current() -> Option<Self> {
+        let mut raw = std::mem::MaybeUninit::<libc::rusage>::zeroed();
+        // SAFETY: `raw` is a valid, writable rusage buffer for the duration of the call.
+        let rc = unsafe { libc::getrusage(libc::RUSAGE_CHILDREN, raw.as_mut_ptr()) };
+        if rc != 0 {
+            return None;
+        }
+        // SAFETY: getrusage returned success and fully initialised the struct.
+        let raw = unsafe { raw.assume_init() };
+        let to_ms = |tv: libc::timeval| tv.tv_sec as u64 * 1_000 + tv.tv_usec as u64 / 1_000;
+        // Linux reports ru_maxrss in KiB, macOS in bytes.
+        let rss_scale = if cfg!(target_os = "macos") { 1 } else { 1024 };
+        Some(Self {
+            user_ms: to_ms(raw.ru_utime),
+            system_ms: to_ms(raw.ru_stime),
+            max_rss_bytes: (raw.ru_maxrss.max(0) as u64) * rss_scale,
+        })
+    }
+
+    #[cfg(not(unix))]
+    fn current() -> Option<Self> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finish_reports_wall_time_and_transfer() {
+        let meter = UsageMeter::start();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let usage = meter.finish(&TransferStats::default());
+        assert!(usage.wall_ms >= 5);
+        assert_eq!(usage.bytes_downloaded, 0);
+        assert_eq!(usage.bytes_uploaded, 0);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn finish_attributes_child_process_usage() {
+        let meter = UsageMeter::start();
+        let status = std::process::Command::new("sh")
+            .arg("-c")
+            .arg("i=0; while [ $i -lt 200000 ]; do i=$((i+1)); done")
+            .status()
+            .expect("spawn sh");
+        assert!(status.success());
+        let usage = meter.finish(&TransferStats::default());
+        assert!(usage.peak_rss_bytes.unwrap_or(0) > 0);
+        assert!(usage.cpu_user_ms.is_some());
+    }
+}
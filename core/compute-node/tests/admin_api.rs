@@ -0,0 +1,158 @@
+use async_trait::async_trait;
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use compute_runner_api::LeaseEnvelope;
+use posemesh_compute_node::admin::{self, ActiveTask, AdminState};
+use posemesh_compute_node::auth::token_manager::{TokenProvider, TokenProviderResult};
+use posemesh_compute_node::engine::ControlState;
+use posemesh_compute_node::session::{CapabilitySelector, HeartbeatPolicy, SessionManager};
+use posemesh_compute_node::storage::{build_ports, TokenRef};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+use tower::util::ServiceExt;
+use uuid::Uuid;
+
+#[derive(Default)]
+struct CountingProvider {
+    refreshes: AtomicUsize,
+}
+
+#[async_trait]
+impl TokenProvider for CountingProvider {
+    async fn bearer(&self) -> TokenProviderResult<String> {
+        Ok("node-token".into())
+    }
+
+    async fn on_unauthorized(&self) {
+        self.refreshes.fetch_add(1, Ordering::SeqCst);
+    }
+
+    async fn expires_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        Some(chrono::DateTime::from_timestamp(1_900_000_000, 0).unwrap())
+    }
+}
+
+fn lease() -> LeaseEnvelope {
+    let now = chrono::Utc::now();
+    serde_json::from_value(json!({
+        "access_token": "secret-lease-token",
+        "access_token_expires_at": now + chrono::Duration::minutes(5),
+        "lease_expires_at": now + chrono::Duration::minutes(5),
+        "cancel": false,
+        "domain_id": Uuid::new_v4(),
+        "domain_server_url": "https://domain.example",
+        "task": {
+            "id": Uuid::new_v4(),
+            "job_id": Uuid::new_v4(),
+            "capability": "/cap",
+            "capability_filters": {},
+            "inputs_cids": [],
+            "meta": {}
+        }
+    }))
+    .unwrap()
+}
+
+async fn call(state: &AdminState, method: &str, uri: &str) -> (StatusCode, Value) {
+    let res = admin::router(state.clone())
+        .oneshot(
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = res.status();
+    let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+    (status, body)
+}
+
+#[tokio::test]
+async fn status_reports_idle_node_and_token_expiry() {
+    let state = AdminState::new();
+    state.set_token_provider(Arc::new(CountingProvider::default()));
+
+    let (status, body) = call(&state, "GET", "/status").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["paused"], json!(false));
+    assert_eq!(body["task"], Value::Null);
+    assert_eq!(body["token_expires_at"], json!("2030-03-17T17:46:40Z"));
+}
+
+#[tokio::test]
+async fn status_exposes_active_task_without_access_token() {
+    let state = AdminState::new();
+    let lease = lease();
+    let session = SessionManager::new(CapabilitySelector::new(vec!["/cap".into()]));
+    let mut rng = StdRng::seed_from_u64(1);
+    session
+        .start_session(
+            &lease,
+            std::time::Instant::now(),
+            &HeartbeatPolicy::default_policy(),
+            &mut rng,
+        )
+        .await
+        .unwrap();
+    let ports = build_ports(&lease, TokenRef::new("secret-lease-token".into())).unwrap();
+    let runner_cancel = CancellationToken::new();
+    let guard = state.begin_task(ActiveTask::new(
+        session,
+        Arc::new(tokio::sync::Mutex::new(ControlState::default())),
+        runner_cancel.clone(),
+        &ports,
+    ));
+
+    let (_, body) = call(&state, "GET", "/status").await;
+    assert_eq!(body["task"]["session"]["task_id"], json!(lease.task.id));
+    assert_eq!(body["task"]["session"]["status"], json!("pending"));
+    assert!(!body.to_string().contains("secret-lease-token"));
+
+    let (status, _) = call(&state, "POST", "/task/cancel").await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert!(runner_cancel.is_cancelled());
+
+    drop(guard);
+    let (status, _) = call(&state, "POST", "/task/cancel").await;
+    assert_eq!(status, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn pause_resume_and_token_refresh() {
+    let state = AdminState::new();
+
+    let (status, _) = call(&state, "POST", "/token/refresh").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+    let provider = Arc::new(CountingProvider::default());
+    state.set_token_provider(provider.clone());
+    let (status, _) = call(&state, "POST", "/token/refresh").await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!(provider.refreshes.load(Ordering::SeqCst), 1);
+
+    let (_, body) = call(&state, "POST", "/leasing/pause").await;
+    assert_eq!(body["paused"], json!(true));
+    assert!(state.is_paused());
+
+    let waiter = tokio::spawn({
+        let state = state.clone();
+        async move { state.wait_resumed().await }
+    });
+    let (_, body) = call(&state, "POST", "/leasing/resume").await;
+    assert_eq!(body["paused"], json!(false));
+    tokio::time::timeout(std::time::Duration::from_secs(1), waiter)
+        .await
+        .expect("resume wakes waiters")
+        .unwrap();
+}
@@ -0,0 +1,118 @@
+use compute_runner_api::{ArtifactSink, InputSource};
+use httpmock::prelude::*;
+use posemesh_compute_node::storage::{
+    client::DomainClient,
+    input::DomainInput,
+    manifest::{sha256_hex, ArtifactManifest, ManifestEntry},
+    output::DomainOutput,
+    TokenRef,
+};
+
+fn client(server: &MockServer) -> DomainClient {
+    DomainClient::new(
+        server.base_url().parse().unwrap(),
+        TokenRef::new("tkn".into()),
+    )
+    .unwrap()
+}
+
+fn mock_lookup(server: &MockServer) {
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v1/domains/dom1/data")
+            .header("accept", "application/json");
+        then.status(200)
+            .header("content-type", "application/json")
+            .body(r#"{"data":[]}"#);
+    });
+}
+
+fn mock_create(server: &MockServer, hash: &str) {
+    let body = format!(
+        r#"{{"data":[{{"id":"data-1","domain_id":"dom1","name":"n","data_type":"json","size":3,"created_at":"2025-01-01T00:00:00Z","updated_at":"2025-01-01T00:00:00Z","hash":"{hash}"}}]}}"#
+    );
+    server.mock(move |when, then| {
+        when.method(POST).path("/api/v1/domains/dom1/data");
+        then.status(200)
+            .header("content-type", "application/json")
+            .body(body);
+    });
+}
+
+#[tokio::test]
+async fn upload_records_checksum_and_accepts_matching_server_hash() {
+    let server = MockServer::start();
+    mock_lookup(&server);
+    mock_create(&server, &format!("sha256:{}", sha256_hex(b"abc")));
+
+    let output = DomainOutput::new(client(&server), "dom1".into(), None, "task-1".into());
+    output.put_bytes("result.json", b"abc").await.unwrap();
+
+    let artifacts = output.uploaded_artifacts();
+    assert_eq!(
+        artifacts[0].sha256.as_deref(),
+        Some(sha256_hex(b"abc").as_str())
+    );
+    assert_eq!(artifacts[0].size, Some(3));
+
+    let manifest = ArtifactManifest::from_artifacts(&artifacts);
+    assert_eq!(manifest.algorithm, "sha256");
+    assert_eq!(manifest.entry_for_id("data-1").unwrap().size, 3);
+}
+
+#[tokio::test]
+async fn upload_fails_when_server_hash_disagrees() {
+    let server = MockServer::start();
+    mock_lookup(&server);
+    mock_create(&server, &sha256_hex(b"xyz"));
+
+    let output = DomainOutput::new(client(&server), "dom1".into(), None, "task-1".into());
+    let err = output.put_bytes("result.json", b"abc").await.unwrap_err();
+    assert!(err.to_string().contains("integrity check failed"), "{err}");
+    assert!(output.uploaded_artifacts().is_empty());
+}
+
+#[tokio::test]
+async fn input_is_verified_against_manifest() {
+    let server = MockServer::start();
+    let boundary = "BOUNDARY";
+    let payload = b"abc";
+    let mut body = format!(
+        "--{boundary}\r\nContent-Type: application/octet-stream\r\nContent-Disposition: form-data; name=\"in\"; data-type=\"json\"; id=\"in-1\"; domain-id=\"dom1\"; size=\"3\"; created-at=\"2025-01-01T00:00:00Z\"; updated-at=\"2025-01-01T00:00:00Z\"\r\n\r\n"
+    )
+    .into_bytes();
+    body.extend_from_slice(payload);
+    body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v1/domains/dom1/data")
+            .query_param("ids", "in-1");
+        then.status(200)
+            .header(
+                "content-type",
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(body);
+    });
+
+    let manifest_for = |sha256: String| ArtifactManifest {
+        algorithm: "sha256".into(),
+        entries: vec![ManifestEntry {
+            logical_path: "in.json".into(),
+            id: Some("in-1".into()),
+            name: "in".into(),
+            data_type: "json".into(),
+            sha256,
+            size: 3,
+        }],
+    };
+
+    let good = DomainInput::new(client(&server), "dom1".into())
+        .with_manifest(manifest_for(sha256_hex(payload)));
+    assert_eq!(good.get_bytes_by_cid("in-1").await.unwrap(), payload);
+
+    let bad = DomainInput::new(client(&server), "dom1".into())
+        .with_manifest(manifest_for(sha256_hex(b"tampered")));
+    let err = bad.get_bytes_by_cid("in-1").await.unwrap_err();
+    assert!(err.to_string().contains("integrity check failed"), "{err}");
+}
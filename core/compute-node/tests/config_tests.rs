@@ -31,6 +31,7 @@ fn loads_required_siwe_defaults() {
         "LOG_FORMAT",
         "ENABLE_NOOP",
         "NOOP_SLEEP_SECS",
+        "ADMIN_BIND",
         "DDS_BASE_URL",
         "SECP256K1_PRIVHEX",
         "REG_SECRET",
@@ -63,6 +64,7 @@ fn loads_required_siwe_defaults() {
     assert_eq!(cfg.log_format, LogFormat::Json);
     assert!(!cfg.enable_noop);
     assert_eq!(cfg.noop_sleep_secs, 5);
+    assert_eq!(cfg.admin_bind, None);
 }
 
 #[test]
@@ -109,3 +111,20 @@ fn log_format_text_is_parsed() {
     let cfg = NodeConfig::from_env().expect("config");
     assert_eq!(cfg.log_format, LogFormat::Text);
 }
+
+#[test]
+fn admin_bind_must_be_loopback() {
+    let _g = ENV_GUARD.lock().unwrap();
+    clear(&["DMS_BASE_URL", "DDS_BASE_URL", "ADMIN_BIND"]);
+    std::env::set_var("REG_SECRET", "secret");
+    std::env::set_var("SECP256K1_PRIVHEX", "abcdef");
+
+    std::env::set_var("ADMIN_BIND", "127.0.0.1:9091");
+    let cfg = NodeConfig::from_env().expect("config");
+    assert_eq!(cfg.admin_bind, Some("127.0.0.1:9091".parse().unwrap()));
+
+    std::env::set_var("ADMIN_BIND", "0.0.0.0:9091");
+    let err = NodeConfig::from_env().expect_err("non-loopback bind rejected");
+    assert!(format!("{err}").contains("loopback"));
+    std::env::remove_var("ADMIN_BIND");
+}
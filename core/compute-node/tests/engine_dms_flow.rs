@@ -34,6 +34,7 @@ fn base_cfg() -> NodeConfig {
         log_format: LogFormat::Json,
         enable_noop: true,
         noop_sleep_secs: 1,
+        admin_bind: None,
     }
 }
 
@@ -155,7 +156,19 @@ async fn happy_path_poll_run_complete_with_heartbeat_token_rotation() {
             .header("content-type", "application/json")
             .body_contains("\"artifact-id\"")
             .body_contains(format!("\"job_id\":\"{}\"", job_id))
-            .body_contains(format!("\"capability\":\"{}\"", complete_cap));
+            .body_contains(format!("\"capability\":\"{}\"", complete_cap))
+            .body_contains("\"usage\"")
+            .body_contains("\"wall_ms\"")
+            .matches(|req| {
+                let body: serde_json::Value =
+                    serde_json::from_slice(req.body.as_deref().unwrap_or_default())
+                        .unwrap_or_default();
+                body["meta"]["usage"]["bytes_uploaded"].as_u64() > Some(0)
+                    && body["meta"]["manifest"]["algorithm"] == "sha256"
+                    && body["meta"]["manifest"]["entries"]
+                        .as_array()
+                        .is_some_and(|entries| !entries.is_empty())
+            });
         then.status(200);
     });
 
@@ -280,7 +293,8 @@ async fn error_path_calls_fail() {
             .header("authorization", format!("Bearer {}", node_token))
             .header("content-type", "application/json")
             .body_contains("\"job\"")
-            .body_contains("\"artifacts\"");
+            .body_contains("\"artifacts\"")
+            .body_contains("\"usage\"");
         then.status(200);
     });
 
@@ -481,6 +495,7 @@ async fn run_node_uses_siwe_token_and_completes_task() {
         log_format: LogFormat::Json,
         enable_noop: true,
         noop_sleep_secs: 0,
+        admin_bind: None,
     };
 
     let shutdown = CancellationToken::new();
@@ -0,0 +1,131 @@
+use async_trait::async_trait;
+use compute_runner_api::{ControlPlane, InputSource};
+use httpmock::prelude::*;
+use posemesh_compute_node::storage::{client::DomainClient, input::DomainInput, TokenRef};
+use serde_json::{json, Value};
+use std::sync::Mutex;
+
+#[derive(Default)]
+struct RecordingCtrl {
+    progress: Mutex<Vec<Value>>,
+}
+
+#[async_trait]
+impl ControlPlane for RecordingCtrl {
+    async fn is_cancelled(&self) -> bool {
+        false
+    }
+    async fn progress(&self, value: Value) -> anyhow::Result<()> {
+        self.progress.lock().unwrap().push(value);
+        Ok(())
+    }
+    async fn log_event(&self, _fields: Value) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+fn multipart(boundary: &str, items: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut body = Vec::new();
+    for (id, payload) in items {
+        body.extend_from_slice(
+            format!(
+                "--{boundary}\r\nContent-Type: application/octet-stream\r\nContent-Disposition: form-data; name=\"scan\"; data-type=\"json\"; id=\"{id}\"; domain-id=\"dom1\"; size=\"{}\"; created-at=\"2025-01-01T00:00:00Z\"; updated-at=\"2025-01-01T00:00:00Z\"\r\n\r\n",
+                payload.len()
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(payload);
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{boundary}--\r\n").as_bytes());
+    body
+}
+
+#[tokio::test]
+async fn materialize_all_batches_ids_and_reports_progress() {
+    let server = MockServer::start();
+    let boundary = "BOUNDARY";
+    let batch_mock = server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v1/domains/dom1/data")
+            .query_param("ids", "a,b,c");
+        then.status(200)
+            .header(
+                "content-type",
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            // Out of order and sharing a name, to exercise id mapping and path collisions.
+            .body(multipart(
+                boundary,
+                &[("c", b"C"), ("a", b"A"), ("b", b"B")],
+            ));
+    });
+    let url_mock = server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v1/domains/dom1/data")
+            .query_param("ids", "u");
+        then.status(200)
+            .header(
+                "content-type",
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(multipart(boundary, &[("u", b"U")]));
+    });
+
+    let client = DomainClient::new(
+        server.base_url().parse().unwrap(),
+        TokenRef::new("tkn".into()),
+    )
+    .unwrap();
+    let input = DomainInput::new(client, "dom1".into());
+    let cids: Vec<String> = vec![
+        "a".into(),
+        format!("{}/api/v1/domains/dom1/data/u", server.base_url()),
+        "b".into(),
+        "c".into(),
+    ];
+    let ctrl = RecordingCtrl::default();
+    let inputs = input.materialize_all(&cids, 1, &ctrl).await.unwrap();
+
+    batch_mock.assert();
+    url_mock.assert();
+    let ids: Vec<_> = inputs.iter().map(|m| m.data_id.as_deref()).collect();
+    assert_eq!(ids, [Some("a"), Some("u"), Some("b"), Some("c")]);
+    for (input, expected) in inputs.iter().zip([b"A", b"U", b"B", b"C"]) {
+        assert_eq!(tokio::fs::read(&input.path).await.unwrap(), expected);
+    }
+    let progress = ctrl.progress.into_inner().unwrap();
+    assert_eq!(
+        progress.last().unwrap(),
+        &json!({"inputs": {"completed": 4, "total": 4}})
+    );
+}
+
+#[tokio::test]
+async fn materialize_all_fails_when_batch_misses_an_id() {
+    let server = MockServer::start();
+    let boundary = "BOUNDARY";
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v1/domains/dom1/data")
+            .query_param("ids", "a,b");
+        then.status(200)
+            .header(
+                "content-type",
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(multipart(boundary, &[("a", b"A")]));
+    });
+
+    let client = DomainClient::new(
+        server.base_url().parse().unwrap(),
+        TokenRef::new("tkn".into()),
+    )
+    .unwrap();
+    let input = DomainInput::new(client, "dom1".into());
+    let err = input
+        .materialize_all(&["a".into(), "b".into()], 1, &RecordingCtrl::default())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("missing data for b"), "{err}");
+}
@@ -0,0 +1,174 @@
+#![cfg(feature = "otel")]
+
+#[allow(dead_code)]
+mod support;
+
+use async_trait::async_trait;
+use httpmock::prelude::*;
+use posemesh_compute_node::auth::token_manager::{TokenProvider, TokenProviderResult};
+use posemesh_compute_node::config::{LogFormat, NodeConfig};
+use posemesh_compute_node::dms::client::DmsClient;
+use posemesh_compute_node::engine::run_cycle_with_dms;
+use posemesh_compute_node::telemetry::{self, OtlpConfig};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+fn base_cfg() -> NodeConfig {
+    NodeConfig {
+        dms_base_url: "https://dms.example".parse().unwrap(),
+        node_version: "1.0.0".into(),
+        request_timeout_secs: 10,
+        dds_base_url: None,
+        reg_secret: None,
+        secp256k1_privhex: None,
+        heartbeat_jitter_ms: 250,
+        heartbeat_min_ratio: 0.25,
+        heartbeat_max_ratio: 0.35,
+        poll_backoff_ms_min: 1000,
+        poll_backoff_ms_max: 30000,
+        token_safety_ratio: 0.75,
+        token_reauth_max_retries: 3,
+        token_reauth_jitter_ms: 500,
+        register_interval_secs: None,
+        register_max_retry: None,
+        max_concurrency: 1,
+        log_format: LogFormat::Text,
+        enable_noop: true,
+        noop_sleep_secs: 1,
+        admin_bind: None,
+    }
+}
+
+struct StaticProvider;
+
+#[async_trait]
+impl TokenProvider for StaticProvider {
+    async fn bearer(&self) -> TokenProviderResult<String> {
+        Ok("node-abc".into())
+    }
+
+    async fn on_unauthorized(&self) {}
+}
+
+#[test]
+fn otlp_config_builds_traces_endpoint() {
+    let cfg = OtlpConfig {
+        endpoint: "http://collector:4318/".into(),
+        service_name: "svc".into(),
+    };
+    assert_eq!(cfg.traces_endpoint(), "http://collector:4318/v1/traces");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn spans_exported_and_traceparent_propagated() {
+    let collector = MockServer::start();
+    let traces_mock = collector.mock(|when, then| {
+        when.method(POST).path("/v1/traces");
+        then.status(200);
+    });
+    telemetry::init_with_otlp(
+        LogFormat::Text,
+        Some(OtlpConfig {
+            endpoint: collector.base_url(),
+            service_name: "compute-node-test".into(),
+        }),
+    )
+    .unwrap();
+
+    let server = MockServer::start();
+    let base_url = server.base_url();
+    let task_id = Uuid::new_v4();
+    let job_id = Uuid::new_v4();
+    let domain_id = Uuid::new_v4();
+    let now = chrono::Utc::now();
+    let lease_body = json!({
+        "access_token": "t-A",
+        "access_token_expires_at": now,
+        "lease_expires_at": now + chrono::Duration::seconds(30),
+        "cancel": false,
+        "status": "leased",
+        "domain_id": domain_id,
+        "domain_server_url": base_url.clone(),
+        "task": {
+            "id": task_id,
+            "job_id": job_id,
+            "capability": support::mock_runner::MOCK_CAPABILITY,
+            "capability_filters": {},
+            "inputs_cids": [],
+            "outputs_prefix": "out",
+            "label": null,
+            "stage": null,
+            "meta": {},
+            "priority": null,
+            "attempts": null,
+            "max_attempts": null,
+            "deps_remaining": null,
+            "status": "leased",
+            "mode": null,
+            "organization_filter": null,
+            "billing_units": null,
+            "estimated_credit_cost": null,
+            "debited_amount": null,
+            "debited_at": null,
+            "lease_expires_at": null
+        }
+    });
+    let lease_mock = server.mock(move |when, then| {
+        when.method(GET).path("/tasks");
+        then.status(200)
+            .header("content-type", "application/json")
+            .json_body(lease_body.clone());
+    });
+    let hb_mock = server.mock(move |when, then| {
+        when.method(POST)
+            .path(format!("/tasks/{}/heartbeat", task_id))
+            .header_exists("traceparent");
+        then.status(200)
+            .header("content-type", "application/json")
+            .json_body(json!({
+                "lease_expires_at": now + chrono::Duration::seconds(30),
+                "cancel": false,
+                "status": "leased"
+            }));
+    });
+    let upload_mock = server.mock(move |when, then| {
+        when.method(POST)
+            .path(format!("/api/v1/domains/{}/data", domain_id))
+            .header_exists("traceparent");
+        then.status(200)
+            .header("content-type", "application/json")
+            .body(r#"{"data":[{"id":"artifact-id","domain_id":"dom","name":"n","data_type":"d","size":1,"created_at":"2025-01-01T00:00:00Z","updated_at":"2025-01-01T00:00:00Z"}]}"#);
+    });
+    let complete_mock = server.mock(move |when, then| {
+        when.method(POST)
+            .path(format!("/tasks/{}/complete", task_id))
+            .header_exists("traceparent");
+        then.status(200);
+    });
+
+    let dms = DmsClient::new(
+        base_url.parse().unwrap(),
+        Duration::from_secs(5),
+        Arc::new(StaticProvider),
+    )
+    .unwrap();
+    let reg = support::mock_runner::registry_with_mock();
+    assert!(run_cycle_with_dms(&base_cfg(), &dms, &reg).await.unwrap());
+
+    lease_mock.assert();
+    assert!(hb_mock.hits() >= 1, "heartbeat should carry traceparent");
+    assert!(upload_mock.hits() >= 1, "upload should carry traceparent");
+    complete_mock.assert();
+
+    tokio::task::spawn_blocking(telemetry::flush).await.unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while traces_mock.hits() < 1 && Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert!(
+        traces_mock.hits() >= 1,
+        "expected spans exported to collector"
+    );
+}
@@ -1,6 +1,6 @@
 /// <reference types="vite/client" />
 
-import { DownloadQuery, signInWithAppCredential, signInWithUserCredential, DomainClient, UploadDomainData, DomainData, DomainDataMetadata, JobRequest, ListDomainsQuery } from '@auki/domain-client';
+import { DownloadQuery, signInWithAppCredential, signInWithUserCredential, DomainClient, UploadDomainData, DomainData, DomainDataMetadata, JobRequest, ListDomainsQuery, TransferEvent, UploadQueue, QueuedUpload, DeleteReport, DomainWithServer, DomainServer } from '@auki/domain-client';
 import { describe, it, expect, beforeAll, afterAll } from 'vitest';
 
 const loadConfig = () => {
@@ -239,7 +239,8 @@ describe('Posemesh Domain HTTP', async() => {
         it('should return auth error for submitting job request with app credential', async () => {
             await expect(client.submitJobV1(domainId, {
                 data_ids: ["a84a36e5-312b-4f80-974a-06f5d19c1e16"],
-                server_url: "test"
+                server_url: "test",
+                server_api_key: "test"
             } as JobRequest)).rejects.toThrow(/Auki response - status: 403 Forbidden, error: Failed to process domain. invalid domain access token/);
         });
     });
@@ -328,6 +329,98 @@ describe('Posemesh Domain HTTP', async() => {
             }
         });
 
+        it('should upload a blob and a readable stream with user credential', async () => {
+            const data = "streamed upload - js test";
+            const events: TransferEvent[] = [];
+            const blobRes: DomainDataMetadata = await client.uploadDomainDataStream(domainId, {
+                name: "to be deleted 2 - js test",
+                data_type: "test",
+                data: new Blob([data]),
+            }, (event: TransferEvent) => events.push(event));
+            expect(blobRes.id).toBeDefined();
+            expect(events).toContainEqual({ type: "items_done", done: 1, total: 1 });
+
+            const stream = new Blob([data]).stream();
+            const streamRes: DomainDataMetadata = await client.uploadDomainDataStream(domainId, {
+                id: blobRes.id,
+                data: stream,
+            });
+            expect(streamRes.id).toEqual(blobRes.id);
+
+            const bytes: Uint8Array = await client.downloadDomainDataById(domainId, blobRes.id);
+            expect(new TextDecoder().decode(bytes)).toEqual(data);
+        });
+
+        it('should reject a stream upload when aborted', async () => {
+            const controller = new AbortController();
+            controller.abort();
+            await expect(client.uploadDomainDataStream(domainId, {
+                name: "aborted - js test",
+                data_type: "test",
+                data: new Blob(["never uploaded"]),
+            }, undefined, controller.signal)).rejects.toMatchObject({ name: "Cancelled" });
+        });
+
+        it('should get and rename a domain', async () => {
+            const domain: DomainWithServer = await client.getDomain(domainId);
+            expect(domain.id).toBe(domainId);
+
+            const renamed: DomainWithServer = await client.updateDomain(domainId, { name: domain.name + " renamed" });
+            try {
+                expect(renamed.name).toBe(domain.name + " renamed");
+            } finally {
+                await client.updateDomain(domainId, { name: domain.name });
+            }
+
+            const server: DomainServer = await client.getDomainServer(domain.domain_server_id);
+            expect(server.url).toBe(domain.domain_server.url);
+            expect(Array.isArray(await client.listDomainPortals(domainId))).toBe(true);
+            expect(Array.isArray(await client.listPortals(domainId))).toBe(true);
+        });
+
+        it('should delete domain data in bulk', async () => {
+            const uploaded: DomainDataMetadata[] = await client.uploadDomainData(domainId, [0, 1, 2].map((i) => ({
+                name: `bulk ${i} - js test`,
+                data_type: "bulk_delete_test",
+                data: new Uint8Array([i]),
+            } as UploadDomainData)));
+            const ids = uploaded.map((meta) => meta.id).sort();
+
+            const query: DownloadQuery = { ids: [], name: null, data_type: "bulk_delete_test" };
+            const dryRun: DeleteReport = await client.deleteDomainDataByQuery(domainId, query, true);
+            expect(dryRun.dry_run).toBe(true);
+            expect(dryRun.deleted.map((meta) => meta.id).sort()).toEqual(ids);
+
+            const report: DeleteReport = await client.deleteDomainData(domainId, [...ids, "missing-id"], false, 2);
+            expect(report.deleted.map((meta) => meta.id).sort()).toEqual(ids);
+            expect(report.failed).toEqual([]);
+            expect(report.missing).toEqual(["missing-id"]);
+        });
+
+        it('should flush an upload queue', async () => {
+            // Node has no IndexedDB, so the queue lives in a Map.
+            const items = new Map<string, Uint8Array>();
+            const storage = {
+                getItem: async (key: string) => items.get(key) ?? null,
+                setItem: async (key: string, value: Uint8Array) => { items.set(key, value); },
+                removeItem: async (key: string) => { items.delete(key); },
+            };
+            const queue = new UploadQueue(undefined, storage);
+            const queued: QueuedUpload = await queue.enqueue(domainId, {
+                name: "queued - js test",
+                data_type: "test",
+                data: new TextEncoder().encode("queued upload - js test"),
+            } as UploadDomainData);
+            expect(queued.state).toBe("pending");
+
+            const flushed: QueuedUpload[] = await queue.flush(client);
+            expect(flushed.map((item) => item.state)).toEqual(["done"]);
+            expect(await queue.clearDone()).toBe(1);
+            expect(await queue.items()).toEqual([]);
+            await client.deleteDomainDataById(domainId, flushed[0].result!.id);
+            queue.free();
+        });
+
         it('should load domain metadata', async () => {
             const metadata: DomainDataMetadata[] = await client.downloadDomainDataMetadata(domainId, {
                 ids: [],
@@ -351,6 +444,7 @@ describe('Posemesh Domain HTTP', async() => {
             await expect(client.submitJobV1(domainId, {
                 data_ids: [],
                 server_url: "test",
+                server_api_key: "test",
                 processing_type: "invalid_processing_type"
             })).rejects.toThrow(/Auki response - status: 400 Bad Request, error: Failed to process domain. invalid processing type/);
         });
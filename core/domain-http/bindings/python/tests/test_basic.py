@@ -23,6 +23,9 @@ from auki_domain_client import (
     new_with_user_credential,
     ListDomainsQuery,
     ListDomainsResponse,
+    QueuedUploadState,
+    UpdateDomainRequest,
+    UploadQueue,
 )
 
 
@@ -192,15 +195,10 @@ class TestAppCredential:
         if len(data) == 0:
             pytest.skip("No domain data found to test download by ID")
 
-        # Note: downloadDomainDataById may not be exposed in Python bindings
-        # If it is, we would test it here:
-        # data_id = data[0].metadata.id
-        # bytes_data = app_client.download_domain_data_by_id(test_domain_id, data_id)
-        # assert bytes_data is not None
-        # assert len(bytes_data) > 0
-
-        # For now, we verify we can get data with the ID in the query
         data_id = data[0].metadata.id
+        bytes_data = app_client.download_domain_data_by_id(test_domain_id, data_id)
+        assert bytes_data == data[0].data
+
         query_by_id = DownloadQuery(ids=[data_id], name=None, data_type=None)
         data_by_id = app_client.download_domain_data(test_domain_id, query_by_id)
 
@@ -210,19 +208,31 @@ class TestAppCredential:
     def test_download_metadata(self, app_client, test_domain_id):
         """Test downloading metadata for domain data."""
         query = DownloadQuery(ids=[], name=None, data_type="test")
-        data = app_client.download_domain_data(test_domain_id, query)
-
-        assert data is not None
-        assert isinstance(data, list)
-        assert len(data) > 0
+        metadata = app_client.download_domain_data_metadata(test_domain_id, query)
+
+        assert isinstance(metadata, list)
+        assert len(metadata) > 0
+
+        for item in metadata:
+            assert isinstance(item, DomainDataMetadata)
+            assert item.id is not None
+            assert item.name is not None
+            assert item.data_type == "test"
+            assert item.size > 0
+            assert item.created_at is not None
+            assert item.updated_at is not None
+
+    def test_download_domain_data_stream(self, app_client, test_domain_id):
+        """Test pulling domain data one item at a time."""
+        query = DownloadQuery(ids=[], name=None, data_type="test")
+        download = app_client.download_domain_data_stream(test_domain_id, query)
 
-        for item in data:
-            assert item.metadata.id is not None
-            assert item.metadata.name is not None
+        items = []
+        while (item := download.next()) is not None:
+            assert isinstance(item, DomainData)
             assert item.metadata.data_type == "test"
-            assert item.metadata.size > 0
-            assert item.metadata.created_at is not None
-            assert item.metadata.updated_at is not None
+            items.append(item)
+        assert len(items) > 0
 
     def test_list_domains(self, app_client):
         """Test listing domains."""
@@ -301,6 +311,74 @@ class TestUserCredential:
         assert len(filtered_data) > 0
         assert all(item.metadata.name == test_name for item in filtered_data)
 
+    def test_upload_domain_data_stream(self, user_client, test_domain_id, tmp_path):
+        """Test uploading items and files through an upload stream, then deleting them."""
+        path = tmp_path / "scan.bin"
+        path.write_bytes(bytes([4, 5, 6]))
+
+        upload = user_client.upload_domain_data_stream(test_domain_id)
+        upload.write(UploadDomainData(action=DomainAction.CREATE("stream_item", "stream_test"), data=bytes([1, 2, 3])))
+        upload.write_file(str(path), DomainAction.CREATE("stream_file", "stream_test"))
+        uploaded = upload.finish()
+
+        assert sorted(item.name for item in uploaded) == ["stream_file", "stream_item"]
+        with pytest.raises(DomainError):
+            upload.write(UploadDomainData(action=DomainAction.CREATE("late", "stream_test"), data=bytes([1])))
+
+        for item in uploaded:
+            user_client.delete_domain_data_by_id(test_domain_id, item.id)
+        query = DownloadQuery(ids=[], name=None, data_type="stream_test")
+        assert user_client.download_domain_data_metadata(test_domain_id, query) == []
+
+    def test_get_and_update_domain(self, user_client, test_domain_id):
+        """Test reading a domain, renaming it and looking up its domain server."""
+        domain = user_client.get_domain(test_domain_id)
+        assert domain.id == test_domain_id
+
+        renamed = user_client.update_domain(test_domain_id, UpdateDomainRequest(name=domain.name + " renamed"))
+        try:
+            assert renamed.name == domain.name + " renamed"
+        finally:
+            user_client.update_domain(test_domain_id, UpdateDomainRequest(name=domain.name))
+
+        server = user_client.get_domain_server(domain.domain_server_id)
+        assert server.url == domain.domain_server.url
+        assert isinstance(user_client.list_domain_portals(test_domain_id), list)
+        assert isinstance(user_client.list_portals(test_domain_id), list)
+
+    def test_bulk_delete(self, user_client, test_domain_id):
+        """Test deleting several entries at once, after a dry run."""
+        data = [UploadDomainData(action=DomainAction.CREATE(f"bulk_{i}", "bulk_delete_test"), data=bytes([i])) for i in range(3)]
+        ids = [meta.id for meta in user_client.upload_domain_data(test_domain_id, data)]
+
+        query = DownloadQuery(ids=[], name=None, data_type="bulk_delete_test")
+        report = user_client.delete_domain_data_by_query(test_domain_id, query, True)
+        assert report.dry_run
+        assert sorted(meta.id for meta in report.deleted) == sorted(ids)
+        assert len(user_client.download_domain_data_metadata(test_domain_id, query)) == 3
+
+        report = user_client.delete_domain_data(test_domain_id, ids + ["missing-id"], False)
+        assert sorted(meta.id for meta in report.deleted) == sorted(ids)
+        assert report.failed == []
+        assert report.missing == ["missing-id"]
+        assert user_client.download_domain_data_metadata(test_domain_id, query) == []
+
+    def test_upload_queue(self, user_client, test_domain_id, tmp_path):
+        """Test enqueueing items while offline and flushing them later."""
+        queue = UploadQueue(str(tmp_path / "queue"))
+        queued = queue.enqueue(test_domain_id, UploadDomainData(action=DomainAction.CREATE("queued_item", "queue_test"), data=bytes([1, 2, 3])))
+        assert queued.state == QueuedUploadState.PENDING
+
+        # A second queue over the same directory sees what the first one stored.
+        queue = UploadQueue(str(tmp_path / "queue"))
+        items = queue.flush(user_client)
+
+        assert [item.state for item in items] == [QueuedUploadState.DONE]
+        assert items[0].result.name == "queued_item"
+        assert queue.clear_done() == 1
+        assert queue.items() == []
+        user_client.delete_domain_data_by_id(test_domain_id, items[0].result.id)
+
     def test_list_domains_by_domain_server_id(self, user_client):
         """Test listing domains by domain server id."""
         query = ListDomainsQuery(org="own", portal_id=None, portal_short_id=None, domain_server_id=None)
@@ -1,22 +1,27 @@
 use base64::{Engine as _, engine::general_purpose};
 use futures::lock::Mutex;
-use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
 use posemesh_utils::now_unix_secs;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use crate::errors::{AukiErrorResponse, AuthError, DomainError};
+use crate::token_store::{StoredSession, TokenStore};
+use crate::transport::{SendVia, Transport};
 
 #[derive(Debug, Clone)]
 pub struct AuthClient {
     pub api_url: String,
-    client: Client,
+    transport: Transport,
     dds_token_cache: Arc<Mutex<Option<DdsTokenCache>>>,
     user_token_cache: Arc<Mutex<Option<UserTokenCache>>>,
     pub client_id: String,
-    app_key: Option<String>,
-    app_secret: Option<String>,
+    // App key and secret of an app sign-in, shared by all clones so logging out clears them.
+    app_credentials: Arc<Mutex<Option<(String, String)>>>,
+    token_store: Option<Arc<dyn TokenStore>>,
+    // Seconds before expiry at which cached tokens are renewed, shared by all clones.
+    refresh_ahead: Arc<AtomicU64>,
 }
 
 #[derive(Debug, Clone)]
@@ -85,14 +90,145 @@ pub struct DdsTokenResponse {
 
 impl AuthClient {
     pub fn new(api_url: &str, client_id: &str) -> Self {
+        Self::new_with_transport(api_url, client_id, Transport::default())
+    }
+
+    pub fn new_with_transport(api_url: &str, client_id: &str, transport: Transport) -> Self {
         Self {
             api_url: api_url.to_string(),
-            client: Client::new(),
+            transport,
             dds_token_cache: Arc::new(Mutex::new(None)),
             user_token_cache: Arc::new(Mutex::new(None)),
             client_id: client_id.to_string(),
-            app_key: None,
-            app_secret: None,
+            app_credentials: Arc::new(Mutex::new(None)),
+            token_store: None,
+            refresh_ahead: Arc::new(AtomicU64::new(REFRESH_CACHE_TIME)),
+        }
+    }
+
+    pub(crate) fn set_transport(&mut self, transport: Transport) {
+        self.transport = transport;
+    }
+
+    /// Seconds before expiry at which cached tokens are renewed.
+    pub(crate) fn refresh_ahead(&self) -> u64 {
+        self.refresh_ahead.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_refresh_ahead(&self, secs: u64) {
+        self.refresh_ahead
+            .store(secs.max(REFRESH_CACHE_TIME), Ordering::Relaxed);
+    }
+
+    /// Expiry of the cached DDS access token, if any.
+    pub(crate) async fn dds_expires_at(&self) -> Option<u64> {
+        self.dds_token_cache
+            .lock()
+            .await
+            .as_ref()
+            .map(|cache| cache.claim.exp)
+    }
+
+    /// Persists the user session to `store` from now on, starting with the current one if any.
+    pub async fn set_token_store(&mut self, store: Arc<dyn TokenStore>) {
+        self.token_store = Some(store);
+        let session = self.user_token_cache.lock().await.clone();
+        if let Some(session) = session {
+            self.persist_session(&session).await;
+        }
+    }
+
+    // Saves the user tokens to the token store. A failing store only loses the session on restart, so it is logged instead of failing the call.
+    async fn persist_session(&self, cache: &UserTokenCache) {
+        if let Some(store) = &self.token_store {
+            let session = StoredSession {
+                refresh_token: cache.refresh_token.clone(),
+                access_token: cache.access_token.clone(),
+            };
+            if let Err(e) = store.save(&session).await {
+                tracing::warn!("Failed to save session: {}", e);
+            }
+        }
+    }
+
+    /// Signs in with the session saved in the token store. Returns `false` when there is no store,
+    /// no saved session or its refresh token has expired.
+    pub async fn restore_session(&mut self) -> Result<bool, DomainError> {
+        let Some(store) = self.token_store.clone() else {
+            return Ok(false);
+        };
+        let session = match store.load().await {
+            Ok(Some(session)) => session,
+            Ok(None) => return Ok(false),
+            Err(e) => {
+                tracing::warn!("Failed to load session: {}", e);
+                return Ok(false);
+            }
+        };
+        let refresh_exp = parse_jwt(&session.refresh_token).map(|claim| claim.exp);
+        if !refresh_exp.is_ok_and(|exp| exp > now_unix_secs()) {
+            if let Err(e) = store.clear().await {
+                tracing::warn!("Failed to clear session: {}", e);
+            }
+            return Ok(false);
+        }
+
+        *self.app_credentials.lock().await = None;
+        *self.user_token_cache.lock().await = Some(UserTokenCache {
+            expires_at: parse_jwt(&session.access_token)
+                .map(|claim| claim.exp)
+                .unwrap_or(0),
+            refresh_token: session.refresh_token,
+            access_token: session.access_token,
+        });
+        *self.dds_token_cache.lock().await = Some(DdsTokenCache::default());
+
+        if let Err(e) = self.get_dds_access_token(None).await {
+            // The refresh token was rejected, so the saved session is of no use anymore.
+            if matches!(e.status().map(|s| s.as_u16()), Some(401 | 403))
+                && let Err(e) = store.clear().await
+            {
+                tracing::warn!("Failed to clear session: {}", e);
+            }
+            return Err(e);
+        }
+        Ok(true)
+    }
+
+    /// Revokes the user refresh token and forgets every token, in memory and in the token store.
+    /// State is cleared even when revoking fails, in which case the error is returned.
+    pub async fn logout(&mut self) -> Result<(), DomainError> {
+        *self.app_credentials.lock().await = None;
+        *self.dds_token_cache.lock().await = None;
+        let user_token_cache = self.user_token_cache.lock().await.take();
+        if let Some(store) = &self.token_store
+            && let Err(e) = store.clear().await
+        {
+            tracing::warn!("Failed to clear session: {}", e);
+        }
+
+        let Some(user_token_cache) = user_token_cache else {
+            return Ok(());
+        };
+        let response = self
+            .transport
+            .post(format!("{}/user/logout", &self.api_url))
+            .header("Content-Type", "application/json")
+            .header("posemesh-client-id", &self.client_id)
+            .bearer_auth(&user_token_cache.refresh_token)
+            .send_via(&self.transport)
+            .await?;
+
+        // Servers without revocation support cannot have anything to revoke.
+        let status = response.status().as_u16();
+        if response.status().is_success() || matches!(status, 401 | 404 | 405 | 501) {
+            Ok(())
+        } else {
+            Err(
+                AukiErrorResponse::from_response(response, "Failed to logout.")
+                    .await
+                    .into(),
+            )
         }
     }
 
@@ -122,8 +258,7 @@ impl AuthClient {
         app_key: &str,
         app_secret: &str,
     ) -> Result<String, DomainError> {
-        self.app_key = Some(app_key.to_string());
-        self.app_secret = Some(app_secret.to_string());
+        *self.app_credentials.lock().await = Some((app_key.to_string(), app_secret.to_string()));
         *self.dds_token_cache.lock().await = None;
         *self.user_token_cache.lock().await = None;
 
@@ -141,7 +276,7 @@ impl AuthClient {
         let result = if let Some(oidc_access_token) = oidc_access_token {
             self.get_dds_access_token_with_oidc_access_token(oidc_access_token)
                 .await
-        } else if self.app_key.is_some() {
+        } else if self.app_credentials.lock().await.is_some() {
             self.get_dds_app_access_token().await
         } else {
             self.get_dds_user_access_token().await
@@ -183,33 +318,32 @@ impl AuthClient {
             cache.clone()
         };
 
-        let app_key = self
-            .app_key
+        let (app_key, app_secret) = self
+            .app_credentials
+            .lock()
+            .await
             .clone()
             .ok_or(AuthError::Unauthorized("App key is not set"))?;
-        let app_secret = self
-            .app_secret
-            .clone()
-            .ok_or(AuthError::Unauthorized("App secret is not set"))?;
 
         let token_cache = get_cached_or_fresh_token(
             &token_cache.unwrap_or(DdsTokenCache {
                 access_token: "".to_string(),
                 claim: JwtClaim { exp: 0, org: None },
             }),
+            self.refresh_ahead(),
             || {
                 let app_key = app_key.to_string();
                 let app_secret = app_secret.to_string();
-                let client = self.client.clone();
+                let transport = self.transport.clone();
                 let api_url = self.api_url.clone();
                 let client_id = self.client_id.clone();
                 async move {
-                    let response = client
+                    let response = transport
                         .post(format!("{}/service/domains-access-token", api_url))
                         .basic_auth(app_key, Some(app_secret))
                         .header("Content-Type", "application/json")
                         .header("posemesh-client-id", client_id)
-                        .send()
+                        .send_via(&transport)
                         .await?;
 
                     if response.status().is_success() {
@@ -219,15 +353,11 @@ impl AuthClient {
                             claim: parse_jwt(&token_response.access_token)?,
                         })
                     } else {
-                        let status = response.status();
-                        let text = response
-                            .text()
-                            .await
-                            .unwrap_or_else(|_| "Unknown error".to_string());
-                        Err(AukiErrorResponse {
-                            status,
-                            error: format!("Failed to get DDS access token. {}", text),
-                        }
+                        Err(AukiErrorResponse::from_response(
+                            response,
+                            "Failed to get DDS access token.",
+                        )
+                        .await
                         .into())
                     }
                 }
@@ -265,26 +395,29 @@ impl AuthClient {
             return Err(AuthError::Unauthorized("Login first").into());
         }
 
-        let token_cache = get_cached_or_fresh_token(&token_cache.unwrap(), || {
-            let client = self.client.clone();
+        let refresh_ahead = self.refresh_ahead();
+        let token_cache = get_cached_or_fresh_token(&token_cache.unwrap(), refresh_ahead, || {
+            let transport = self.transport.clone();
             let api_url = self.api_url.clone();
             let client_id = self.client_id.clone();
 
             async move {
-                let client_clone = client.clone();
+                let transport_clone = transport.clone();
                 let api_url_clone = api_url.clone();
                 let client_id_clone = client_id.clone();
                 let refresh_token = user_token_cache.clone().unwrap().refresh_token;
-                let user_token_cache =
-                    get_cached_or_fresh_token(&user_token_cache.unwrap(), || async move {
-                        let response = client_clone
+                let previous_access_token = user_token_cache.clone().unwrap().access_token;
+                let user_token_cache = get_cached_or_fresh_token(
+                    &user_token_cache.unwrap(),
+                    refresh_ahead,
+                    || async move {
+                        let response = transport_clone
                             .post(format!("{}/user/refresh", api_url_clone))
                             .header("Content-Type", "application/json")
                             .header("posemesh-client-id", client_id_clone)
                             .header("Authorization", format!("Bearer {}", refresh_token))
-                            .send()
-                            .await
-                            .expect("Failed to refresh token");
+                            .send_via(&transport_clone)
+                            .await?;
 
                         if response.status().is_success() {
                             let token_response: UserTokenResponse = response.json().await?;
@@ -294,24 +427,24 @@ impl AuthClient {
                                 expires_at: parse_jwt(&token_response.access_token)?.exp,
                             })
                         } else {
-                            let status = response.status();
-                            let text = response
-                                .text()
-                                .await
-                                .unwrap_or_else(|_| "Unknown error".to_string());
-                            Err(AukiErrorResponse {
-                                status,
-                                error: format!("Failed to refresh token. {}", text),
-                            }
+                            Err(AukiErrorResponse::from_response(
+                                response,
+                                "Failed to refresh token.",
+                            )
+                            .await
                             .into())
                         }
-                    })
-                    .await?;
+                    },
+                )
+                .await?;
 
                 {
                     let mut cache = self.user_token_cache.lock().await;
                     *cache = Some(user_token_cache.clone());
                 }
+                if user_token_cache.access_token != previous_access_token {
+                    self.persist_session(&user_token_cache).await;
+                }
 
                 let dds_token_response = self
                     .get_dds_token_by_token(&user_token_cache.access_token)
@@ -340,8 +473,7 @@ impl AuthClient {
 
     // Login with user credentials, return DDS access token. It clears all caches and sets the app credentials to none.
     pub async fn user_login(&mut self, email: &str, password: &str) -> Result<String, DomainError> {
-        self.app_key = None;
-        self.app_secret = None;
+        *self.app_credentials.lock().await = None;
 
         let credentials = UserCredentials {
             email: email.to_string(),
@@ -349,23 +481,24 @@ impl AuthClient {
         };
 
         let response = self
-            .client
+            .transport
             .post(format!("{}/user/login", &self.api_url))
             .header("Content-Type", "application/json")
             .header("posemesh-client-id", &self.client_id)
             .json(&credentials)
-            .send()
+            .send_via(&self.transport)
             .await?;
 
         if response.status().is_success() {
             let token_response: UserTokenResponse = response.json().await?;
             {
-                let mut cache = self.user_token_cache.lock().await;
-                *cache = Some(UserTokenCache {
+                let user_token_cache = UserTokenCache {
                     refresh_token: token_response.refresh_token.clone(),
                     access_token: token_response.access_token.clone(),
                     expires_at: parse_jwt(&token_response.access_token)?.exp,
-                });
+                };
+                *self.user_token_cache.lock().await = Some(user_token_cache.clone());
+                self.persist_session(&user_token_cache).await;
             }
 
             let dds_token_response = self
@@ -379,29 +512,23 @@ impl AuthClient {
             *cache = Some(token_cache.clone());
             Ok(token_cache.access_token)
         } else {
-            let status = response.status();
-            let text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-
-            Err(AukiErrorResponse {
-                status,
-                error: format!("Failed to login. {}", text),
-            }
-            .into())
+            Err(
+                AukiErrorResponse::from_response(response, "Failed to login.")
+                    .await
+                    .into(),
+            )
         }
     }
 
     // Get DDS access token with either user access token or oidc_access_token, doesn't cache
     async fn get_dds_token_by_token(&self, token: &str) -> Result<DdsTokenResponse, DomainError> {
         let dds_response = self
-            .client
+            .transport
             .post(format!("{}/service/domains-access-token", &self.api_url))
             .header("Authorization", format!("Bearer {}", token))
             .header("Content-Type", "application/json")
             .header("posemesh-client-id", &self.client_id)
-            .send()
+            .send_via(&self.transport)
             .await?;
 
         if dds_response.status().is_success() {
@@ -410,24 +537,21 @@ impl AuthClient {
                 .await
                 .map_err(|e| e.into())
         } else {
-            let status = dds_response.status();
-            let text = dds_response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            Err(AukiErrorResponse {
-                status,
-                error: format!("Failed to get DDS access token. {}", text),
-            }
-            .into())
+            Err(
+                AukiErrorResponse::from_response(dds_response, "Failed to get DDS access token.")
+                    .await
+                    .into(),
+            )
         }
     }
 }
 
 pub const REFRESH_CACHE_TIME: u64 = 60; // 1 minute
 
+// Returns the cached token, or a new one from `token_fetcher` when it expires within `refresh_ahead` seconds.
 pub(crate) async fn get_cached_or_fresh_token<R, F, Fut>(
     cache: &R,
+    refresh_ahead: u64,
     token_fetcher: F,
 ) -> Result<R, DomainError>
 where
@@ -438,8 +562,8 @@ where
     // Check if we have a valid cached token
     let expires_at = cache.get_expires_at();
     let current_time = now_unix_secs();
-    // If token expires in more than REFRESH_CACHE_TIME seconds, return cached token
-    if expires_at > current_time && expires_at - current_time > REFRESH_CACHE_TIME {
+    // If token expires in more than `refresh_ahead` seconds, return cached token
+    if expires_at > current_time && expires_at - current_time > refresh_ahead {
         return Ok(cache.clone());
     }
 
@@ -471,6 +595,8 @@ mod tests {
     use std::time::{SystemTime, UNIX_EPOCH};
     use tokio::sync::Mutex;
 
+    use crate::transport::test_support::json_response;
+
     #[derive(Clone, Debug)]
     struct DummyTokenCache {
         access_token: String,
@@ -530,7 +656,7 @@ mod tests {
             }
         };
 
-        let result = get_cached_or_fresh_token(&cache, token_fetcher)
+        let result = get_cached_or_fresh_token(&cache, REFRESH_CACHE_TIME, token_fetcher)
             .await
             .unwrap();
         // Should have called fetcher
@@ -564,7 +690,7 @@ mod tests {
             }
         };
 
-        let result = get_cached_or_fresh_token(&cache, token_fetcher)
+        let result = get_cached_or_fresh_token(&cache, REFRESH_CACHE_TIME, token_fetcher)
             .await
             .unwrap();
         // Should NOT have called fetcher
@@ -575,4 +701,102 @@ mod tests {
         // Should have same expiration
         assert_eq!(result.expires_at, not_expiring);
     }
+
+    /// Answers the auth API with fresh tokens and records the paths it saw.
+    struct AuthApi {
+        paths: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    impl crate::transport::HttpExecutor for AuthApi {
+        fn execute(&self, request: reqwest::Request) -> crate::transport::ExecuteFuture {
+            let path = request.url().path().to_string();
+            self.paths.lock().unwrap().push(path.clone());
+            let exp = now_unix_secs() + 3600;
+            let body = match path.as_str() {
+                "/user/refresh" => serde_json::json!({
+                    "access_token": make_jwt(exp),
+                    "refresh_token": make_jwt(exp + 1),
+                }),
+                "/service/domains-access-token" => {
+                    serde_json::json!({ "access_token": make_jwt(exp) })
+                }
+                _ => serde_json::json!({}),
+            };
+            let res = json_response(200, &body);
+            Box::pin(async move { Ok(res) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_restore_session_and_logout() {
+        let paths = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let transport = Transport::with_executor(AuthApi {
+            paths: paths.clone(),
+        });
+        let store = Arc::new(crate::token_store::MemoryTokenStore::default());
+        let now = now_unix_secs();
+        store
+            .save(&StoredSession {
+                refresh_token: make_jwt(now + 3600),
+                access_token: make_jwt(now - 10),
+            })
+            .await
+            .unwrap();
+
+        let mut client = AuthClient::new_with_transport("http://api", "client", transport);
+        client.set_token_store(store.clone()).await;
+        assert!(client.restore_session().await.unwrap());
+        assert!(client.get_dds_access_token(None).await.is_ok());
+        // The expired access token was refreshed and the new session saved.
+        let saved = store.load().await.unwrap().unwrap();
+        assert_eq!(parse_jwt(&saved.refresh_token).unwrap().exp, now + 3601);
+
+        client.logout().await.unwrap();
+        assert_eq!(store.load().await.unwrap(), None);
+        assert!(client.get_dds_access_token(None).await.is_err());
+        assert!(!client.restore_session().await.unwrap());
+        assert_eq!(
+            *paths.lock().unwrap(),
+            [
+                "/user/refresh",
+                "/service/domains-access-token",
+                "/user/logout"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_logout_on_a_clone_forgets_app_credentials() {
+        let paths = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let transport = Transport::with_executor(AuthApi {
+            paths: paths.clone(),
+        });
+        let mut client = AuthClient::new_with_transport("http://api", "client", transport);
+        client
+            .sign_in_with_app_credentials("key", "secret")
+            .await
+            .unwrap();
+
+        // The bindings log out through a clone of the client they wrap.
+        client.clone().logout().await.unwrap();
+        assert!(client.get_dds_access_token(None).await.is_err());
+        assert_eq!(*paths.lock().unwrap(), ["/service/domains-access-token"]);
+    }
+
+    #[tokio::test]
+    async fn test_restore_session_with_expired_refresh_token() {
+        let store = Arc::new(crate::token_store::MemoryTokenStore::default());
+        let expired = make_jwt(now_unix_secs() - 10);
+        store
+            .save(&StoredSession {
+                refresh_token: expired.clone(),
+                access_token: expired,
+            })
+            .await
+            .unwrap();
+        let mut client = AuthClient::new("http://api", "client");
+        client.set_token_store(store.clone()).await;
+        assert!(!client.restore_session().await.unwrap());
+        assert_eq!(store.load().await.unwrap(), None);
+    }
 }
@@ -0,0 +1,255 @@
+//! Deleting many items of a domain at once.
+//!
+//! Items are picked by id or by a [`DownloadQuery`], looked up first so the report can say what
+//! was deleted, and then deleted up to [`DeleteOptions::concurrency`] at a time. A failed item
+//! does not stop the others; it is reported with its error instead. With
+//! [`DeleteOptions::dry_run`] nothing is deleted and the report lists what would have been.
+
+use std::collections::BTreeSet;
+
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+
+use crate::domain_data::{
+    DomainDataMetadata, DownloadQuery, ID_BATCH, delete_by_id, download_metadata_v1,
+};
+use crate::errors::DomainError;
+use crate::transport::Transport;
+
+/// Deletes sent at once when the caller has no better idea.
+pub const DEFAULT_DELETE_CONCURRENCY: usize = 8;
+
+#[derive(Debug, Clone, Copy)]
+pub struct DeleteOptions {
+    /// Only look up the items, without deleting them.
+    pub dry_run: bool,
+    /// Deletes sent at once.
+    pub concurrency: usize,
+}
+
+impl Default for DeleteOptions {
+    fn default() -> Self {
+        Self {
+            dry_run: false,
+            concurrency: DEFAULT_DELETE_CONCURRENCY,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteFailure {
+    pub metadata: DomainDataMetadata,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeleteReport {
+    pub dry_run: bool,
+    /// Metadata of the items deleted, or that would be deleted in a dry run.
+    pub deleted: Vec<DomainDataMetadata>,
+    pub failed: Vec<DeleteFailure>,
+    /// Requested ids that were not in the domain, or were gone by the time they were deleted.
+    pub missing: Vec<String>,
+}
+
+/// Deletes the items `ids` of the domain.
+pub async fn delete_v1_many(
+    transport: &Transport,
+    url: &str,
+    client_id: &str,
+    access_token: &str,
+    domain_id: &str,
+    ids: &[String],
+    options: &DeleteOptions,
+) -> Result<DeleteReport, DomainError> {
+    let ids: Vec<String> = ids
+        .iter()
+        .cloned()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let mut found = Vec::new();
+    for batch in ids.chunks(ID_BATCH) {
+        let query = DownloadQuery {
+            ids: batch.to_vec(),
+            name: None,
+            data_type: None,
+        };
+        found.extend(
+            download_metadata_v1(transport, url, client_id, access_token, domain_id, &query)
+                .await?
+                .into_iter()
+                .filter(|meta| batch.contains(&meta.id)),
+        );
+    }
+    let missing: Vec<String> = ids
+        .into_iter()
+        .filter(|id| !found.iter().any(|meta| &meta.id == id))
+        .collect();
+
+    let mut report = delete_all(transport, url, access_token, domain_id, found, options).await;
+    report.missing.splice(0..0, missing);
+    Ok(report)
+}
+
+/// Deletes every item of the domain matching `query`. A query without ids, name or data type
+/// is refused rather than clearing the domain.
+pub async fn delete_v1_by_query(
+    transport: &Transport,
+    url: &str,
+    client_id: &str,
+    access_token: &str,
+    domain_id: &str,
+    query: &DownloadQuery,
+    options: &DeleteOptions,
+) -> Result<DeleteReport, DomainError> {
+    if query.ids.is_empty() && query.name.is_none() && query.data_type.is_none() {
+        return Err(DomainError::InvalidRequest(
+            "Refusing to delete by a query that matches all domain data.",
+        ));
+    }
+    let found =
+        download_metadata_v1(transport, url, client_id, access_token, domain_id, query).await?;
+    Ok(delete_all(transport, url, access_token, domain_id, found, options).await)
+}
+
+async fn delete_all(
+    transport: &Transport,
+    url: &str,
+    access_token: &str,
+    domain_id: &str,
+    items: Vec<DomainDataMetadata>,
+    options: &DeleteOptions,
+) -> DeleteReport {
+    let mut report = DeleteReport {
+        dry_run: options.dry_run,
+        ..Default::default()
+    };
+    if options.dry_run {
+        report.deleted = items;
+        return report;
+    }
+
+    let mut results = futures::stream::iter(items)
+        .map(|meta| async move {
+            let result = delete_by_id(transport, url, access_token, domain_id, &meta.id).await;
+            (meta, result)
+        })
+        .buffer_unordered(options.concurrency.max(1));
+    while let Some((meta, result)) = results.next().await {
+        match result {
+            Ok(()) => report.deleted.push(meta),
+            Err(DomainError::NotFound(_)) => report.missing.push(meta.id),
+            Err(e) => report.failed.push(DeleteFailure {
+                metadata: meta,
+                error: e.to_string(),
+            }),
+        }
+    }
+    report
+}
+
+#[cfg(not(target_family = "wasm"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    use crate::transport::test_support::json_response;
+    use crate::transport::{ExecuteFuture, HttpExecutor};
+
+    fn meta(id: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": id, "domain_id": "dom", "name": format!("scan-{id}"), "data_type": "ply",
+            "size": 4, "created_at": "2025-01-01T00:00:00Z", "updated_at": "2025-01-01T00:00:00Z",
+        })
+    }
+
+    /// Domain server holding items `1`, `2` and `3`; deleting `3` fails. Records deleted ids.
+    #[derive(Default)]
+    struct Server(Arc<Mutex<Vec<String>>>);
+
+    impl HttpExecutor for Server {
+        fn execute(&self, request: reqwest::Request) -> ExecuteFuture {
+            let path = request.url().path().to_string();
+            let (status, body) = if request.method() == reqwest::Method::GET {
+                let data = [meta("1"), meta("2"), meta("3")];
+                (200, serde_json::json!({ "data": data }))
+            } else if path.ends_with("/3") {
+                (403, serde_json::json!({ "message": "read only" }))
+            } else {
+                let id = path.rsplit('/').next().unwrap().to_string();
+                self.0.lock().unwrap().push(id);
+                (200, serde_json::json!({}))
+            };
+            let res = json_response(status, &body);
+            Box::pin(async move { Ok(res) })
+        }
+    }
+
+    fn ids(metas: &[DomainDataMetadata]) -> Vec<&str> {
+        let mut ids: Vec<_> = metas.iter().map(|m| m.id.as_str()).collect();
+        ids.sort();
+        ids
+    }
+
+    #[tokio::test]
+    async fn reports_each_item() {
+        let deleted = Arc::new(Mutex::new(Vec::new()));
+        let transport = Transport::with_executor(Server(deleted.clone()));
+        let requested = ["2", "3", "9"].map(String::from);
+        let delete = |dry_run| {
+            let options = DeleteOptions {
+                dry_run,
+                concurrency: 2,
+            };
+            let (transport, requested) = (&transport, &requested);
+            async move {
+                delete_v1_many(
+                    transport,
+                    "http://domain",
+                    "client",
+                    "token",
+                    "dom",
+                    requested,
+                    &options,
+                )
+                .await
+                .unwrap()
+            }
+        };
+
+        let report = delete(true).await;
+        assert_eq!(ids(&report.deleted), ["2", "3"]);
+        assert_eq!(report.missing, ["9"]);
+        assert!(deleted.lock().unwrap().is_empty());
+
+        let report = delete(false).await;
+        assert_eq!(ids(&report.deleted), ["2"]);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].metadata.id, "3");
+        assert_eq!(report.missing, ["9"]);
+        assert_eq!(*deleted.lock().unwrap(), ["2"]);
+    }
+
+    #[tokio::test]
+    async fn refuses_unfiltered_query() {
+        let transport = Transport::with_executor(Server::default());
+        let query = DownloadQuery {
+            ids: vec![],
+            name: None,
+            data_type: None,
+        };
+        let result = delete_v1_by_query(
+            &transport,
+            "http://domain",
+            "client",
+            "token",
+            "dom",
+            &query,
+            &DeleteOptions::default(),
+        )
+        .await;
+        assert!(matches!(result, Err(DomainError::InvalidRequest(_))));
+    }
+}
@@ -0,0 +1,235 @@
+//! Uploads that skip items whose content is already on the server.
+//!
+//! Every item is hashed locally (SHA-256) and compared with the `hash` the server reports in
+//! its metadata: for updates, the item with that id; for creates, the items with the same name
+//! and data type. Matching items are reported as skipped instead of being sent again. Items the
+//! server reports no hash for are always uploaded.
+
+use std::collections::{BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::domain_data::{
+    DomainAction, DomainDataMetadata, DownloadQuery, ID_BATCH, UploadDomainData,
+    download_metadata_v1, upload_v1,
+};
+use crate::errors::DomainError;
+use crate::transport::Transport;
+
+pub(crate) fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+/// Server hashes may carry a `sha256:` prefix.
+pub(crate) fn same_hash(remote: Option<&str>, local: &str) -> bool {
+    remote.is_some_and(|h| {
+        h.strip_prefix("sha256:")
+            .unwrap_or(h)
+            .eq_ignore_ascii_case(local)
+    })
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UploadReport {
+    /// Metadata returned by the server for the items that were sent.
+    pub uploaded: Vec<DomainDataMetadata>,
+    /// Existing metadata of the items that were skipped because their content was unchanged.
+    pub skipped: Vec<DomainDataMetadata>,
+}
+
+/// Same as [`upload_v1`], but skips items whose content the server already has.
+pub async fn upload_v1_dedup(
+    transport: &Transport,
+    url: &str,
+    client_id: &str,
+    access_token: &str,
+    domain_id: &str,
+    data: Vec<UploadDomainData>,
+) -> Result<UploadReport, DomainError> {
+    let mut ids = BTreeSet::new();
+    let mut names = BTreeSet::new();
+    for datum in &data {
+        match &datum.action {
+            DomainAction::Update { id } => ids.insert(id.clone()),
+            DomainAction::Create { name, data_type } => {
+                names.insert((name.clone(), data_type.clone()))
+            }
+        };
+    }
+
+    let ids: Vec<String> = ids.into_iter().collect();
+    let mut queries: Vec<DownloadQuery> = ids
+        .chunks(ID_BATCH)
+        .map(|batch| DownloadQuery {
+            ids: batch.to_vec(),
+            name: None,
+            data_type: None,
+        })
+        .collect();
+    // Only the items sharing a name and data type with a create can match it, so ask for those
+    // rather than listing the whole data type.
+    queries.extend(names.into_iter().map(|(name, data_type)| DownloadQuery {
+        ids: vec![],
+        name: Some(name),
+        data_type: Some(data_type),
+    }));
+
+    let mut by_id = HashMap::new();
+    let mut by_name: HashMap<(String, String), Vec<DomainDataMetadata>> = HashMap::new();
+    for query in &queries {
+        let existing =
+            download_metadata_v1(transport, url, client_id, access_token, domain_id, query).await?;
+        for meta in existing {
+            by_name
+                .entry((meta.name.clone(), meta.data_type.clone()))
+                .or_default()
+                .push(meta.clone());
+            by_id.insert(meta.id.clone(), meta);
+        }
+    }
+
+    let mut report = UploadReport::default();
+    let mut to_upload = Vec::new();
+    for datum in data {
+        let hash = sha256_hex(&datum.data);
+        let unchanged = match &datum.action {
+            DomainAction::Update { id } => by_id
+                .get(id)
+                .filter(|meta| same_hash(meta.hash.as_deref(), &hash)),
+            DomainAction::Create { name, data_type } => by_name
+                .get(&(name.clone(), data_type.clone()))
+                .and_then(|metas| {
+                    metas
+                        .iter()
+                        .find(|meta| same_hash(meta.hash.as_deref(), &hash))
+                }),
+        };
+        match unchanged {
+            Some(meta) => report.skipped.push(meta.clone()),
+            None => to_upload.push(datum),
+        }
+    }
+
+    if !to_upload.is_empty() {
+        report.uploaded = upload_v1(transport, url, access_token, domain_id, to_upload).await?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    use crate::transport::test_support::{body_bytes, json_response};
+    use crate::transport::{ExecuteFuture, HttpExecutor};
+
+    /// Domain server holding `scan.ply` and `mesh.obj`; records the queries of listings and the
+    /// bodies of uploads.
+    #[derive(Default)]
+    struct Server {
+        queries: Arc<Mutex<Vec<String>>>,
+        bodies: Arc<Mutex<Vec<String>>>,
+    }
+
+    fn meta(id: &str, name: &str, data_type: &str, hash: String) -> serde_json::Value {
+        serde_json::json!({
+            "id": id, "domain_id": "dom", "name": name, "data_type": data_type, "size": 4,
+            "created_at": "2025-01-01T00:00:00Z", "updated_at": "2025-01-01T00:00:00Z",
+            "hash": hash,
+        })
+    }
+
+    impl HttpExecutor for Server {
+        fn execute(&self, mut request: reqwest::Request) -> ExecuteFuture {
+            let (status, body) = if request.url().path().ends_with("/info") {
+                (404, serde_json::json!({}))
+            } else if request.method() == reqwest::Method::GET {
+                let query: HashMap<_, _> = request.url().query_pairs().into_owned().collect();
+                self.queries
+                    .lock()
+                    .unwrap()
+                    .push(request.url().query().unwrap_or_default().to_string());
+                let data: Vec<_> = [
+                    meta("1", "scan", "ply", sha256_hex(b"same")),
+                    meta("2", "mesh", "obj", format!("sha256:{}", sha256_hex(b"old"))),
+                ]
+                .into_iter()
+                .filter(|meta| {
+                    query.get("name").is_none_or(|name| meta["name"] == *name)
+                        && query
+                            .get("ids")
+                            .is_none_or(|ids| ids.split(',').any(|id| meta["id"] == id))
+                })
+                .collect();
+                (200, serde_json::json!({ "data": data }))
+            } else {
+                let body = body_bytes(&mut request);
+                self.bodies
+                    .lock()
+                    .unwrap()
+                    .push(String::from_utf8_lossy(&body).into_owned());
+                let data = [meta("3", "new", "ply", String::new())];
+                (200, serde_json::json!({ "data": data }))
+            };
+            let res = json_response(status, &body);
+            Box::pin(async move { Ok(res) })
+        }
+    }
+
+    fn create(name: &str, data_type: &str, data: &[u8]) -> UploadDomainData {
+        UploadDomainData {
+            action: DomainAction::Create {
+                name: name.into(),
+                data_type: data_type.into(),
+            },
+            data: data.to_vec(),
+        }
+    }
+
+    #[tokio::test]
+    async fn skips_unchanged_items() {
+        let server = Server::default();
+        let (queries, bodies) = (server.queries.clone(), server.bodies.clone());
+        let transport = Transport::with_executor(server);
+        let data = vec![
+            create("scan", "ply", b"same"),
+            UploadDomainData {
+                action: DomainAction::Update { id: "2".into() },
+                data: b"new!".to_vec(),
+            },
+            create("other", "ply", b"same"),
+        ];
+
+        let report = upload_v1_dedup(&transport, "http://domain", "client", "token", "dom", data)
+            .await
+            .unwrap();
+
+        let skipped: Vec<_> = report.skipped.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(skipped, ["1"]);
+        let queries = queries.lock().unwrap();
+        assert_eq!(queries.len(), 3);
+        assert!(queries.iter().any(|q| q == "ids=2"));
+        for name in ["name=scan", "name=other"] {
+            assert!(
+                queries
+                    .iter()
+                    .any(|q| q.contains(name) && q.contains("data_type=ply"))
+            );
+        }
+        let bodies = bodies.lock().unwrap();
+        assert_eq!(bodies.len(), 2);
+        assert!(bodies.iter().any(|b| b.contains("\"other\"")));
+        assert!(bodies.iter().all(|b| !b.contains("\"scan\"")));
+        assert!(bodies.iter().any(|b| b.contains("new!")));
+    }
+
+    #[test]
+    fn compares_prefixed_hashes() {
+        let local = sha256_hex(b"x");
+        assert!(same_hash(Some(&format!("sha256:{local}")), &local));
+        assert!(same_hash(Some(&local.to_uppercase()), &local));
+        assert!(!same_hash(None, &local));
+    }
+}
@@ -1,8 +1,15 @@
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
 
 use futures::lock::Mutex;
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use reqwest::{Method, RequestBuilder, Response};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 
 #[cfg(not(target_family = "wasm"))]
 use tokio::spawn;
@@ -18,6 +25,10 @@ use tokio::time::sleep;
 use crate::{
     auth::{AuthClient, REFRESH_CACHE_TIME, TokenCache, get_cached_or_fresh_token, parse_jwt},
     errors::{AukiErrorResponse, DomainError},
+    oidc::{OidcTokenProvider, OidcTokenSource},
+    token_refresh::{DEFAULT_MAX_CACHED_DOMAINS, TokenRefreshOptions},
+    token_store::TokenStore,
+    transport::{SendVia, Transport},
 };
 pub const ALL_DOMAINS_ORG: &str = "all";
 pub const OWN_DOMAINS_ORG: &str = "own";
@@ -59,13 +70,136 @@ pub struct DomainWithServer {
     pub domain_server: DomainServer,
 }
 
+// Bounds of the wait between two runs of the token refresher.
+const MIN_REFRESH_INTERVAL: u64 = 10;
+const MAX_REFRESH_INTERVAL: u64 = 60;
+
+// Domain tokens by domain id, dropping the least recently used beyond `capacity`.
+#[derive(Debug)]
+struct DomainCache {
+    entries: HashMap<String, CachedDomain>,
+    capacity: usize,
+    // Bumped on every use to order entries, as seconds are too coarse.
+    clock: u64,
+}
+
+#[derive(Debug)]
+struct CachedDomain {
+    domain: DomainWithToken,
+    last_used: u64,
+    used_at: u64,
+}
+
+impl DomainCache {
+    fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            capacity: DEFAULT_MAX_CACHED_DOMAINS,
+            clock: 0,
+        }
+    }
+
+    fn get(&mut self, domain_id: &str) -> Option<DomainWithToken> {
+        self.clock += 1;
+        let entry = self.entries.get_mut(domain_id)?;
+        entry.last_used = self.clock;
+        entry.used_at = now_unix_secs();
+        Some(entry.domain.clone())
+    }
+
+    fn peek(&self, domain_id: &str) -> Option<DomainWithToken> {
+        self.entries
+            .get(domain_id)
+            .map(|entry| entry.domain.clone())
+    }
+
+    fn insert(&mut self, domain: DomainWithToken) {
+        self.clock += 1;
+        self.entries.insert(
+            domain.domain.id.clone(),
+            CachedDomain {
+                domain,
+                last_used: self.clock,
+                used_at: now_unix_secs(),
+            },
+        );
+        self.evict();
+    }
+
+    // Replaces a renewed token without counting it as a use. Domains evicted meanwhile stay out.
+    fn renew(&mut self, domain: DomainWithToken) {
+        if let Some(entry) = self.entries.get_mut(&domain.domain.id) {
+            entry.domain = domain;
+        }
+    }
+
+    fn remove(&mut self, domain_id: &str) {
+        self.entries.remove(domain_id);
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        while self.entries.len() > self.capacity {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(id, _)| id.clone())
+            else {
+                return;
+            };
+            self.entries.remove(&oldest);
+        }
+    }
+
+    fn evict_idle(&mut self, unused_since: u64) {
+        self.entries
+            .retain(|_, entry| entry.used_at >= unused_since);
+    }
+
+    fn next_expiry(&self) -> Option<u64> {
+        self.entries
+            .values()
+            .map(|entry| entry.domain.expires_at)
+            .min()
+    }
+
+    fn expiring_before(&self, time: u64) -> Vec<String> {
+        self.entries
+            .iter()
+            .filter(|(_, entry)| entry.domain.expires_at <= time)
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct DiscoveryService {
     dds_url: String,
-    client: Client,
-    cache: Arc<Mutex<HashMap<String, DomainWithToken>>>,
+    transport: Transport,
+    cache: Arc<Mutex<DomainCache>>,
     api_client: AuthClient,
-    oidc_access_token: Option<String>,
+    // Shared by all clones so logging out on one clears it for every clone.
+    oidc: Arc<std::sync::Mutex<OidcLogin>>,
+    // Bumped on every sign-in and logout so a remember-password loop of an older session stops.
+    session: Arc<AtomicU64>,
+    // Bumped when the token refresher is started or stopped so an older one stops.
+    refresher: Arc<AtomicU64>,
+}
+
+/// The OIDC access token, or the provider asked for one, that a client signs in with.
+#[derive(Debug, Clone, Default)]
+struct OidcLogin {
+    access_token: Option<String>,
+    token_source: Option<OidcTokenSource>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -73,6 +207,45 @@ pub struct ListDomainsResponse {
     pub domains: Vec<DomainWithServer>,
 }
 
+/// Changes to a domain; fields left `None` are kept.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateDomainRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// An empty string removes the redirect URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirect_url: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct MoveDomainRequest<'a> {
+    domain_server_id: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListDomainServersResponse {
+    domain_servers: Vec<DomainServer>,
+}
+
+/// A portal (lighthouse) attached to a domain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Portal {
+    pub id: String,
+    /// The id printed on the physical portal.
+    pub short_id: String,
+    #[serde(default)]
+    pub organization_id: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListPortalsResponse {
+    lighthouses: Vec<Portal>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct CreateDomainRequest {
     pub name: String,
@@ -102,17 +275,42 @@ fn get_mac_address() -> Result<String, DomainError> {
 
 impl DiscoveryService {
     pub fn new(api_url: &str, dds_url: &str, client_id: &str) -> Self {
-        let api_client = AuthClient::new(api_url, client_id);
+        Self::new_with_transport(api_url, dds_url, client_id, Transport::default())
+    }
+
+    /// Same as [`DiscoveryService::new`], sharing `transport` with the DDS and auth clients.
+    pub fn new_with_transport(
+        api_url: &str,
+        dds_url: &str,
+        client_id: &str,
+        transport: Transport,
+    ) -> Self {
+        let api_client = AuthClient::new_with_transport(api_url, client_id, transport.clone());
 
         Self {
             dds_url: dds_url.to_string(),
-            client: Client::new(),
-            cache: Arc::new(Mutex::new(HashMap::new())),
+            transport,
+            cache: Arc::new(Mutex::new(DomainCache::new())),
             api_client,
-            oidc_access_token: None,
+            oidc: Arc::default(),
+            session: Arc::new(AtomicU64::new(0)),
+            refresher: Arc::new(AtomicU64::new(0)),
         }
     }
 
+    pub(crate) fn set_transport(&mut self, transport: Transport) {
+        self.api_client.set_transport(transport.clone());
+        self.transport = transport;
+    }
+
+    fn oidc_login(&self) -> OidcLogin {
+        self.oidc.lock().unwrap().clone()
+    }
+
+    fn clear_oidc_login(&self) {
+        *self.oidc.lock().unwrap() = OidcLogin::default();
+    }
+
     /// List domains with domain server without issue token
     ///
     /// - org: (required) The organization to list domains from:
@@ -135,10 +333,7 @@ impl DiscoveryService {
         org: &str,
         domain_server_id: Option<&str>,
     ) -> Result<ListDomainsResponse, DomainError> {
-        let access_token = self
-            .api_client
-            .get_dds_access_token(self.oidc_access_token.as_deref())
-            .await?;
+        let access_token = self.get_dds_access_token().await?;
         let mut url = format!(
             "{}/api/v1/domains?org={}&with=domain_server",
             self.dds_url, org
@@ -147,7 +342,7 @@ impl DiscoveryService {
             url.push_str(&format!("&domain_server_id={}", domain_server_id));
         }
         let response = self
-            .client
+            .transport
             .get(&url)
             .bearer_auth(access_token)
             .header("Content-Type", "application/json")
@@ -157,23 +352,18 @@ impl DiscoveryService {
                 "posemesh-gateway-mac",
                 get_mac_address().unwrap_or_default(),
             )
-            .send()
+            .send_via(&self.transport)
             .await?;
 
         if response.status().is_success() {
             let domain_servers: ListDomainsResponse = response.json().await?;
             Ok(domain_servers)
         } else {
-            let status = response.status();
-            let text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            Err(AukiErrorResponse {
-                status,
-                error: format!("Failed to list domains. {}", text),
-            }
-            .into())
+            Err(
+                AukiErrorResponse::from_response(response, "Failed to list domains.")
+                    .await
+                    .into(),
+            )
         }
     }
 
@@ -184,14 +374,16 @@ impl DiscoveryService {
         remember_password: bool,
     ) -> Result<String, DomainError> {
         self.cache.lock().await.clear();
-        self.oidc_access_token = None;
+        self.clear_oidc_login();
+        let session = self.session.fetch_add(1, Ordering::SeqCst) + 1;
         let token = self.api_client.user_login(email, password).await?;
         if remember_password {
             let mut api_client = self.api_client.clone();
+            let current_session = self.session.clone();
             let email = email.to_string();
             let password = password.to_string();
             spawn(async move {
-                loop {
+                while current_session.load(Ordering::SeqCst) == session {
                     let expires_at = api_client
                         .get_expires_at()
                         .await
@@ -211,6 +403,9 @@ impl DiscoveryService {
                             tracing::info!("Refreshing token in {} seconds", expiration.as_secs());
                             sleep(expiration).await;
                         }
+                        if current_session.load(Ordering::SeqCst) != session {
+                            break;
+                        }
 
                         let _ = api_client
                             .user_login(&email, &password)
@@ -229,69 +424,160 @@ impl DiscoveryService {
         app_secret: &str,
     ) -> Result<String, DomainError> {
         self.cache.lock().await.clear();
-        self.oidc_access_token = None;
+        self.clear_oidc_login();
+        self.session.fetch_add(1, Ordering::SeqCst);
         self.api_client
             .sign_in_with_app_credentials(app_key, app_secret)
             .await
     }
 
+    /// Persists the user session to `store` from now on.
+    pub async fn set_token_store(&mut self, store: Arc<dyn TokenStore>) {
+        self.api_client.set_token_store(store).await;
+    }
+
+    /// Signs in with the session saved in the token store, see [`AuthClient::restore_session`].
+    pub async fn restore_session(&mut self) -> Result<bool, DomainError> {
+        self.cache.lock().await.clear();
+        self.clear_oidc_login();
+        self.session.fetch_add(1, Ordering::SeqCst);
+        self.api_client.restore_session().await
+    }
+
+    /// Revokes the user session and clears every cached token, see [`AuthClient::logout`].
+    pub async fn logout(&mut self) -> Result<(), DomainError> {
+        self.cache.lock().await.clear();
+        self.clear_oidc_login();
+        self.session.fetch_add(1, Ordering::SeqCst);
+        self.api_client.logout().await
+    }
+
     pub fn with_oidc_access_token(&self, oidc_access_token: &str) -> Self {
-        if let Some(cached_oidc_access_token) = self.oidc_access_token.as_deref()
-            && cached_oidc_access_token == oidc_access_token
-        {
+        if self.oidc_login().access_token.as_deref() == Some(oidc_access_token) {
             return self.clone();
         }
         Self {
             dds_url: self.dds_url.clone(),
-            client: self.client.clone(),
-            cache: Arc::new(Mutex::new(HashMap::new())),
-            api_client: AuthClient::new(&self.api_client.api_url, &self.api_client.client_id),
-            oidc_access_token: Some(oidc_access_token.to_string()),
+            transport: self.transport.clone(),
+            cache: Arc::new(Mutex::new(DomainCache::new())),
+            api_client: AuthClient::new_with_transport(
+                &self.api_client.api_url,
+                &self.api_client.client_id,
+                self.transport.clone(),
+            ),
+            oidc: Arc::new(std::sync::Mutex::new(OidcLogin {
+                access_token: Some(oidc_access_token.to_string()),
+                token_source: None,
+            })),
+            session: Arc::new(AtomicU64::new(0)),
+            refresher: Arc::new(AtomicU64::new(0)),
         }
     }
 
-    pub async fn auth_domain(&self, domain_id: &str) -> Result<DomainWithToken, DomainError> {
-        let access_token = self
+    /// Like [`DiscoveryService::with_oidc_access_token`], asking `provider` for a new token when
+    /// the current one is about to expire or is rejected.
+    pub fn with_oidc_token_provider(&self, provider: OidcTokenProvider) -> Self {
+        Self {
+            dds_url: self.dds_url.clone(),
+            transport: self.transport.clone(),
+            cache: Arc::new(Mutex::new(DomainCache::new())),
+            api_client: AuthClient::new_with_transport(
+                &self.api_client.api_url,
+                &self.api_client.client_id,
+                self.transport.clone(),
+            ),
+            oidc: Arc::new(std::sync::Mutex::new(OidcLogin {
+                access_token: None,
+                token_source: Some(OidcTokenSource::new(provider)),
+            })),
+            session: Arc::new(AtomicU64::new(0)),
+            refresher: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Drops the OIDC token and the domain tokens derived from it after a 401, so the next call
+    /// asks the provider again. Returns `false` when there is no provider and retrying is useless.
+    pub(crate) async fn invalidate_oidc_token(&self) -> bool {
+        let Some(source) = self.oidc_login().token_source else {
+            return false;
+        };
+        source.invalidate().await;
+        self.cache.lock().await.clear();
+        true
+    }
+
+    // Get DDS access token with the current credentials. With an OIDC token provider, a rejected token is replaced once before giving up.
+    async fn get_dds_access_token(&self) -> Result<String, DomainError> {
+        let oidc = self.oidc_login();
+        let Some(source) = oidc.token_source else {
+            return self
+                .api_client
+                .get_dds_access_token(oidc.access_token.as_deref())
+                .await;
+        };
+        let oidc_access_token = source.token().await?;
+        match self
             .api_client
-            .get_dds_access_token(self.oidc_access_token.as_deref())
-            .await?;
-        // Check cache first
-        let cache = if let Some(cached_domain) = self.cache.lock().await.get(domain_id) {
-            cached_domain.clone()
-        } else {
-            DomainWithToken {
-                domain: DomainWithServer {
-                    id: domain_id.to_string(),
-                    name: "".to_string(),
+            .get_dds_access_token(Some(&oidc_access_token))
+            .await
+        {
+            Err(e) if e.status() == Some(reqwest::StatusCode::UNAUTHORIZED) => {
+                source.invalidate().await;
+                let oidc_access_token = source.token().await?;
+                self.api_client
+                    .get_dds_access_token(Some(&oidc_access_token))
+                    .await
+            }
+            result => result,
+        }
+    }
+
+    pub async fn auth_domain(&self, domain_id: &str) -> Result<DomainWithToken, DomainError> {
+        let cached = self.cache.lock().await.get(domain_id);
+        let domain = self.fresh_domain_token(domain_id, cached).await?;
+        self.cache.lock().await.insert(domain.clone());
+        Ok(domain)
+    }
+
+    // Returns `cached` unless it is missing or about to expire, in which case a new token is fetched.
+    async fn fresh_domain_token(
+        &self,
+        domain_id: &str,
+        cached: Option<DomainWithToken>,
+    ) -> Result<DomainWithToken, DomainError> {
+        let access_token = self.get_dds_access_token().await?;
+        let cache = cached.unwrap_or_else(|| DomainWithToken {
+            domain: DomainWithServer {
+                id: domain_id.to_string(),
+                name: "".to_string(),
+                organization_id: "".to_string(),
+                domain_server_id: "".to_string(),
+                redirect_url: None,
+                domain_server: DomainServer {
+                    id: "".to_string(),
                     organization_id: "".to_string(),
-                    domain_server_id: "".to_string(),
-                    redirect_url: None,
-                    domain_server: DomainServer {
-                        id: "".to_string(),
-                        organization_id: "".to_string(),
-                        name: "".to_string(),
-                        url: "".to_string(),
-                    },
+                    name: "".to_string(),
+                    url: "".to_string(),
                 },
-                expires_at: 0,
-                access_token: "".to_string(),
-            }
-        };
+            },
+            expires_at: 0,
+            access_token: "".to_string(),
+        });
 
-        let cached = get_cached_or_fresh_token(&cache, || {
-            let client = self.client.clone();
+        get_cached_or_fresh_token(&cache, self.api_client.refresh_ahead(), || {
+            let transport = self.transport.clone();
             let dds_url = self.dds_url.clone();
             let client_id = self.api_client.client_id.clone();
             async move {
                 let mac_address = get_mac_address().unwrap_or_default();
-                let response = client
+                let response = transport
                     .post(format!("{}/api/v1/domains/{}/auth", dds_url, domain_id))
                     .bearer_auth(access_token)
                     .header("Content-Type", "application/json")
                     .header("posemesh-client-id", client_id)
                     .header("posemesh-sdk-version", crate::VERSION)
                     .header("posemesh-gateway-mac", mac_address)
-                    .send()
+                    .send_via(&transport)
                     .await?;
 
                 if response.status().is_success() {
@@ -300,25 +586,89 @@ impl DiscoveryService {
                         parse_jwt(&domain_with_token.get_access_token())?.exp;
                     Ok(domain_with_token)
                 } else {
-                    let status = response.status();
-                    let text = response
-                        .text()
-                        .await
-                        .unwrap_or_else(|_| "Unknown error".to_string());
-                    Err(AukiErrorResponse {
-                        status,
-                        error: format!("Failed to auth domain. {}", text),
-                    }
-                    .into())
+                    Err(
+                        AukiErrorResponse::from_response(response, "Failed to auth domain.")
+                            .await
+                            .into(),
+                    )
                 }
             }
         })
-        .await?;
+        .await
+    }
+
+    /// Renews the DDS token and the tokens of recently used domains in the background,
+    /// `options.refresh_ahead` before they expire, until [`DiscoveryService::stop_token_refresh`]
+    /// is called or every clone of this service is dropped. Replaces a refresher started before.
+    pub async fn start_token_refresh(&self, options: TokenRefreshOptions) {
+        let generation = self.refresher.fetch_add(1, Ordering::SeqCst) + 1;
+        self.cache.lock().await.set_capacity(options.max_domains);
+        self.api_client
+            .set_refresh_ahead(options.refresh_ahead.as_secs());
 
-        // Cache the result
-        let mut cache = self.cache.lock().await;
-        cache.insert(domain_id.to_string(), cached.clone());
-        Ok(cached)
+        let service = self.clone();
+        spawn(async move {
+            loop {
+                let delay = service
+                    .next_refresh_at()
+                    .await
+                    .map_or(MAX_REFRESH_INTERVAL, |at| {
+                        at.saturating_sub(now_unix_secs())
+                    })
+                    .clamp(MIN_REFRESH_INTERVAL, MAX_REFRESH_INTERVAL);
+                sleep(Duration::from_secs(delay)).await;
+                // The loop holds the last clone once the service is dropped everywhere else.
+                if service.refresher.load(Ordering::SeqCst) != generation
+                    || Arc::strong_count(&service.refresher) == 1
+                {
+                    break;
+                }
+                service.refresh_tokens(options.idle_timeout).await;
+            }
+        });
+    }
+
+    /// Stops the background refresher; tokens are renewed on use again.
+    pub fn stop_token_refresh(&self) {
+        self.refresher.fetch_add(1, Ordering::SeqCst);
+        self.api_client.set_refresh_ahead(REFRESH_CACHE_TIME);
+    }
+
+    // When the first cached token enters the refresh window, if any token is cached.
+    async fn next_refresh_at(&self) -> Option<u64> {
+        let dds = self.api_client.dds_expires_at().await;
+        let domains = self.cache.lock().await.next_expiry();
+        let expires_at = dds.into_iter().chain(domains).min()?;
+        Some(expires_at.saturating_sub(self.api_client.refresh_ahead()))
+    }
+
+    // Drops idle domains and renews the tokens that expire soon. Failures are left to the next call.
+    async fn refresh_tokens(&self, idle_timeout: Duration) {
+        let now = now_unix_secs();
+        let renew_before = now + self.api_client.refresh_ahead();
+        if self
+            .api_client
+            .dds_expires_at()
+            .await
+            .is_some_and(|exp| exp <= renew_before)
+            && let Err(e) = self.get_dds_access_token().await
+        {
+            tracing::warn!("Failed to renew DDS access token: {}", e);
+            return;
+        }
+
+        let expiring = {
+            let mut cache = self.cache.lock().await;
+            cache.evict_idle(now.saturating_sub(idle_timeout.as_secs()));
+            cache.expiring_before(renew_before)
+        };
+        for domain_id in expiring {
+            let cached = self.cache.lock().await.peek(&domain_id);
+            match self.fresh_domain_token(&domain_id, cached).await {
+                Ok(domain) => self.cache.lock().await.renew(domain),
+                Err(e) => tracing::warn!("Failed to renew token of domain {}: {}", domain_id, e),
+            }
+        }
     }
 
     pub async fn create_domain(
@@ -335,12 +685,9 @@ impl DiscoveryService {
                 "domain_server_id or domain_server_url is required",
             ));
         }
-        let access_token: String = self
-            .api_client
-            .get_dds_access_token(self.oidc_access_token.as_deref())
-            .await?;
+        let access_token: String = self.get_dds_access_token().await?;
         let response = self
-            .client
+            .transport
             .post(format!("{}/api/v1/domains?issue_token=true", self.dds_url))
             .bearer_auth(access_token)
             .header("Content-Type", "application/json")
@@ -356,30 +703,21 @@ impl DiscoveryService {
                 redirect_url,
                 domain_server_url: domain_server_url.to_string(),
             })
-            .send()
+            .send_via(&self.transport)
             .await?;
 
         if response.status().is_success() {
             let mut domain_with_token: DomainWithToken = response.json().await?;
             domain_with_token.expires_at = parse_jwt(&domain_with_token.get_access_token())?.exp;
             // Cache the result
-            let mut cache = self.cache.lock().await;
-            cache.insert(
-                domain_with_token.domain.id.clone(),
-                domain_with_token.clone(),
-            );
+            self.cache.lock().await.insert(domain_with_token.clone());
             Ok(domain_with_token)
         } else {
-            let status = response.status();
-            let text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            Err(AukiErrorResponse {
-                status,
-                error: format!("Failed to create domain. {}", text),
-            }
-            .into())
+            Err(
+                AukiErrorResponse::from_response(response, "Failed to create domain.")
+                    .await
+                    .into(),
+            )
         }
     }
 
@@ -393,10 +731,7 @@ impl DiscoveryService {
         portal_short_id: Option<&str>,
         org: &str,
     ) -> Result<ListDomainsResponse, DomainError> {
-        let access_token: String = self
-            .api_client
-            .get_dds_access_token(self.oidc_access_token.as_deref())
-            .await?;
+        let access_token: String = self.get_dds_access_token().await?;
         if portal_id.is_none() && portal_short_id.is_none() {
             return Err(DomainError::InvalidRequest(
                 "portal_id or portal_short_id is required",
@@ -404,7 +739,7 @@ impl DiscoveryService {
         }
         let id = portal_id.or(portal_short_id).unwrap();
         let response = self
-            .client
+            .transport
             .get(format!(
                 "{}/api/v1/lighthouses/{}/domains?with=domain_server,lighthouse&org={}",
                 self.dds_url, id, org
@@ -417,32 +752,152 @@ impl DiscoveryService {
                 "posemesh-gateway-mac",
                 get_mac_address().unwrap_or_default(),
             )
-            .send()
+            .send_via(&self.transport)
             .await?;
         if response.status().is_success() {
             let domains: ListDomainsResponse = response.json().await?;
             Ok(domains)
         } else {
-            let status = response.status();
-            let text = response
-                .text()
+            Err(
+                AukiErrorResponse::from_response(response, "Failed to list domains by portal.")
+                    .await
+                    .into(),
+            )
+        }
+    }
+
+    /// Starts a DDS request at `path` with the client headers and the DDS access token.
+    async fn dds_request(&self, method: Method, path: &str) -> Result<RequestBuilder, DomainError> {
+        let access_token = self.get_dds_access_token().await?;
+        Ok(self
+            .transport
+            .request(method, format!("{}{}", self.dds_url, path))
+            .bearer_auth(access_token)
+            .header("Content-Type", "application/json")
+            .header("posemesh-client-id", self.api_client.client_id.clone())
+            .header("posemesh-sdk-version", crate::VERSION)
+            .header(
+                "posemesh-gateway-mac",
+                get_mac_address().unwrap_or_default(),
+            ))
+    }
+
+    async fn read_json<T: DeserializeOwned>(
+        response: Response,
+        context: &str,
+    ) -> Result<T, DomainError> {
+        if response.status().is_success() {
+            Ok(response.json().await?)
+        } else {
+            Err(AukiErrorResponse::from_response(response, context)
                 .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            Err(AukiErrorResponse {
-                status,
-                error: format!("Failed to list domains by portal. {}", text),
-            }
-            .into())
+                .into())
         }
     }
 
+    pub async fn get_domain(&self, domain_id: &str) -> Result<DomainWithServer, DomainError> {
+        let response = self
+            .dds_request(
+                Method::GET,
+                &format!("/api/v1/domains/{}?with=domain_server", domain_id),
+            )
+            .await?
+            .send_via(&self.transport)
+            .await?;
+        Self::read_json(response, "Failed to get domain.").await
+    }
+
+    /// Renames a domain or changes its redirect URL.
+    pub async fn update_domain(
+        &self,
+        domain_id: &str,
+        update: &UpdateDomainRequest,
+    ) -> Result<DomainWithServer, DomainError> {
+        let response = self
+            .dds_request(
+                Method::PATCH,
+                &format!("/api/v1/domains/{}?with=domain_server", domain_id),
+            )
+            .await?
+            .json(update)
+            .send_via(&self.transport)
+            .await?;
+        let domain = Self::read_json(response, "Failed to update domain.").await?;
+        self.cache.lock().await.remove(domain_id);
+        Ok(domain)
+    }
+
+    /// Moves a domain and its data to another domain server. Cached access to the domain is
+    /// dropped, so later calls go to the new server.
+    pub async fn move_domain(
+        &self,
+        domain_id: &str,
+        domain_server_id: &str,
+    ) -> Result<DomainWithServer, DomainError> {
+        let response = self
+            .dds_request(
+                Method::PATCH,
+                &format!("/api/v1/domains/{}?with=domain_server", domain_id),
+            )
+            .await?
+            .json(&MoveDomainRequest { domain_server_id })
+            .send_via(&self.transport)
+            .await?;
+        let domain = Self::read_json(response, "Failed to move domain.").await?;
+        self.cache.lock().await.remove(domain_id);
+        Ok(domain)
+    }
+
+    /// Lists the domain servers available to `org`, see [`DiscoveryService::list_domains`] for
+    /// its values.
+    pub async fn list_domain_servers(&self, org: &str) -> Result<Vec<DomainServer>, DomainError> {
+        let response = self
+            .dds_request(Method::GET, &format!("/api/v1/domain_servers?org={}", org))
+            .await?
+            .send_via(&self.transport)
+            .await?;
+        let servers: ListDomainServersResponse =
+            Self::read_json(response, "Failed to list domain servers.").await?;
+        Ok(servers.domain_servers)
+    }
+
+    pub async fn get_domain_server(
+        &self,
+        domain_server_id: &str,
+    ) -> Result<DomainServer, DomainError> {
+        let response = self
+            .dds_request(
+                Method::GET,
+                &format!("/api/v1/domain_servers/{}", domain_server_id),
+            )
+            .await?
+            .send_via(&self.transport)
+            .await?;
+        Self::read_json(response, "Failed to get domain server.").await
+    }
+
+    /// Lists the portals attached to a domain.
+    pub async fn list_domain_portals(&self, domain_id: &str) -> Result<Vec<Portal>, DomainError> {
+        let response = self
+            .dds_request(
+                Method::GET,
+                &format!("/api/v1/domains/{}/lighthouses", domain_id),
+            )
+            .await?
+            .send_via(&self.transport)
+            .await?;
+        let portals: ListPortalsResponse =
+            Self::read_json(response, "Failed to list domain portals.").await?;
+        Ok(portals.lighthouses)
+    }
+
     pub(crate) async fn delete_domain(
         &self,
         access_token: &str,
         domain_id: &str,
     ) -> Result<(), DomainError> {
         let response = self
-            .client
+            .transport
             .delete(format!("{}/api/v1/domains/{}", self.dds_url, domain_id))
             .bearer_auth(access_token)
             .header("Content-Type", "application/json")
@@ -452,21 +907,156 @@ impl DiscoveryService {
                 "posemesh-gateway-mac",
                 get_mac_address().unwrap_or_default(),
             )
-            .send()
+            .send_via(&self.transport)
             .await?;
         if response.status().is_success() {
             Ok(())
         } else {
-            let status = response.status();
-            let text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            Err(AukiErrorResponse {
-                status,
-                error: format!("Failed to delete domain. {}", text),
-            }
-            .into())
+            Err(
+                AukiErrorResponse::from_response(response, "Failed to delete domain.")
+                    .await
+                    .into(),
+            )
         }
     }
 }
+
+#[cfg(not(target_family = "wasm"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+
+    use crate::transport::test_support::{dds_token, domain_auth, json_response, jwt};
+    use crate::transport::{ExecuteFuture, HttpExecutor};
+
+    /// DDS hosting `dom` on server `old` until it is moved.
+    #[derive(Default)]
+    struct Dds {
+        moved: AtomicBool,
+    }
+
+    impl HttpExecutor for Dds {
+        fn execute(&self, request: reqwest::Request) -> ExecuteFuture {
+            let server = || {
+                if self.moved.load(Ordering::SeqCst) {
+                    "new"
+                } else {
+                    "old"
+                }
+            };
+            let body = match (request.method().as_str(), request.url().path()) {
+                (_, "/service/domains-access-token") => dds_token(),
+                ("POST", "/api/v1/domains/dom/auth") | ("GET", "/api/v1/domains/dom") => {
+                    domain_auth("dom", server())
+                }
+                ("PATCH", "/api/v1/domains/dom") => {
+                    let body = request.body().and_then(|b| b.as_bytes()).unwrap();
+                    assert_eq!(body, br#"{"domain_server_id":"new"}"#);
+                    self.moved.store(true, Ordering::SeqCst);
+                    domain_auth("dom", server())
+                }
+                ("GET", "/api/v1/domains/dom/lighthouses") => serde_json::json!({
+                    "lighthouses": [{ "id": "p1", "short_id": "ABC" }],
+                }),
+                other => panic!("unexpected request {other:?}"),
+            };
+            let res = json_response(200, &body);
+            Box::pin(async move { Ok(res) })
+        }
+    }
+
+    #[tokio::test]
+    async fn move_domain_drops_cached_server() {
+        let dds = DiscoveryService::new_with_transport(
+            "http://api",
+            "http://dds",
+            "client",
+            Transport::with_executor(Dds::default()),
+        )
+        .with_oidc_access_token(&jwt());
+
+        let before = dds.auth_domain("dom").await.unwrap();
+        assert_eq!(before.domain.domain_server.url, "http://old");
+
+        let moved = dds.move_domain("dom", "new").await.unwrap();
+        assert_eq!(moved.domain_server_id, "new");
+        let after = dds.auth_domain("dom").await.unwrap();
+        assert_eq!(after.domain.domain_server.url, "http://new");
+        assert_eq!(dds.get_domain("dom").await.unwrap().domain_server_id, "new");
+
+        let portals = dds.list_domain_portals("dom").await.unwrap();
+        assert_eq!(portals[0].short_id, "ABC");
+        assert_eq!(portals[0].created_at, None);
+    }
+
+    #[tokio::test]
+    async fn logout_on_a_clone_forgets_the_oidc_token() {
+        let dds = DiscoveryService::new_with_transport(
+            "http://api",
+            "http://dds",
+            "client",
+            Transport::with_executor(Dds::default()),
+        )
+        .with_oidc_access_token(&jwt());
+        dds.auth_domain("dom").await.unwrap();
+
+        dds.clone().logout().await.unwrap();
+        assert!(dds.auth_domain("dom").await.is_err());
+    }
+
+    /// DDS authorizing any domain, counting the domain tokens it issues.
+    #[derive(Default)]
+    struct CountingDds(Arc<AtomicU64>);
+
+    impl HttpExecutor for CountingDds {
+        fn execute(&self, request: reqwest::Request) -> ExecuteFuture {
+            let path = request.url().path();
+            let body = if path == "/service/domains-access-token" {
+                dds_token()
+            } else {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                domain_auth(path.split('/').nth(4).unwrap(), "srv")
+            };
+            let res = json_response(200, &body);
+            Box::pin(async move { Ok(res) })
+        }
+    }
+
+    #[tokio::test]
+    async fn refresher_renews_tokens_and_evicts_unused_domains() {
+        let issued = Arc::new(AtomicU64::new(0));
+        let dds = DiscoveryService::new_with_transport(
+            "http://api",
+            "http://dds",
+            "client",
+            Transport::with_executor(CountingDds(issued.clone())),
+        )
+        .with_oidc_access_token(&jwt());
+        dds.start_token_refresh(TokenRefreshOptions {
+            max_domains: 2,
+            ..Default::default()
+        })
+        .await;
+
+        for id in ["a", "b", "a", "c"] {
+            dds.auth_domain(id).await.unwrap();
+        }
+        assert_eq!(issued.load(Ordering::SeqCst), 3);
+        assert!(dds.cache.lock().await.peek("b").is_none());
+
+        // Tokens valid for an hour are not renewed yet, and are once within the refresh window.
+        dds.refresh_tokens(Duration::from_secs(3600)).await;
+        assert_eq!(issued.load(Ordering::SeqCst), 3);
+        dds.api_client.set_refresh_ahead(7200);
+        dds.refresh_tokens(Duration::from_secs(3600)).await;
+        assert_eq!(issued.load(Ordering::SeqCst), 5);
+        assert!(dds.cache.lock().await.peek("a").is_some());
+
+        dds.cache.lock().await.entries.get_mut("a").unwrap().used_at = 0;
+        dds.refresh_tokens(Duration::from_secs(3600)).await;
+        assert!(dds.cache.lock().await.peek("a").is_none());
+        assert!(dds.cache.lock().await.peek("c").is_some());
+        dds.stop_token_refresh();
+    }
+}
@@ -1,16 +1,40 @@
 use crate::domain_data::{
-    DomainData, DomainDataMetadata, DownloadQuery, UploadDomainData, delete_by_id, download_by_id,
-    download_metadata_v1, download_v1_stream, upload_v1,
+    DomainAction, DomainData, DomainDataMetadata, DownloadQuery, UploadDomainData, delete_by_id,
+    download_by_id, download_metadata_v1, download_v1_stream_with_progress, upload_v1_from_stream,
+    upload_v1_with_progress,
 };
+use crate::listing::{ListQuery, MetadataPage, list_metadata_page_v1, paginate};
+use bytes::Bytes;
+use futures::Stream;
 use futures::channel::mpsc::Receiver;
 use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::Arc;
 
 pub use crate::auth;
 use crate::auth::TokenCache;
+use crate::bulk_delete::{DeleteOptions, DeleteReport, delete_v1_by_query, delete_v1_many};
 pub use crate::config;
-use crate::discovery::{DiscoveryService, DomainWithToken, ListDomainsResponse};
+#[cfg(not(target_family = "wasm"))]
+use crate::dedup::UploadReport;
+use crate::discovery::{
+    DiscoveryService, DomainServer, DomainWithServer, DomainWithToken, ListDomainsResponse, Portal,
+    UpdateDomainRequest,
+};
 use crate::errors::DomainError;
-pub use crate::reconstruction::JobRequest;
+use crate::fanout::{DomainSelection, DomainStream, FanOutResult, fan_out};
+use crate::oidc::OidcTokenProvider;
+use crate::portals::{
+    PlacePortalRequest, PortalPlacement, get_portal_v1, list_portals_v1, place_portal_v1,
+    remove_portal_v1,
+};
+use crate::progress::TransferControl;
+pub use crate::reconstruction::{Job, JobRequest, JobStatus};
+use crate::retry::RetryPolicy;
+use crate::token_refresh::{Reopen, TokenRefreshOptions, hand_off};
+use crate::token_store::TokenStore;
+use crate::transport::Transport;
+use crate::watch::{DomainDataEvent, DomainEndpoint, WatchOptions, watch_v1};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ListDomainsQuery {
@@ -23,20 +47,73 @@ pub struct ListDomainsQuery {
 #[derive(Debug, Clone)]
 pub struct DomainClient {
     discovery_client: DiscoveryService,
+    transport: Transport,
     pub client_id: String,
 }
 
 impl DomainClient {
     pub fn new(api_url: &str, dds_url: &str, client_id: &str) -> Self {
+        Self::new_with_transport(api_url, dds_url, client_id, Transport::default())
+    }
+
+    /// Creates an unauthenticated client whose requests to the auth API, DDS and domain servers
+    /// all go through `transport`. Sign in with [`DomainClient::sign_in_with_app_credential`] or
+    /// [`DomainClient::sign_in_with_user_credential`].
+    pub fn new_with_transport(
+        api_url: &str,
+        dds_url: &str,
+        client_id: &str,
+        transport: Transport,
+    ) -> Self {
         if client_id.is_empty() {
             panic!("client_id is empty");
         }
         Self {
-            discovery_client: DiscoveryService::new(api_url, dds_url, client_id),
+            discovery_client: DiscoveryService::new_with_transport(
+                api_url,
+                dds_url,
+                client_id,
+                transport.clone(),
+            ),
+            transport,
             client_id: client_id.to_string(),
         }
     }
 
+    pub fn transport(&self) -> &Transport {
+        &self.transport
+    }
+
+    /// Replaces the retry policy used for all requests of this client.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.transport = self.transport.with_retry_policy(retry_policy);
+        self.discovery_client.set_transport(self.transport.clone());
+        self
+    }
+
+    pub async fn sign_in_with_app_credential(
+        &mut self,
+        app_key: &str,
+        app_secret: &str,
+    ) -> Result<(), DomainError> {
+        self.discovery_client
+            .sign_in_as_auki_app(app_key, app_secret)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn sign_in_with_user_credential(
+        &mut self,
+        email: &str,
+        password: &str,
+        remember_password: bool,
+    ) -> Result<(), DomainError> {
+        self.discovery_client
+            .sign_in_with_auki_account(email, password, remember_password)
+            .await?;
+        Ok(())
+    }
+
     pub async fn new_with_app_credential(
         api_url: &str,
         dds_url: &str,
@@ -45,10 +122,7 @@ impl DomainClient {
         app_secret: &str,
     ) -> Result<Self, DomainError> {
         let mut dc = DomainClient::new(api_url, dds_url, client_id);
-        let _ = dc
-            .discovery_client
-            .sign_in_as_auki_app(app_key, app_secret)
-            .await?;
+        dc.sign_in_with_app_credential(app_key, app_secret).await?;
         Ok(dc)
     }
 
@@ -61,44 +135,165 @@ impl DomainClient {
         remember_password: bool,
     ) -> Result<Self, DomainError> {
         let mut dc = DomainClient::new(api_url, dds_url, client_id);
-        let _ = dc
-            .discovery_client
-            .sign_in_with_auki_account(email, password, remember_password)
+        dc.sign_in_with_user_credential(email, password, remember_password)
             .await?;
         Ok(dc)
     }
 
+    /// Saves the user session to `store` after every login and token refresh, starting with the
+    /// current session when already signed in.
+    pub async fn set_token_store(&mut self, store: Arc<dyn TokenStore>) {
+        self.discovery_client.set_token_store(store).await;
+    }
+
+    /// Signs in again with the session saved in the token store, without the user's password.
+    /// Returns `false` when nothing usable is saved.
+    pub async fn restore_session(&mut self) -> Result<bool, DomainError> {
+        self.discovery_client.restore_session().await
+    }
+
+    /// Revokes the user session on the server, then forgets it in memory and in the token
+    /// store. Local state is cleared even when revoking fails.
+    pub async fn logout(&mut self) -> Result<(), DomainError> {
+        self.discovery_client.logout().await
+    }
+
     pub fn with_oidc_access_token(&self, token: &str) -> Self {
         Self {
             discovery_client: self.discovery_client.with_oidc_access_token(token),
+            transport: self.transport.clone(),
             client_id: self.client_id.clone(),
         }
     }
 
+    /// Returns a client that authenticates with OIDC access tokens from `provider`. The provider
+    /// is called for the first request, when the current token is about to expire and when a
+    /// request is rejected with 401, in which case the request is sent once more.
+    ///
+    /// ```no_run
+    /// # use posemesh_domain_http::{domain_client::DomainClient, oidc::oidc_token_provider};
+    /// # let client = DomainClient::new("https://api.example.com", "https://dds.example.com", "my-client");
+    /// let client = client.with_oidc_token_provider(oidc_token_provider(|| async {
+    ///     // run the app's OIDC refresh flow
+    ///     Ok("fresh-token".to_string())
+    /// }));
+    /// ```
+    pub fn with_oidc_token_provider(&self, provider: OidcTokenProvider) -> Self {
+        Self {
+            discovery_client: self.discovery_client.with_oidc_token_provider(provider),
+            transport: self.transport.clone(),
+            client_id: self.client_id.clone(),
+        }
+    }
+
+    /// Renews tokens in the background ahead of expiry, for this client and its clones, and
+    /// keeps at most `options.max_domains` domain tokens. See [`crate::token_refresh`].
+    pub async fn start_token_refresh(&self, options: TokenRefreshOptions) {
+        self.discovery_client.start_token_refresh(options).await;
+    }
+
+    /// Stops the refresher started by [`DomainClient::start_token_refresh`].
+    pub fn stop_token_refresh(&self) {
+        self.discovery_client.stop_token_refresh();
+    }
+
+    // Drops tokens rejected by a domain server so the next call fetches new ones. Returns whether retrying can help.
+    async fn forget_rejected_token<T>(&self, result: &Result<T, DomainError>) -> bool {
+        matches!(result, Err(e) if e.status() == Some(reqwest::StatusCode::UNAUTHORIZED))
+            && self.discovery_client.invalidate_oidc_token().await
+    }
+
+    // Authenticates against the domain and runs `request`, sending it once more with new tokens if the domain server rejects the OIDC-derived token.
+    async fn with_domain<T, F, Fut>(&self, domain_id: &str, request: F) -> Result<T, DomainError>
+    where
+        F: Fn(DomainWithToken) -> Fut,
+        Fut: Future<Output = Result<T, DomainError>>,
+    {
+        let domain = self.discovery_client.auth_domain(domain_id).await?;
+        let result = request(domain).await;
+        if self.forget_rejected_token(&result).await {
+            let domain = self.discovery_client.auth_domain(domain_id).await?;
+            return request(domain).await;
+        }
+        result
+    }
+
     pub async fn download_domain_data_stream(
         &self,
         domain_id: &str,
         query: &DownloadQuery,
     ) -> Result<Receiver<Result<DomainData, DomainError>>, DomainError> {
-        let domain = self.discovery_client.auth_domain(domain_id).await?;
-        let rx = download_v1_stream(
-            &domain.domain.domain_server.url,
-            &self.client_id,
-            &domain.get_access_token(),
-            domain_id,
-            query,
-        )
-        .await?;
-        Ok(rx)
+        self.download_domain_data_stream_with_progress(domain_id, query, &TransferControl::new())
+            .await
+    }
+
+    /// Same as [`DomainClient::download_domain_data_stream`], reporting received bytes and items
+    /// to `transfer`. Once cancelled, the stream ends with [`DomainError::Cancelled`]. A stream
+    /// failing after its token expired is reopened with a renewed token, skipping items already
+    /// received.
+    pub async fn download_domain_data_stream_with_progress(
+        &self,
+        domain_id: &str,
+        query: &DownloadQuery,
+        transfer: &TransferControl,
+    ) -> Result<Receiver<Result<DomainData, DomainError>>, DomainError> {
+        let download = self
+            .with_domain(domain_id, |domain| async move {
+                let items = download_v1_stream_with_progress(
+                    &self.transport,
+                    &domain.domain.domain_server.url,
+                    &self.client_id,
+                    &domain.get_access_token(),
+                    domain_id,
+                    query,
+                    transfer,
+                )
+                .await?;
+                Ok((domain.expires_at, items))
+            })
+            .await?;
+
+        let (client, domain_id, transfer) = (self.clone(), domain_id.to_string(), transfer.clone());
+        let reopen: Reopen = Box::new(move |query| {
+            let (client, domain_id, transfer) =
+                (client.clone(), domain_id.clone(), transfer.clone());
+            Box::pin(async move {
+                let domain = client.discovery_client.auth_domain(&domain_id).await?;
+                let items = download_v1_stream_with_progress(
+                    &client.transport,
+                    &domain.domain.domain_server.url,
+                    &client.client_id,
+                    &domain.get_access_token(),
+                    &domain_id,
+                    &query,
+                    &transfer,
+                )
+                .await?;
+                Ok((domain.expires_at, items))
+            })
+        });
+        Ok(hand_off(download, query.clone(), reopen))
     }
 
     pub async fn download_domain_data(
         &self,
         domain_id: &str,
         query: &DownloadQuery,
+    ) -> Result<Vec<DomainData>, DomainError> {
+        self.download_domain_data_with_progress(domain_id, query, &TransferControl::new())
+            .await
+    }
+
+    pub async fn download_domain_data_with_progress(
+        &self,
+        domain_id: &str,
+        query: &DownloadQuery,
+        transfer: &TransferControl,
     ) -> Result<Vec<DomainData>, DomainError> {
         use futures::StreamExt;
-        let mut rx = self.download_domain_data_stream(domain_id, query).await?;
+        let mut rx = self
+            .download_domain_data_stream_with_progress(domain_id, query, transfer)
+            .await?;
 
         let mut results = Vec::new();
         while let Some(result) = rx.next().await {
@@ -113,15 +308,32 @@ impl DomainClient {
         domain_id: &str,
         data: Receiver<UploadDomainData>,
     ) -> Result<Vec<DomainDataMetadata>, DomainError> {
-        use crate::{auth::TokenCache, domain_data::upload_v1_stream};
+        self.upload_domain_data_stream_with_progress(domain_id, data, &TransferControl::new())
+            .await
+    }
+
+    /// Same as [`DomainClient::upload_domain_data_stream`], reporting progress to `transfer`.
+    /// Totals are unknown up front, so events carry none.
+    #[cfg(not(target_family = "wasm"))]
+    pub async fn upload_domain_data_stream_with_progress(
+        &self,
+        domain_id: &str,
+        data: Receiver<UploadDomainData>,
+        transfer: &TransferControl,
+    ) -> Result<Vec<DomainDataMetadata>, DomainError> {
+        use crate::{auth::TokenCache, domain_data::upload_v1_stream_with_progress};
         let domain = self.discovery_client.auth_domain(domain_id).await?;
-        upload_v1_stream(
+        let result = upload_v1_stream_with_progress(
+            &self.transport,
             &domain.domain.domain_server.url,
             &domain.get_access_token(),
             domain_id,
             data,
+            transfer,
         )
-        .await
+        .await;
+        self.forget_rejected_token(&result).await;
+        result
     }
 
     pub async fn upload_domain_data(
@@ -129,75 +341,422 @@ impl DomainClient {
         domain_id: &str,
         data: Vec<UploadDomainData>,
     ) -> Result<Vec<DomainDataMetadata>, DomainError> {
+        self.upload_domain_data_with_progress(domain_id, data, &TransferControl::new())
+            .await
+    }
+
+    /// Same as [`DomainClient::upload_domain_data`], reporting progress to `transfer` and
+    /// stopping with [`DomainError::Cancelled`] once it is cancelled. Multipart uploads in flight
+    /// are aborted on the server.
+    ///
+    /// ```no_run
+    /// # use posemesh_domain_http::{domain_client::DomainClient, domain_data::UploadDomainData};
+    /// # async fn example(client: DomainClient, data: Vec<UploadDomainData>) {
+    /// use posemesh_domain_http::progress::{CancellationToken, TransferControl, TransferEvent};
+    ///
+    /// let cancel = CancellationToken::new();
+    /// let transfer = TransferControl::new()
+    ///     .on_event(|event| {
+    ///         if let TransferEvent::BytesSent { sent, total: Some(total) } = event {
+    ///             println!("{sent}/{total} bytes");
+    ///         }
+    ///     })
+    ///     .with_cancellation(cancel.clone());
+    /// let uploaded = client.upload_domain_data_with_progress("domain", data, &transfer).await;
+    /// # }
+    /// ```
+    pub async fn upload_domain_data_with_progress(
+        &self,
+        domain_id: &str,
+        data: Vec<UploadDomainData>,
+        transfer: &TransferControl,
+    ) -> Result<Vec<DomainDataMetadata>, DomainError> {
+        // The payload is consumed, so a rejected upload is not retried.
         let domain = self.discovery_client.auth_domain(domain_id).await?;
-        upload_v1(
+        let result = upload_v1_with_progress(
+            &self.transport,
             &domain.domain.domain_server.url,
             &domain.get_access_token(),
             domain_id,
             data,
+            transfer,
         )
-        .await
+        .await;
+        self.forget_rejected_token(&result).await;
+        result
     }
 
-    pub async fn download_metadata(
+    /// Same as [`DomainClient::upload_domain_data`], but items whose content the server already
+    /// has (same SHA-256 as the item with the same id, or with the same name and data type) are
+    /// not sent again and are reported as skipped.
+    #[cfg(not(target_family = "wasm"))]
+    pub async fn upload_domain_data_dedup(
         &self,
         domain_id: &str,
-        query: &DownloadQuery,
-    ) -> Result<Vec<DomainDataMetadata>, DomainError> {
+        data: Vec<UploadDomainData>,
+    ) -> Result<UploadReport, DomainError> {
+        use crate::dedup::upload_v1_dedup;
         let domain = self.discovery_client.auth_domain(domain_id).await?;
-        download_metadata_v1(
+        let result = upload_v1_dedup(
+            &self.transport,
             &domain.domain.domain_server.url,
             &self.client_id,
             &domain.get_access_token(),
             domain_id,
-            query,
+            data,
         )
-        .await
+        .await;
+        self.forget_rejected_token(&result).await;
+        result
     }
 
-    pub async fn download_domain_data_by_id(
+    /// Uploads one item read from `chunks`, e.g. a file or a browser `ReadableStream`, without
+    /// holding all of it in memory: the data is cut into multipart parts as it arrives. `size`
+    /// lets the server and progress events know the total up front.
+    pub async fn upload_domain_data_from_stream<S>(
         &self,
         domain_id: &str,
-        id: &str,
-    ) -> Result<Vec<u8>, DomainError> {
+        action: DomainAction,
+        size: Option<u64>,
+        chunks: S,
+        transfer: &TransferControl,
+    ) -> Result<DomainDataMetadata, DomainError>
+    where
+        S: Stream<Item = Result<Bytes, DomainError>>,
+    {
+        // The stream is consumed, so a rejected upload is not retried.
         let domain = self.discovery_client.auth_domain(domain_id).await?;
-        download_by_id(
+        let result = upload_v1_from_stream(
+            &self.transport,
             &domain.domain.domain_server.url,
-            &self.client_id,
             &domain.get_access_token(),
             domain_id,
-            id,
+            action,
+            size,
+            chunks,
+            transfer,
         )
+        .await;
+        self.forget_rejected_token(&result).await;
+        result
+    }
+
+    pub async fn download_metadata(
+        &self,
+        domain_id: &str,
+        query: &DownloadQuery,
+    ) -> Result<Vec<DomainDataMetadata>, DomainError> {
+        self.with_domain(domain_id, |domain| async move {
+            download_metadata_v1(
+                &self.transport,
+                &domain.domain.domain_server.url,
+                &self.client_id,
+                &domain.get_access_token(),
+                domain_id,
+                query,
+            )
+            .await
+        })
         .await
     }
 
-    pub async fn delete_domain_data_by_id(
+    /// Fetches one page of metadata. Pass the returned `next_cursor` as `query.cursor` to get the
+    /// next page.
+    pub async fn list_metadata_page(
         &self,
         domain_id: &str,
-        id: &str,
-    ) -> Result<(), DomainError> {
+        query: &ListQuery,
+    ) -> Result<MetadataPage, DomainError> {
+        self.with_domain(domain_id, |domain| async move {
+            list_metadata_page_v1(
+                &self.transport,
+                &domain.domain.domain_server.url,
+                &self.client_id,
+                &domain.get_access_token(),
+                domain_id,
+                query,
+            )
+            .await
+        })
+        .await
+    }
+
+    /// Streams all metadata matching `query`, fetching the next page as the stream is consumed.
+    pub fn list_metadata<'a>(
+        &'a self,
+        domain_id: &'a str,
+        query: ListQuery,
+    ) -> impl Stream<Item = Result<DomainDataMetadata, DomainError>> + 'a {
+        paginate(query, move |page| async move {
+            self.list_metadata_page(domain_id, &page).await
+        })
+    }
+
+    /// Streams the metadata matching `query` from every selected domain, querying up to
+    /// `concurrency` domains at a time. Domains that fail are reported in the stream and do not
+    /// stop the others.
+    pub async fn fan_out_metadata<'a>(
+        &'a self,
+        domains: impl Into<DomainSelection>,
+        query: &'a DownloadQuery,
+        concurrency: usize,
+    ) -> Result<impl Stream<Item = FanOutResult<DomainDataMetadata>> + 'a, DomainError> {
+        use futures::TryStreamExt;
+        let domain_ids = domains.into().resolve(self).await?;
+        Ok(fan_out(domain_ids, concurrency, move |domain_id| {
+            let items =
+                futures::stream::once(
+                    async move { self.download_metadata(&domain_id, query).await },
+                )
+                .map_ok(|items| futures::stream::iter(items.into_iter().map(Ok)))
+                .try_flatten();
+            Box::pin(items) as DomainStream<'a, _>
+        }))
+    }
+
+    /// Same as [`DomainClient::fan_out_metadata`], downloading the data as well.
+    pub async fn fan_out_download<'a>(
+        &'a self,
+        domains: impl Into<DomainSelection>,
+        query: &'a DownloadQuery,
+        concurrency: usize,
+    ) -> Result<impl Stream<Item = FanOutResult<DomainData>> + 'a, DomainError> {
+        use futures::TryStreamExt;
+        let domain_ids = domains.into().resolve(self).await?;
+        Ok(fan_out(domain_ids, concurrency, move |domain_id| {
+            let items = futures::stream::once(async move {
+                self.download_domain_data_stream(&domain_id, query).await
+            })
+            .try_flatten();
+            Box::pin(items) as DomainStream<'a, _>
+        }))
+    }
+
+    /// Mirrors the domain into `dir`, fetching only items changed since the previous sync and,
+    /// with [`SyncOptions::push`](crate::sync::SyncOptions::push), uploading local edits.
+    #[cfg(not(target_family = "wasm"))]
+    pub async fn sync_to_dir(
+        &self,
+        domain_id: &str,
+        dir: impl AsRef<std::path::Path>,
+        options: &crate::sync::SyncOptions,
+    ) -> Result<crate::sync::SyncReport, crate::sync::SyncError> {
         let domain = self.discovery_client.auth_domain(domain_id).await?;
-        delete_by_id(
+        crate::sync::sync_dir_v1(
+            &self.transport,
             &domain.domain.domain_server.url,
+            &self.client_id,
             &domain.get_access_token(),
             domain_id,
-            id,
+            dir.as_ref(),
+            options,
+        )
+        .await
+    }
+
+    /// Streams created, updated and deleted domain data matching `query` until the stream is
+    /// dropped, using server events when available and metadata polling otherwise.
+    pub fn watch_domain_data<'a>(
+        &'a self,
+        domain_id: &'a str,
+        query: ListQuery,
+        options: WatchOptions,
+    ) -> impl Stream<Item = Result<DomainDataEvent, DomainError>> + 'a {
+        watch_v1(
+            &self.transport,
+            &self.client_id,
+            domain_id,
+            query,
+            options,
+            move || async move {
+                let domain = self.discovery_client.auth_domain(domain_id).await?;
+                Ok(DomainEndpoint {
+                    url: domain.domain.domain_server.url.clone(),
+                    access_token: domain.get_access_token(),
+                })
+            },
         )
+    }
+
+    pub async fn download_domain_data_by_id(
+        &self,
+        domain_id: &str,
+        id: &str,
+    ) -> Result<Vec<u8>, DomainError> {
+        self.with_domain(domain_id, |domain| async move {
+            download_by_id(
+                &self.transport,
+                &domain.domain.domain_server.url,
+                &self.client_id,
+                &domain.get_access_token(),
+                domain_id,
+                id,
+            )
+            .await
+        })
         .await
     }
 
+    pub async fn delete_domain_data_by_id(
+        &self,
+        domain_id: &str,
+        id: &str,
+    ) -> Result<(), DomainError> {
+        self.with_domain(domain_id, |domain| async move {
+            delete_by_id(
+                &self.transport,
+                &domain.domain.domain_server.url,
+                &domain.get_access_token(),
+                domain_id,
+                id,
+            )
+            .await
+        })
+        .await
+    }
+
+    /// Deletes the items `ids`, up to `options.concurrency` at a time. Items that fail are
+    /// reported with their error and do not stop the others.
+    pub async fn delete_domain_data(
+        &self,
+        domain_id: &str,
+        ids: &[String],
+        options: &DeleteOptions,
+    ) -> Result<DeleteReport, DomainError> {
+        self.with_domain(domain_id, |domain| async move {
+            delete_v1_many(
+                &self.transport,
+                &domain.domain.domain_server.url,
+                &self.client_id,
+                &domain.get_access_token(),
+                domain_id,
+                ids,
+                options,
+            )
+            .await
+        })
+        .await
+    }
+
+    /// Same as [`DomainClient::delete_domain_data`] for every item matching `query`.
+    pub async fn delete_domain_data_by_query(
+        &self,
+        domain_id: &str,
+        query: &DownloadQuery,
+        options: &DeleteOptions,
+    ) -> Result<DeleteReport, DomainError> {
+        self.with_domain(domain_id, |domain| async move {
+            delete_v1_by_query(
+                &self.transport,
+                &domain.domain.domain_server.url,
+                &self.client_id,
+                &domain.get_access_token(),
+                domain_id,
+                query,
+                options,
+            )
+            .await
+        })
+        .await
+    }
+
+    /// Submits a reconstruction job and returns it with its id.
     pub async fn submit_job_request_v1(
         &self,
         domain_id: &str,
         request: &JobRequest,
-    ) -> Result<reqwest::Response, DomainError> {
-        let domain = self.discovery_client.auth_domain(domain_id).await?;
-        crate::reconstruction::forward_job_request_v1(
-            &domain.domain.domain_server.url,
-            &self.client_id,
-            &domain.get_access_token(),
-            domain_id,
-            request,
+    ) -> Result<Job, DomainError> {
+        self.with_domain(domain_id, |domain| async move {
+            crate::reconstruction::forward_job_request_v1(
+                &self.transport,
+                &domain.domain.domain_server.url,
+                &self.client_id,
+                &domain.get_access_token(),
+                domain_id,
+                request,
+            )
+            .await
+        })
+        .await
+    }
+
+    pub async fn get_job(&self, domain_id: &str, job_id: &str) -> Result<Job, DomainError> {
+        self.with_domain(domain_id, |domain| async move {
+            crate::reconstruction::get_job_v1(
+                &self.transport,
+                &domain.domain.domain_server.url,
+                &self.client_id,
+                &domain.get_access_token(),
+                domain_id,
+                job_id,
+            )
+            .await
+        })
+        .await
+    }
+
+    pub async fn list_jobs(&self, domain_id: &str) -> Result<Vec<Job>, DomainError> {
+        self.with_domain(domain_id, |domain| async move {
+            crate::reconstruction::list_jobs_v1(
+                &self.transport,
+                &domain.domain.domain_server.url,
+                &self.client_id,
+                &domain.get_access_token(),
+                domain_id,
+            )
+            .await
+        })
+        .await
+    }
+
+    pub async fn cancel_job(&self, domain_id: &str, job_id: &str) -> Result<(), DomainError> {
+        self.with_domain(domain_id, |domain| async move {
+            crate::reconstruction::cancel_job_v1(
+                &self.transport,
+                &domain.domain.domain_server.url,
+                &self.client_id,
+                &domain.get_access_token(),
+                domain_id,
+                job_id,
+            )
+            .await
+        })
+        .await
+    }
+
+    /// Ids of the data written by a finished job.
+    pub async fn get_job_results(
+        &self,
+        domain_id: &str,
+        job_id: &str,
+    ) -> Result<Vec<String>, DomainError> {
+        self.with_domain(domain_id, |domain| async move {
+            crate::reconstruction::get_job_results_v1(
+                &self.transport,
+                &domain.domain.domain_server.url,
+                &self.client_id,
+                &domain.get_access_token(),
+                domain_id,
+                job_id,
+            )
+            .await
+        })
+        .await
+    }
+
+    /// Waits until the job succeeded, failed, was cancelled or reports a status this version does
+    /// not know, calling `on_progress` whenever its status or progress changes.
+    pub async fn wait_for_job(
+        &self,
+        domain_id: &str,
+        job_id: &str,
+        poll_interval: std::time::Duration,
+        on_progress: impl FnMut(&Job),
+    ) -> Result<Job, DomainError> {
+        crate::reconstruction::wait_for_job(
+            || self.get_job(domain_id, job_id),
+            poll_interval,
+            on_progress,
         )
         .await
     }
@@ -239,6 +798,77 @@ impl DomainClient {
         }
     }
 
+    /// Lists the portals placed in a domain with their poses.
+    pub async fn list_portals(&self, domain_id: &str) -> Result<Vec<PortalPlacement>, DomainError> {
+        self.with_domain(domain_id, |domain| async move {
+            list_portals_v1(
+                &self.transport,
+                &domain.domain.domain_server.url,
+                &self.client_id,
+                &domain.get_access_token(),
+                domain_id,
+            )
+            .await
+        })
+        .await
+    }
+
+    pub async fn get_portal(
+        &self,
+        domain_id: &str,
+        short_id: &str,
+    ) -> Result<PortalPlacement, DomainError> {
+        self.with_domain(domain_id, |domain| async move {
+            get_portal_v1(
+                &self.transport,
+                &domain.domain.domain_server.url,
+                &self.client_id,
+                &domain.get_access_token(),
+                domain_id,
+                short_id,
+            )
+            .await
+        })
+        .await
+    }
+
+    /// Places the portal `short_id` in a domain, or moves it if it is placed already.
+    pub async fn place_portal(
+        &self,
+        domain_id: &str,
+        short_id: &str,
+        request: &PlacePortalRequest,
+    ) -> Result<PortalPlacement, DomainError> {
+        self.with_domain(domain_id, |domain| async move {
+            place_portal_v1(
+                &self.transport,
+                &domain.domain.domain_server.url,
+                &self.client_id,
+                &domain.get_access_token(),
+                domain_id,
+                short_id,
+                request,
+            )
+            .await
+        })
+        .await
+    }
+
+    pub async fn remove_portal(&self, domain_id: &str, short_id: &str) -> Result<(), DomainError> {
+        self.with_domain(domain_id, |domain| async move {
+            remove_portal_v1(
+                &self.transport,
+                &domain.domain.domain_server.url,
+                &self.client_id,
+                &domain.get_access_token(),
+                domain_id,
+                short_id,
+            )
+            .await
+        })
+        .await
+    }
+
     pub async fn create_domain(
         &self,
         name: &str,
@@ -251,12 +881,57 @@ impl DomainClient {
             .await
     }
 
-    pub async fn delete_domain(&self, domain_id: &str) -> Result<(), DomainError> {
-        let domain = self.discovery_client.auth_domain(domain_id).await?;
+    pub async fn get_domain(&self, domain_id: &str) -> Result<DomainWithServer, DomainError> {
+        self.discovery_client.get_domain(domain_id).await
+    }
+
+    /// Renames a domain or changes its redirect URL.
+    pub async fn update_domain(
+        &self,
+        domain_id: &str,
+        update: &UpdateDomainRequest,
+    ) -> Result<DomainWithServer, DomainError> {
+        self.discovery_client.update_domain(domain_id, update).await
+    }
+
+    /// Moves a domain and its data to another domain server.
+    pub async fn move_domain(
+        &self,
+        domain_id: &str,
+        domain_server_id: &str,
+    ) -> Result<DomainWithServer, DomainError> {
+        self.discovery_client
+            .move_domain(domain_id, domain_server_id)
+            .await
+    }
+
+    /// Lists the domain servers available to `org`: `own`, `all` or an organization id.
+    pub async fn list_domain_servers(&self, org: &str) -> Result<Vec<DomainServer>, DomainError> {
+        self.discovery_client.list_domain_servers(org).await
+    }
+
+    pub async fn get_domain_server(
+        &self,
+        domain_server_id: &str,
+    ) -> Result<DomainServer, DomainError> {
         self.discovery_client
-            .delete_domain(&domain.get_access_token(), domain_id)
+            .get_domain_server(domain_server_id)
             .await
     }
+
+    /// Lists the portals attached to a domain.
+    pub async fn list_domain_portals(&self, domain_id: &str) -> Result<Vec<Portal>, DomainError> {
+        self.discovery_client.list_domain_portals(domain_id).await
+    }
+
+    pub async fn delete_domain(&self, domain_id: &str) -> Result<(), DomainError> {
+        self.with_domain(domain_id, |domain| async move {
+            self.discovery_client
+                .delete_domain(&domain.get_access_token(), domain_id)
+                .await
+        })
+        .await
+    }
 }
 
 #[cfg(not(target_family = "wasm"))]
@@ -728,7 +1403,7 @@ mod tests {
 
         let job_request = JobRequest {
             processing_type: "invalid_processing_type".to_string(),
-            ..Default::default()
+            ..JobRequest::new("", "test", vec![])
         };
         let res = client
             .submit_job_request_v1(&config.1, &job_request)
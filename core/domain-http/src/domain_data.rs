@@ -1,18 +1,28 @@
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use futures::lock::Mutex;
-use futures::{SinkExt, Stream, channel::mpsc, stream::StreamExt};
-use reqwest::{Body, Client, Response, StatusCode};
+use futures::{SinkExt, Stream, TryStreamExt, channel::mpsc, stream::StreamExt};
+#[cfg(not(target_family = "wasm"))]
+use reqwest::{Body, header::CONTENT_LENGTH};
+use reqwest::{RequestBuilder, Response, StatusCode};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::OnceLock;
 #[cfg(not(target_family = "wasm"))]
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+#[cfg(not(target_family = "wasm"))]
 use tokio::spawn;
+use tracing::Instrument;
 #[cfg(target_family = "wasm")]
 use wasm_bindgen_futures::spawn_local as spawn;
 
 use posemesh_utils::now_unix_secs;
 
 use crate::errors::{AukiErrorResponse, DomainError};
+use crate::progress::{PartStatus, TransferControl, TransferEvent};
+use crate::transport::{SendVia, Transport};
 
 #[derive(Debug, Deserialize, Clone)]
 struct InfoResponse {
@@ -49,10 +59,10 @@ fn info_cache() -> &'static Mutex<HashMap<String, InfoCacheEntry>> {
     INFO_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
 }
 
-async fn fetch_info_v1(url: &str) -> Result<Option<UploadInfoV1>, ()> {
-    let resp = Client::new()
+async fn fetch_info_v1(transport: &Transport, url: &str) -> Result<Option<UploadInfoV1>, ()> {
+    let resp = transport
         .get(format!("{}/api/v1/info", url))
-        .send()
+        .send_via(transport)
         .await
         .map_err(|_| ())?;
 
@@ -69,7 +79,7 @@ async fn fetch_info_v1(url: &str) -> Result<Option<UploadInfoV1>, ()> {
     }))
 }
 
-pub async fn get_upload_info_v1(url: &str) -> Option<UploadInfoV1> {
+pub async fn get_upload_info_v1(transport: &Transport, url: &str) -> Option<UploadInfoV1> {
     let now = now_unix_secs();
     {
         let cache = info_cache().lock().await;
@@ -80,7 +90,7 @@ pub async fn get_upload_info_v1(url: &str) -> Option<UploadInfoV1> {
         }
     }
 
-    let fetched = match fetch_info_v1(url).await {
+    let fetched = match fetch_info_v1(transport, url).await {
         Ok(v) => v,
         Err(_) => return None,
     };
@@ -97,17 +107,15 @@ pub async fn get_upload_info_v1(url: &str) -> Option<UploadInfoV1> {
     fetched
 }
 
-fn is_unsupported_endpoint_status(status: StatusCode) -> bool {
+pub(crate) fn is_unsupported_endpoint_status(status: StatusCode) -> bool {
     status == StatusCode::NOT_FOUND
         || status == StatusCode::METHOD_NOT_ALLOWED
         || status == StatusCode::NOT_IMPLEMENTED
 }
 
 fn is_unsupported_endpoint_error(err: &DomainError) -> bool {
-    match err {
-        DomainError::AukiErrorResponse(resp) => is_unsupported_endpoint_status(resp.status),
-        _ => false,
-    }
+    err.response()
+        .is_some_and(|resp| is_unsupported_endpoint_status(resp.status))
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -119,6 +127,9 @@ pub struct DomainDataMetadata {
     pub size: u64,
     pub created_at: String,
     pub updated_at: String,
+    /// Content hash reported by the server (hex SHA-256, optionally `sha256:`-prefixed).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -139,7 +150,7 @@ pub struct CreateDomainData {
     pub data_type: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum DomainAction {
     Create { name: String, data_type: String },
@@ -184,7 +195,10 @@ pub struct UploadDomainData {
     pub data: Vec<u8>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Ids per metadata request; they are sent in the query string.
+pub(crate) const ID_BATCH: usize = 50;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DownloadQuery {
     pub ids: Vec<String>,
     pub name: Option<String>,
@@ -197,65 +211,67 @@ struct ListDomainDataMetadata {
 }
 
 pub async fn download_by_id(
+    transport: &Transport,
     url: &str,
     client_id: &str,
     access_token: &str,
     domain_id: &str,
     id: &str,
 ) -> Result<Vec<u8>, DomainError> {
-    let response = Client::new()
+    let response = transport
         .get(format!(
             "{}/api/v1/domains/{}/data/{}?raw=true",
             url, domain_id, id
         ))
         .bearer_auth(access_token)
         .header("posemesh-client-id", client_id)
-        .send()
+        .send_via(transport)
         .await?;
 
     if response.status().is_success() {
         let data = response.bytes().await?;
         Ok(data.to_vec())
     } else {
-        let status = response.status();
-        let error = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        Err(AukiErrorResponse {
-            status,
-            error: format!("Failed to download data by id. {}", error),
-        }
-        .into())
+        Err(
+            AukiErrorResponse::from_response(response, "Failed to download data by id.")
+                .await
+                .into(),
+        )
     }
 }
 
 pub async fn download_metadata_v1(
+    transport: &Transport,
     url: &str,
     client_id: &str,
     access_token: &str,
     domain_id: &str,
     query: &DownloadQuery,
 ) -> Result<Vec<DomainDataMetadata>, DomainError> {
-    let response = download_v1(url, client_id, access_token, domain_id, query, false).await?;
+    let response = download_v1(
+        transport,
+        url,
+        client_id,
+        access_token,
+        domain_id,
+        query,
+        false,
+    )
+    .await?;
     if response.status().is_success() {
         let data = response.json::<ListDomainDataMetadata>().await?;
         Ok(data.data)
     } else {
-        let status = response.status();
-        let text = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        Err(AukiErrorResponse {
-            status,
-            error: format!("Failed to download metadata. {}", text),
-        }
-        .into())
+        Err(
+            AukiErrorResponse::from_response(response, "Failed to download metadata.")
+                .await
+                .into(),
+        )
     }
 }
 
 pub async fn download_v1(
+    transport: &Transport,
     url: &str,
     client_id: &str,
     access_token: &str,
@@ -284,7 +300,7 @@ pub async fn download_v1(
         }
     };
 
-    let response = Client::new()
+    let response = transport
         .get(format!("{}/api/v1/domains/{}/data{}", url, domain_id, ids))
         .bearer_auth(access_token)
         .header(
@@ -297,33 +313,62 @@ pub async fn download_v1(
         )
         .header("posemesh-client-id", client_id)
         .query(&params)
-        .send()
+        .send_via(transport)
         .await?;
 
     if response.status().is_success() {
         Ok(response)
     } else {
-        let status = response.status();
-        let text = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        Err(AukiErrorResponse {
-            status,
-            error: format!("Failed to download data. {}", text),
-        }
-        .into())
+        Err(
+            AukiErrorResponse::from_response(response, "Failed to download data.")
+                .await
+                .into(),
+        )
     }
 }
 
 pub async fn download_v1_stream(
+    transport: &Transport,
+    url: &str,
+    client_id: &str,
+    access_token: &str,
+    domain_id: &str,
+    query: &DownloadQuery,
+) -> Result<mpsc::Receiver<Result<DomainData, DomainError>>, DomainError> {
+    download_v1_stream_with_progress(
+        transport,
+        url,
+        client_id,
+        access_token,
+        domain_id,
+        query,
+        &TransferControl::default(),
+    )
+    .await
+}
+
+/// Same as [`download_v1_stream`], reporting received bytes and items to `transfer`. Once
+/// cancelled, the stream ends with [`DomainError::Cancelled`].
+pub async fn download_v1_stream_with_progress(
+    transport: &Transport,
     url: &str,
     client_id: &str,
     access_token: &str,
     domain_id: &str,
     query: &DownloadQuery,
+    transfer: &TransferControl,
 ) -> Result<mpsc::Receiver<Result<DomainData, DomainError>>, DomainError> {
-    let response = download_v1(url, client_id, access_token, domain_id, query, true).await?;
+    let response = transfer
+        .run(download_v1(
+            transport,
+            url,
+            client_id,
+            access_token,
+            domain_id,
+            query,
+            true,
+        ))
+        .await?;
 
     let (mut tx, rx) = mpsc::channel::<Result<DomainData, DomainError>>(100);
 
@@ -346,52 +391,77 @@ pub async fn download_v1_stream(
         }
     };
 
+    let transfer = transfer.clone();
     spawn(async move {
-        let stream = response.bytes_stream();
-        handle_domain_data_stream(tx, stream, &boundary).await;
+        let received = transfer.clone();
+        let stream = response.bytes_stream().inspect(move |chunk| {
+            if let Ok(chunk) = chunk {
+                received.bytes_received(chunk.len() as u64);
+            }
+        });
+        // Parsed items go through `parsed` so they are counted before reaching the caller.
+        let (parsed_tx, mut parsed) = mpsc::channel::<Result<DomainData, DomainError>>(1);
+        let mut out = tx.clone();
+        let done = transfer.clone();
+        let forward = async move {
+            while let Some(item) = parsed.next().await {
+                if item.is_ok() {
+                    done.items_done(1);
+                }
+                if out.send(item).await.is_err() {
+                    break;
+                }
+            }
+        };
+        let download = futures::future::join(
+            handle_domain_data_stream(parsed_tx, stream, &boundary),
+            forward,
+        );
+        futures::pin_mut!(download);
+        let cancelled = transfer.cancellation().cancelled();
+        if let futures::future::Either::Right(_) =
+            futures::future::select(download, cancelled).await
+        {
+            let _ = tx.send(Err(DomainError::Cancelled)).await;
+        }
     });
 
     Ok(rx)
 }
 
 pub async fn delete_by_id(
+    transport: &Transport,
     url: &str,
     access_token: &str,
     domain_id: &str,
     id: &str,
 ) -> Result<(), DomainError> {
     let endpoint = format!("{}/api/v1/domains/{}/data/{}", url, domain_id, id);
-    let client = Client::new();
-    let resp = client
+    let resp = transport
         .delete(&endpoint)
         .bearer_auth(access_token)
-        .send()
+        .send_via(transport)
         .await?;
 
     if resp.status().is_success() {
         Ok(())
     } else {
-        let status = resp.status();
-        let err = resp
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        Err(AukiErrorResponse {
-            status,
-            error: format!("Failed to delete data by id. {}", err),
-        }
-        .into())
+        Err(
+            AukiErrorResponse::from_response(resp, "Failed to delete data by id.")
+                .await
+                .into(),
+        )
     }
 }
 
 async fn initiate_domain_data_multipart_upload(
-    client: &Client,
+    transport: &Transport,
     url: &str,
     access_token: &str,
     domain_id: &str,
     req: &InitiateMultipartRequest,
 ) -> Result<InitiateMultipartResponse, DomainError> {
-    let resp = client
+    let resp = transport
         .post(format!(
             "{}/api/v1/domains/{}/data/multipart?uploads",
             url, domain_id
@@ -399,27 +469,22 @@ async fn initiate_domain_data_multipart_upload(
         .bearer_auth(access_token)
         .header("Content-Type", "application/json")
         .json(req)
-        .send()
+        .send_via(transport)
         .await?;
 
     if resp.status().is_success() {
         Ok(resp.json::<InitiateMultipartResponse>().await?)
     } else {
-        let status = resp.status();
-        let err = resp
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        Err(AukiErrorResponse {
-            status,
-            error: format!("Failed to initiate multipart upload. {}", err),
-        }
-        .into())
+        Err(
+            AukiErrorResponse::from_response(resp, "Failed to initiate multipart upload.")
+                .await
+                .into(),
+        )
     }
 }
 
 async fn upload_domain_data_multipart_part(
-    client: &Client,
+    transport: &Transport,
     url: &str,
     access_token: &str,
     domain_id: &str,
@@ -427,7 +492,7 @@ async fn upload_domain_data_multipart_part(
     part_number: i32,
     bytes: Bytes,
 ) -> Result<UploadPartResult, DomainError> {
-    let resp = client
+    let resp = transport
         .put(format!(
             "{}/api/v1/domains/{}/data/multipart?uploadId={}&partNumber={}",
             url, domain_id, upload_id, part_number
@@ -435,34 +500,29 @@ async fn upload_domain_data_multipart_part(
         .bearer_auth(access_token)
         .header("Content-Type", "application/octet-stream")
         .body(bytes)
-        .send()
+        .send_via(transport)
         .await?;
 
     if resp.status().is_success() {
         Ok(resp.json::<UploadPartResult>().await?)
     } else {
-        let status = resp.status();
-        let err = resp
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        Err(AukiErrorResponse {
-            status,
-            error: format!("Failed to upload multipart part. {}", err),
-        }
-        .into())
+        Err(
+            AukiErrorResponse::from_response(resp, "Failed to upload multipart part.")
+                .await
+                .into(),
+        )
     }
 }
 
 async fn complete_domain_data_multipart_upload(
-    client: &Client,
+    transport: &Transport,
     url: &str,
     access_token: &str,
     domain_id: &str,
     upload_id: &str,
     parts: Vec<CompletedPart>,
 ) -> Result<DomainDataMetadata, DomainError> {
-    let resp = client
+    let resp = transport
         .post(format!(
             "{}/api/v1/domains/{}/data/multipart?uploadId={}",
             url, domain_id, upload_id
@@ -470,90 +530,162 @@ async fn complete_domain_data_multipart_upload(
         .bearer_auth(access_token)
         .header("Content-Type", "application/json")
         .json(&CompleteMultipartRequest { parts })
-        .send()
+        .send_via(transport)
         .await?;
 
     if resp.status().is_success() {
         Ok(resp.json::<DomainDataMetadata>().await?)
     } else {
-        let status = resp.status();
-        let err = resp
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        Err(AukiErrorResponse {
-            status,
-            error: format!("Failed to complete multipart upload. {}", err),
-        }
-        .into())
+        Err(
+            AukiErrorResponse::from_response(resp, "Failed to complete multipart upload.")
+                .await
+                .into(),
+        )
     }
 }
 
 async fn abort_domain_data_multipart_upload(
-    client: &Client,
+    transport: &Transport,
     url: &str,
     access_token: &str,
     domain_id: &str,
     upload_id: &str,
 ) -> Result<(), DomainError> {
-    let resp = client
+    let resp = transport
         .delete(format!(
             "{}/api/v1/domains/{}/data/multipart?uploadId={}",
             url, domain_id, upload_id
         ))
         .bearer_auth(access_token)
-        .send()
+        .send_via(transport)
         .await?;
 
     if resp.status().is_success() {
         Ok(())
     } else {
-        let status = resp.status();
-        let err = resp
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        Err(AukiErrorResponse {
-            status,
-            error: format!("Failed to abort multipart upload. {}", err),
-        }
-        .into())
+        Err(
+            AukiErrorResponse::from_response(resp, "Failed to abort multipart upload.")
+                .await
+                .into(),
+        )
     }
 }
 
 async fn upload_domain_data_multipart_bytes(
+    transport: &Transport,
     url: &str,
     access_token: &str,
     domain_id: &str,
     action: DomainAction,
     bytes: Bytes,
+    transfer: &TransferControl,
 ) -> Result<DomainDataMetadata, DomainError> {
-    if bytes.is_empty() {
-        return Err(DomainError::InvalidRequest(
-            "multipart upload requires non-empty data",
-        ));
+    let size = bytes.len() as u64;
+    upload_domain_data_multipart(
+        transport,
+        url,
+        access_token,
+        domain_id,
+        action,
+        Some(size),
+        futures::stream::iter([Ok(bytes)]),
+        transfer,
+    )
+    .await
+}
+
+/// Chunks read from a stream, cut into parts without copying chunks that hold a whole part.
+#[derive(Default)]
+struct PartBuffer {
+    chunks: VecDeque<Bytes>,
+    len: usize,
+}
+
+impl PartBuffer {
+    fn push(&mut self, chunk: Bytes) {
+        if !chunk.is_empty() {
+            self.len += chunk.len();
+            self.chunks.push_back(chunk);
+        }
+    }
+
+    /// Removes and returns the first `max` bytes, or everything if there are fewer.
+    fn take(&mut self, max: usize) -> Bytes {
+        let n = max.min(self.len);
+        self.len -= n;
+        let mut part = BytesMut::new();
+        while let Some(chunk) = self.chunks.front_mut() {
+            let wanted = n - part.len();
+            if wanted == 0 {
+                break;
+            }
+            let piece = chunk.split_to(wanted.min(chunk.len()));
+            if chunk.is_empty() {
+                self.chunks.pop_front();
+            }
+            if part.is_empty() && piece.len() == n {
+                return piece;
+            }
+            part.extend_from_slice(&piece);
+        }
+        part.freeze()
+    }
+}
+
+/// Uploads `chunks` with the multipart flow, holding about one part in memory. `size` is
+/// announced to the server when known.
+#[allow(clippy::too_many_arguments)]
+async fn upload_domain_data_multipart<S>(
+    transport: &Transport,
+    url: &str,
+    access_token: &str,
+    domain_id: &str,
+    action: DomainAction,
+    size: Option<u64>,
+    chunks: S,
+    transfer: &TransferControl,
+) -> Result<DomainDataMetadata, DomainError>
+where
+    S: Stream<Item = Result<Bytes, DomainError>>,
+{
+    futures::pin_mut!(chunks);
+    let mut buffer = PartBuffer::default();
+    // Reads ahead until there is data, so empty input fails before the upload is initiated.
+    while buffer.len == 0 {
+        match transfer
+            .run(async { chunks.next().await.transpose() })
+            .await?
+        {
+            Some(chunk) => buffer.push(chunk),
+            None => {
+                return Err(DomainError::InvalidRequest(
+                    "multipart upload requires non-empty data",
+                ));
+            }
+        }
     }
 
     let (name, data_type, existing_id) = match action {
         DomainAction::Create { name, data_type } => (name, data_type, None),
         DomainAction::Update { id } => ("".to_string(), "".to_string(), Some(id)),
     };
+    let label = existing_id.clone().unwrap_or_else(|| name.clone());
 
-    let client = Client::new();
-    let init_res = initiate_domain_data_multipart_upload(
-        &client,
-        url,
-        access_token,
-        domain_id,
-        &InitiateMultipartRequest {
-            name,
-            data_type,
-            size: Some(bytes.len() as i64),
-            content_type: Some("application/octet-stream".to_string()),
-            existing_id,
-        },
-    )
-    .await?;
+    let init_res = transfer
+        .run(initiate_domain_data_multipart_upload(
+            transport,
+            url,
+            access_token,
+            domain_id,
+            &InitiateMultipartRequest {
+                name,
+                data_type,
+                size: size.map(|size| size as i64),
+                content_type: Some("application/octet-stream".to_string()),
+                existing_id,
+            },
+        ))
+        .await?;
 
     let part_size = usize::try_from(init_res.part_size)
         .map_err(|_| DomainError::InvalidRequest("invalid multipart part_size"))?;
@@ -563,32 +695,56 @@ async fn upload_domain_data_multipart_bytes(
 
     let upload_id = init_res.upload_id;
     let mut parts = Vec::new();
+    let total_parts =
+        size.map(|size| u32::try_from(size.div_ceil(part_size as u64)).unwrap_or(u32::MAX));
+    let part_event = |part_number: i32, status: PartStatus| TransferEvent::Part {
+        name: label.clone(),
+        part_number: part_number as u32,
+        total_parts,
+        status,
+    };
 
     let upload_res = async {
-        let mut offset = 0usize;
         let mut part_number: i32 = 1;
+        let mut finished = false;
 
-        while offset < bytes.len() {
-            let end = std::cmp::min(offset + part_size, bytes.len());
-            let chunk = bytes.slice(offset..end);
-
-            let res = upload_domain_data_multipart_part(
-                &client,
-                url,
-                access_token,
-                domain_id,
-                &upload_id,
-                part_number,
-                chunk,
-            )
-            .await?;
+        loop {
+            while !finished && buffer.len < part_size {
+                match transfer
+                    .run(async { chunks.next().await.transpose() })
+                    .await?
+                {
+                    Some(chunk) => buffer.push(chunk),
+                    None => finished = true,
+                }
+            }
+            if buffer.len == 0 {
+                break;
+            }
+            let chunk = buffer.take(part_size);
+            let chunk_len = chunk.len() as u64;
+
+            transfer.emit(part_event(part_number, PartStatus::Uploading));
+            let res = transfer
+                .run(upload_domain_data_multipart_part(
+                    transport,
+                    url,
+                    access_token,
+                    domain_id,
+                    &upload_id,
+                    part_number,
+                    chunk,
+                ))
+                .await
+                .inspect_err(|_| transfer.emit(part_event(part_number, PartStatus::Failed)))?;
+            transfer.emit(part_event(part_number, PartStatus::Uploaded));
+            transfer.bytes_sent(chunk_len);
 
             parts.push(CompletedPart {
                 part_number,
                 etag: res.etag,
             });
 
-            offset = end;
             part_number = part_number
                 .checked_add(1)
                 .ok_or(DomainError::InvalidRequest(
@@ -596,49 +752,130 @@ async fn upload_domain_data_multipart_bytes(
                 ))?;
         }
 
-        complete_domain_data_multipart_upload(
-            &client,
-            url,
-            access_token,
-            domain_id,
-            &upload_id,
-            parts,
-        )
-        .await
+        let meta = transfer
+            .run(complete_domain_data_multipart_upload(
+                transport,
+                url,
+                access_token,
+                domain_id,
+                &upload_id,
+                parts,
+            ))
+            .await?;
+        transfer.items_done(1);
+        Ok(meta)
     }
     .await;
 
     if upload_res.is_err() {
         let _ =
-            abort_domain_data_multipart_upload(&client, url, access_token, domain_id, &upload_id)
+            abort_domain_data_multipart_upload(transport, url, access_token, domain_id, &upload_id)
                 .await;
     }
 
     upload_res
 }
 
+/// Uploads one item read from `chunks`, holding about one multipart part in memory rather than
+/// the whole item. `size` is announced to the server when known. Servers without multipart
+/// uploads get the item in a single request, which needs it buffered.
+#[allow(clippy::too_many_arguments)]
+pub async fn upload_v1_from_stream<S>(
+    transport: &Transport,
+    url: &str,
+    access_token: &str,
+    domain_id: &str,
+    action: DomainAction,
+    size: Option<u64>,
+    chunks: S,
+    transfer: &TransferControl,
+) -> Result<DomainDataMetadata, DomainError>
+where
+    S: Stream<Item = Result<Bytes, DomainError>>,
+{
+    let multipart_enabled = get_upload_info_v1(transport, url)
+        .await
+        .is_some_and(|info| info.multipart_enabled);
+    if !multipart_enabled {
+        let data = transfer
+            .run(chunks.try_fold(Vec::new(), |mut data, chunk| async move {
+                data.extend_from_slice(&chunk);
+                Ok(data)
+            }))
+            .await?;
+        let mut res = upload_v1_with_progress(
+            transport,
+            url,
+            access_token,
+            domain_id,
+            vec![UploadDomainData { action, data }],
+            transfer,
+        )
+        .await?;
+        return res
+            .pop()
+            .ok_or(DomainError::InvalidRequest("server returned no metadata"));
+    }
+
+    transfer.set_totals(size, Some(1));
+    upload_domain_data_multipart(
+        transport,
+        url,
+        access_token,
+        domain_id,
+        action,
+        size,
+        chunks,
+        transfer,
+    )
+    .await
+}
+
 #[cfg(not(target_family = "wasm"))]
 pub async fn upload_v1_stream(
+    transport: &Transport,
+    url: &str,
+    access_token: &str,
+    domain_id: &str,
+    rx: mpsc::Receiver<UploadDomainData>,
+) -> Result<Vec<DomainDataMetadata>, DomainError> {
+    upload_v1_stream_with_progress(
+        transport,
+        url,
+        access_token,
+        domain_id,
+        rx,
+        &TransferControl::default(),
+    )
+    .await
+}
+
+/// Same as [`upload_v1_stream`], reporting progress to and stopping on cancellation of
+/// `transfer`.
+#[cfg(not(target_family = "wasm"))]
+pub async fn upload_v1_stream_with_progress(
+    transport: &Transport,
     url: &str,
     access_token: &str,
     domain_id: &str,
     mut rx: mpsc::Receiver<UploadDomainData>,
+    transfer: &TransferControl,
 ) -> Result<Vec<DomainDataMetadata>, DomainError> {
     use futures::channel::oneshot;
 
     let boundary = "boundary";
 
-    let info = get_upload_info_v1(url).await;
+    let info = get_upload_info_v1(transport, url).await;
     let request_max_bytes = info.as_ref().map(|i| i.request_max_bytes).unwrap_or(0);
     let multipart_enabled = info.as_ref().map(|i| i.multipart_enabled).unwrap_or(false);
 
     // If we can't determine a meaningful request size limit, keep the existing streaming behavior.
     if request_max_bytes <= 0 || !multipart_enabled {
-        let (mut create_tx, create_rx) = mpsc::channel(100);
-        let (mut update_tx, update_rx) = mpsc::channel(100);
+        let (mut create_tx, create_rx) = mpsc::channel::<Vec<BodyChunk>>(100);
+        let (mut update_tx, update_rx) = mpsc::channel::<Vec<BodyChunk>>(100);
 
-        let create_body = Body::wrap_stream(create_rx.map(Ok::<Vec<u8>, std::io::Error>));
-        let update_body = Body::wrap_stream(update_rx.map(Ok::<Vec<u8>, std::io::Error>));
+        let create_body = UploadBody::streamed(create_rx.flat_map(futures::stream::iter), transfer);
+        let update_body = UploadBody::streamed(update_rx.flat_map(futures::stream::iter), transfer);
 
         let url = url.to_string();
         let url_2 = url.clone();
@@ -646,51 +883,73 @@ pub async fn upload_v1_stream(
         let domain_id = domain_id.to_string();
         let access_token_2 = access_token.clone();
         let domain_id_2 = domain_id.clone();
+        let transport_1 = transport.clone();
+        let transport_2 = transport.clone();
+        let transfer_1 = transfer.clone();
+        let transfer_2 = transfer.clone();
 
         let (create_signal, create_signal_rx) =
             oneshot::channel::<Result<Vec<DomainDataMetadata>, DomainError>>();
         let (update_signal, update_signal_rx) =
             oneshot::channel::<Result<Vec<DomainDataMetadata>, DomainError>>();
 
-        spawn(async move {
-            let create_response =
-                create_v1(&url, &access_token, &domain_id, boundary, create_body).await;
-            if let Err(Err(e)) = create_signal.send(create_response) {
-                tracing::error!("Failed to send create response: {}", e);
+        spawn(
+            async move {
+                let create_response = create_v1(
+                    &transport_1,
+                    &url,
+                    &access_token,
+                    &domain_id,
+                    boundary,
+                    create_body,
+                    &transfer_1,
+                )
+                .await;
+                if let Err(Err(e)) = create_signal.send(create_response) {
+                    tracing::error!("Failed to send create response: {}", e);
+                }
             }
-        });
+            .in_current_span(),
+        );
 
-        spawn(async move {
-            let update_response =
-                update_v1(&url_2, &access_token_2, &domain_id_2, boundary, update_body).await;
-            if let Err(Err(e)) = update_signal.send(update_response) {
-                tracing::error!("Failed to send update response: {}", e);
+        spawn(
+            async move {
+                let update_response = update_v1(
+                    &transport_2,
+                    &url_2,
+                    &access_token_2,
+                    &domain_id_2,
+                    boundary,
+                    update_body,
+                    &transfer_2,
+                )
+                .await;
+                if let Err(Err(e)) = update_signal.send(update_response) {
+                    tracing::error!("Failed to send update response: {}", e);
+                }
             }
-        });
+            .in_current_span(),
+        );
 
-        while let Some(datum) = rx.next().await {
+        while let Some(datum) = transfer.run(async { Ok(rx.next().await) }).await? {
             match datum.action {
                 DomainAction::Create { name, data_type } => {
                     let create_data = write_create_body(
                         boundary,
                         &CreateDomainData { name, data_type },
-                        &datum.data,
+                        datum.data.into(),
                     );
                     create_tx.clone().send(create_data).await?;
                 }
                 DomainAction::Update { id } => {
                     let update_data =
-                        write_update_body(boundary, &UpdateDomainData { id }, &datum.data);
+                        write_update_body(boundary, &UpdateDomainData { id }, datum.data.into());
                     update_tx.send(update_data).await?;
                 }
             }
         }
-        update_tx
-            .send(format!("--{}--\r\n", boundary).as_bytes().to_vec())
-            .await?;
-        create_tx
-            .send(format!("--{}--\r\n", boundary).as_bytes().to_vec())
-            .await?;
+        update_tx.send(vec![closing_chunk(boundary)]).await?;
+        create_tx.send(vec![closing_chunk(boundary)]).await?;
         update_tx.close().await?;
         create_tx.close().await?;
 
@@ -715,11 +974,11 @@ pub async fn upload_v1_stream(
         return Ok(data);
     }
 
-    let closing = format!("--{}--\r\n", boundary).into_bytes();
-    let closing_len = closing.len();
+    let closing = closing_chunk(boundary);
+    let closing_len = closing.0.len();
 
     struct Batch {
-        tx: mpsc::Sender<Vec<u8>>,
+        tx: mpsc::Sender<Vec<BodyChunk>>,
         done: oneshot::Receiver<Result<Vec<DomainDataMetadata>, DomainError>>,
         size: usize,
     }
@@ -732,16 +991,30 @@ pub async fn upload_v1_stream(
     let mut update_res = Vec::new();
 
     let spawn_create_batch = |url: String, access_token: String, domain_id: String| {
-        let (tx, rx) = mpsc::channel(100);
-        let body = Body::wrap_stream(rx.map(Ok::<Vec<u8>, std::io::Error>));
+        let (tx, rx) = mpsc::channel::<Vec<BodyChunk>>(100);
+        let body = UploadBody::streamed(rx.flat_map(futures::stream::iter), transfer);
         let (signal, signal_rx) =
             oneshot::channel::<Result<Vec<DomainDataMetadata>, DomainError>>();
-        spawn(async move {
-            let create_response = create_v1(&url, &access_token, &domain_id, boundary, body).await;
-            if let Err(Err(e)) = signal.send(create_response) {
-                tracing::error!("Failed to send create response: {}", e);
+        let transport = transport.clone();
+        let transfer = transfer.clone();
+        spawn(
+            async move {
+                let create_response = create_v1(
+                    &transport,
+                    &url,
+                    &access_token,
+                    &domain_id,
+                    boundary,
+                    body,
+                    &transfer,
+                )
+                .await;
+                if let Err(Err(e)) = signal.send(create_response) {
+                    tracing::error!("Failed to send create response: {}", e);
+                }
             }
-        });
+            .in_current_span(),
+        );
         Batch {
             tx,
             done: signal_rx,
@@ -750,16 +1023,30 @@ pub async fn upload_v1_stream(
     };
 
     let spawn_update_batch = |url: String, access_token: String, domain_id: String| {
-        let (tx, rx) = mpsc::channel(100);
-        let body = Body::wrap_stream(rx.map(Ok::<Vec<u8>, std::io::Error>));
+        let (tx, rx) = mpsc::channel::<Vec<BodyChunk>>(100);
+        let body = UploadBody::streamed(rx.flat_map(futures::stream::iter), transfer);
         let (signal, signal_rx) =
             oneshot::channel::<Result<Vec<DomainDataMetadata>, DomainError>>();
-        spawn(async move {
-            let update_response = update_v1(&url, &access_token, &domain_id, boundary, body).await;
-            if let Err(Err(e)) = signal.send(update_response) {
-                tracing::error!("Failed to send update response: {}", e);
+        let transport = transport.clone();
+        let transfer = transfer.clone();
+        spawn(
+            async move {
+                let update_response = update_v1(
+                    &transport,
+                    &url,
+                    &access_token,
+                    &domain_id,
+                    boundary,
+                    body,
+                    &transfer,
+                )
+                .await;
+                if let Err(Err(e)) = signal.send(update_response) {
+                    tracing::error!("Failed to send update response: {}", e);
+                }
             }
-        });
+            .in_current_span(),
+        );
         Batch {
             tx,
             done: signal_rx,
@@ -771,7 +1058,7 @@ pub async fn upload_v1_stream(
     let token = access_token.to_string();
     let did = domain_id.to_string();
 
-    while let Some(datum) = rx.next().await {
+    while let Some(datum) = transfer.run(async { Ok(rx.next().await) }).await? {
         let bytes = Bytes::from(datum.data);
         match datum.action {
             DomainAction::Create { name, data_type } => {
@@ -784,6 +1071,7 @@ pub async fn upload_v1_stream(
                 let fits_alone = (part_len + closing_len) as i64 <= request_max_bytes;
                 if multipart_enabled && !fits_alone {
                     match upload_domain_data_multipart_bytes(
+                        transport,
                         &base_url,
                         &token,
                         &did,
@@ -792,6 +1080,7 @@ pub async fn upload_v1_stream(
                             data_type: data_type.clone(),
                         },
                         bytes.clone(),
+                        transfer,
                     )
                     .await
                     {
@@ -802,14 +1091,18 @@ pub async fn upload_v1_stream(
                         Err(e) => {
                             if is_unsupported_endpoint_error(&e) {
                                 // Endpoint not supported: fall back to single upload (will likely 413).
-                                let mut body = Vec::with_capacity(part_len + closing.len());
-                                body.extend_from_slice(header.as_bytes());
-                                body.extend_from_slice(bytes.as_ref());
-                                body.extend_from_slice("\r\n".as_bytes());
-                                body.extend_from_slice(&closing);
-                                let res =
-                                    create_v1(&base_url, &token, &did, boundary, Body::from(body))
-                                        .await?;
+                                let mut body = part_chunks(header, bytes);
+                                body.push(closing.clone());
+                                let res = create_v1(
+                                    transport,
+                                    &base_url,
+                                    &token,
+                                    &did,
+                                    boundary,
+                                    UploadBody::new(body, transfer),
+                                    transfer,
+                                )
+                                .await?;
                                 create_res.extend(res);
                                 continue;
                             }
@@ -829,17 +1122,13 @@ pub async fn upload_v1_stream(
                 if batch.size > 0
                     && (batch.size + part_len + closing_len) as i64 > request_max_bytes
                 {
-                    batch.tx.send(closing.clone()).await?;
+                    batch.tx.send(vec![closing.clone()]).await?;
                     batch.tx.close().await?;
                     create_done.push(batch.done);
                     batch = spawn_create_batch(base_url.clone(), token.clone(), did.clone());
                 }
-                let mut part = Vec::with_capacity(part_len);
-                part.extend_from_slice(header.as_bytes());
-                part.extend_from_slice(bytes.as_ref());
-                part.extend_from_slice("\r\n".as_bytes());
-                batch.size += part.len();
-                batch.tx.send(part).await?;
+                batch.size += part_len;
+                batch.tx.send(part_chunks(header, bytes)).await?;
                 create_batch = Some(batch);
             }
             DomainAction::Update { id } => {
@@ -852,11 +1141,13 @@ pub async fn upload_v1_stream(
                 let fits_alone = (part_len + closing_len) as i64 <= request_max_bytes;
                 if multipart_enabled && !fits_alone {
                     match upload_domain_data_multipart_bytes(
+                        transport,
                         &base_url,
                         &token,
                         &did,
                         DomainAction::Update { id: id.clone() },
                         bytes.clone(),
+                        transfer,
                     )
                     .await
                     {
@@ -866,14 +1157,18 @@ pub async fn upload_v1_stream(
                         }
                         Err(e) => {
                             if is_unsupported_endpoint_error(&e) {
-                                let mut body = Vec::with_capacity(part_len + closing.len());
-                                body.extend_from_slice(header.as_bytes());
-                                body.extend_from_slice(bytes.as_ref());
-                                body.extend_from_slice("\r\n".as_bytes());
-                                body.extend_from_slice(&closing);
-                                let res =
-                                    update_v1(&base_url, &token, &did, boundary, Body::from(body))
-                                        .await?;
+                                let mut body = part_chunks(header, bytes);
+                                body.push(closing.clone());
+                                let res = update_v1(
+                                    transport,
+                                    &base_url,
+                                    &token,
+                                    &did,
+                                    boundary,
+                                    UploadBody::new(body, transfer),
+                                    transfer,
+                                )
+                                .await?;
                                 update_res.extend(res);
                                 continue;
                             }
@@ -893,29 +1188,25 @@ pub async fn upload_v1_stream(
                 if batch.size > 0
                     && (batch.size + part_len + closing_len) as i64 > request_max_bytes
                 {
-                    batch.tx.send(closing.clone()).await?;
+                    batch.tx.send(vec![closing.clone()]).await?;
                     batch.tx.close().await?;
                     update_done.push(batch.done);
                     batch = spawn_update_batch(base_url.clone(), token.clone(), did.clone());
                 }
-                let mut part = Vec::with_capacity(part_len);
-                part.extend_from_slice(header.as_bytes());
-                part.extend_from_slice(bytes.as_ref());
-                part.extend_from_slice("\r\n".as_bytes());
-                batch.size += part.len();
-                batch.tx.send(part).await?;
+                batch.size += part_len;
+                batch.tx.send(part_chunks(header, bytes)).await?;
                 update_batch = Some(batch);
             }
         }
     }
 
     if let Some(mut batch) = create_batch {
-        batch.tx.send(closing.clone()).await?;
+        batch.tx.send(vec![closing.clone()]).await?;
         batch.tx.close().await?;
         create_done.push(batch.done);
     }
     if let Some(mut batch) = update_batch {
-        batch.tx.send(closing.clone()).await?;
+        batch.tx.send(vec![closing.clone()]).await?;
         batch.tx.close().await?;
         update_done.push(batch.done);
     }
@@ -943,22 +1234,44 @@ pub async fn upload_v1_stream(
 }
 
 async fn update_v1(
+    transport: &Transport,
     url: &str,
     access_token: &str,
     domain_id: &str,
     boundary: &str,
-    body: Body,
+    body: UploadBody,
+    transfer: &TransferControl,
 ) -> Result<Vec<DomainDataMetadata>, DomainError> {
-    let update_response = Client::new()
+    let data = transfer
+        .run(update_v1_request(
+            transport,
+            url,
+            access_token,
+            domain_id,
+            boundary,
+            body,
+        ))
+        .await?;
+    transfer.items_done(data.len() as u64);
+    Ok(data)
+}
+
+async fn update_v1_request(
+    transport: &Transport,
+    url: &str,
+    access_token: &str,
+    domain_id: &str,
+    boundary: &str,
+    body: UploadBody,
+) -> Result<Vec<DomainDataMetadata>, DomainError> {
+    let request = transport
         .put(format!("{}/api/v1/domains/{}/data", url, domain_id))
         .bearer_auth(access_token)
         .header(
             "Content-Type",
             &format!("multipart/form-data; boundary={}", boundary),
-        )
-        .body(body)
-        .send()
-        .await?;
+        );
+    let update_response = body.send(request, transport).await?;
 
     if update_response.status().is_success() {
         let data = update_response
@@ -967,36 +1280,53 @@ async fn update_v1(
             .unwrap();
         Ok(data.data)
     } else {
-        let status = update_response.status();
-        let err = update_response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        Err(AukiErrorResponse {
-            status,
-            error: format!("Failed to update data. {}", err),
-        }
-        .into())
+        Err(
+            AukiErrorResponse::from_response(update_response, "Failed to update data.")
+                .await
+                .into(),
+        )
     }
 }
 
 async fn create_v1(
+    transport: &Transport,
+    url: &str,
+    access_token: &str,
+    domain_id: &str,
+    boundary: &str,
+    body: UploadBody,
+    transfer: &TransferControl,
+) -> Result<Vec<DomainDataMetadata>, DomainError> {
+    let data = transfer
+        .run(create_v1_request(
+            transport,
+            url,
+            access_token,
+            domain_id,
+            boundary,
+            body,
+        ))
+        .await?;
+    transfer.items_done(data.len() as u64);
+    Ok(data)
+}
+
+async fn create_v1_request(
+    transport: &Transport,
     url: &str,
     access_token: &str,
     domain_id: &str,
     boundary: &str,
-    body: Body,
+    body: UploadBody,
 ) -> Result<Vec<DomainDataMetadata>, DomainError> {
-    let create_response = Client::new()
+    let request = transport
         .post(format!("{}/api/v1/domains/{}/data", url, domain_id))
         .bearer_auth(access_token)
         .header(
             "Content-Type",
             &format!("multipart/form-data; boundary={}", boundary),
-        )
-        .body(body)
-        .send()
-        .await?;
+        );
+    let create_response = body.send(request, transport).await?;
 
     if create_response.status().is_success() {
         let data = create_response
@@ -1005,50 +1335,174 @@ async fn create_v1(
             .unwrap();
         Ok(data.data)
     } else {
-        let status = create_response.status();
-        let err = create_response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        Err(AukiErrorResponse {
-            status,
-            error: format!("Failed to create data. {}", err),
+        Err(
+            AukiErrorResponse::from_response(create_response, "Failed to create data.")
+                .await
+                .into(),
+        )
+    }
+}
+
+// Item bytes go into request bodies in chunks of this size, so upload progress moves while a
+// request is being sent.
+const BODY_CHUNK_SIZE: usize = 64 * 1024;
+
+/// A chunk of a multipart request body and how many item bytes it holds.
+type BodyChunk = (Bytes, u64);
+
+/// Chunks of one multipart part: its header, the item bytes and the closing line break.
+fn part_chunks(header: String, mut data: Bytes) -> Vec<BodyChunk> {
+    let mut chunks = vec![(Bytes::from(header), 0)];
+    while !data.is_empty() {
+        let chunk = data.split_to(data.len().min(BODY_CHUNK_SIZE));
+        let len = chunk.len() as u64;
+        chunks.push((chunk, len));
+    }
+    chunks.push((Bytes::from_static(b"\r\n"), 0));
+    chunks
+}
+
+fn closing_chunk(boundary: &str) -> BodyChunk {
+    (Bytes::from(format!("--{}--\r\n", boundary)), 0)
+}
+
+/// Multipart body of a create or update request. Natively the item bytes are reported to the
+/// transfer as the body is sent; in the browser the body is sent in one piece and they are
+/// reported once the request is done.
+enum UploadBody {
+    /// The whole body, built again for every attempt so the request can be retried.
+    Buffered(Vec<BodyChunk>, TransferControl),
+    /// A body read from a channel while it is sent, so the request is sent only once.
+    #[cfg(not(target_family = "wasm"))]
+    Streamed(Body),
+}
+
+impl UploadBody {
+    fn new(chunks: Vec<BodyChunk>, transfer: &TransferControl) -> Self {
+        Self::Buffered(chunks, transfer.clone())
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    fn streamed(
+        chunks: impl Stream<Item = BodyChunk> + Send + 'static,
+        transfer: &TransferControl,
+    ) -> Self {
+        Self::Streamed(Body::wrap_stream(report_sent(
+            chunks,
+            transfer.clone(),
+            Arc::default(),
+        )))
+    }
+
+    async fn send(
+        self,
+        request: RequestBuilder,
+        transport: &Transport,
+    ) -> Result<Response, DomainError> {
+        match self {
+            #[cfg(not(target_family = "wasm"))]
+            Self::Streamed(body) => request.body(body).send_via(transport).await,
+            #[cfg(not(target_family = "wasm"))]
+            Self::Buffered(chunks, transfer) => {
+                let len: usize = chunks.iter().map(|(chunk, _)| chunk.len()).sum();
+                let reported = Arc::new(AtomicU64::new(0));
+                transport
+                    .send_with_body(request.header(CONTENT_LENGTH, len), move || {
+                        Body::wrap_stream(report_sent(
+                            futures::stream::iter(chunks.clone()),
+                            transfer.clone(),
+                            reported.clone(),
+                        ))
+                    })
+                    .await
+            }
+            #[cfg(target_family = "wasm")]
+            Self::Buffered(chunks, transfer) => {
+                let mut body = BytesMut::new();
+                let mut sent = 0;
+                for (chunk, len) in chunks {
+                    body.extend_from_slice(&chunk);
+                    sent += len;
+                }
+                let response = request.body(body.freeze()).send_via(transport).await?;
+                if response.status().is_success() && sent > 0 {
+                    transfer.bytes_sent(sent);
+                }
+                Ok(response)
+            }
         }
-        .into())
     }
 }
 
-fn write_create_body(boundary: &str, data: &CreateDomainData, data_bytes: &[u8]) -> Vec<u8> {
-    let create_bytes = format!(
+/// Reports the item bytes of `chunks` to `transfer` as they are read. `reported` is shared by
+/// all attempts to send the same body, so a retry only reports bytes no earlier attempt did.
+#[cfg(not(target_family = "wasm"))]
+fn report_sent(
+    chunks: impl Stream<Item = BodyChunk>,
+    transfer: TransferControl,
+    reported: Arc<AtomicU64>,
+) -> impl Stream<Item = Result<Bytes, std::io::Error>> {
+    let mut read = 0;
+    chunks.map(move |(chunk, sent)| {
+        read += sent;
+        let before = reported.fetch_max(read, Ordering::SeqCst);
+        if read > before {
+            transfer.bytes_sent(read - before);
+        }
+        Ok(chunk)
+    })
+}
+
+fn write_create_body(boundary: &str, data: &CreateDomainData, data_bytes: Bytes) -> Vec<BodyChunk> {
+    let header = format!(
         "--{}\r\nContent-Type: application/octet-stream\r\nContent-Disposition: form-data; name=\"{}\"; data-type=\"{}\"\r\n\r\n",
         boundary, data.name, data.data_type
     );
-    let mut create_data = create_bytes.into_bytes();
-    create_data.extend_from_slice(data_bytes);
-    create_data.extend_from_slice("\r\n".as_bytes());
-    create_data
+    part_chunks(header, data_bytes)
 }
 
-fn write_update_body(boundary: &str, data: &UpdateDomainData, data_bytes: &[u8]) -> Vec<u8> {
-    let update_bytes = format!(
+fn write_update_body(boundary: &str, data: &UpdateDomainData, data_bytes: Bytes) -> Vec<BodyChunk> {
+    let header = format!(
         "--{}\r\nContent-Type: application/octet-stream\r\nContent-Disposition: form-data; id=\"{}\"\r\n\r\n",
         boundary, data.id
     );
-    let mut update_data = update_bytes.into_bytes();
-    update_data.extend_from_slice(data_bytes);
-    update_data.extend_from_slice("\r\n".as_bytes());
-    update_data
+    part_chunks(header, data_bytes)
 }
 
 pub async fn upload_v1(
+    transport: &Transport,
     url: &str,
     access_token: &str,
     domain_id: &str,
     data: Vec<UploadDomainData>,
+) -> Result<Vec<DomainDataMetadata>, DomainError> {
+    upload_v1_with_progress(
+        transport,
+        url,
+        access_token,
+        domain_id,
+        data,
+        &TransferControl::default(),
+    )
+    .await
+}
+
+/// Same as [`upload_v1`], reporting progress to and stopping on cancellation of `transfer`.
+pub async fn upload_v1_with_progress(
+    transport: &Transport,
+    url: &str,
+    access_token: &str,
+    domain_id: &str,
+    data: Vec<UploadDomainData>,
+    transfer: &TransferControl,
 ) -> Result<Vec<DomainDataMetadata>, DomainError> {
     let boundary = "boundary";
+    transfer.set_totals(
+        Some(data.iter().map(|d| d.data.len() as u64).sum()),
+        Some(data.len() as u64),
+    );
 
-    let info = get_upload_info_v1(url).await;
+    let info = get_upload_info_v1(transport, url).await;
     let request_max_bytes = info.as_ref().map(|i| i.request_max_bytes).unwrap_or(0);
     let multipart_enabled = info.as_ref().map(|i| i.multipart_enabled).unwrap_or(false);
 
@@ -1066,32 +1520,49 @@ pub async fn upload_v1(
                     let create_data = write_create_body(
                         boundary,
                         &CreateDomainData { name, data_type },
-                        &datum.data,
+                        datum.data.into(),
                     );
-                    create_body.extend_from_slice(&create_data);
+                    create_body.extend(create_data);
                 }
                 DomainAction::Update { id } => {
                     to_update = true;
                     let update_data =
-                        write_update_body(boundary, &UpdateDomainData { id }, &datum.data);
-                    update_body.extend_from_slice(&update_data);
+                        write_update_body(boundary, &UpdateDomainData { id }, datum.data.into());
+                    update_body.extend(update_data);
                 }
             }
         }
 
-        create_body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
-        update_body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+        create_body.push(closing_chunk(boundary));
+        update_body.push(closing_chunk(boundary));
 
-        let create_body = Body::from(create_body);
-        let update_body = Body::from(update_body);
+        let create_body = UploadBody::new(create_body, transfer);
+        let update_body = UploadBody::new(update_body, transfer);
         let mut res = Vec::new();
 
         if to_create {
-            res = create_v1(url, access_token, domain_id, boundary, create_body).await?;
+            res = create_v1(
+                transport,
+                url,
+                access_token,
+                domain_id,
+                boundary,
+                create_body,
+                transfer,
+            )
+            .await?;
         }
         if to_update {
-            let update_response =
-                update_v1(url, access_token, domain_id, boundary, update_body).await?;
+            let update_response = update_v1(
+                transport,
+                url,
+                access_token,
+                domain_id,
+                boundary,
+                update_body,
+                transfer,
+            )
+            .await?;
             if !update_response.is_empty() {
                 res.extend(update_response);
             }
@@ -1100,8 +1571,8 @@ pub async fn upload_v1(
         return Ok(res);
     }
 
-    let closing = format!("--{}--\r\n", boundary).into_bytes();
-    let closing_len = closing.len();
+    let closing = closing_chunk(boundary);
+    let closing_len = closing.0.len();
 
     let mut create_res = Vec::new();
     let mut update_res = Vec::new();
@@ -1126,14 +1597,23 @@ pub async fn upload_v1(
                 if multipart_enabled && !fits_alone {
                     if !create_batch.is_empty() {
                         let mut body = std::mem::take(&mut create_batch);
-                        body.extend_from_slice(&closing);
+                        body.push(closing.clone());
                         create_res.extend(
-                            create_v1(url, access_token, domain_id, boundary, Body::from(body))
-                                .await?,
+                            create_v1(
+                                transport,
+                                url,
+                                access_token,
+                                domain_id,
+                                boundary,
+                                UploadBody::new(body, transfer),
+                                transfer,
+                            )
+                            .await?,
                         );
                         create_size = 0;
                     }
                     match upload_domain_data_multipart_bytes(
+                        transport,
                         url,
                         access_token,
                         domain_id,
@@ -1142,6 +1622,7 @@ pub async fn upload_v1(
                             data_type: data_type.clone(),
                         },
                         bytes.clone(),
+                        transfer,
                     )
                     .await
                     {
@@ -1151,18 +1632,17 @@ pub async fn upload_v1(
                         Err(e) => {
                             if is_unsupported_endpoint_error(&e) {
                                 // Fall back to single upload (will likely 413).
-                                let mut body = Vec::with_capacity(part_len + closing.len());
-                                body.extend_from_slice(header.as_bytes());
-                                body.extend_from_slice(bytes.as_ref());
-                                body.extend_from_slice("\r\n".as_bytes());
-                                body.extend_from_slice(&closing);
+                                let mut body = part_chunks(header, bytes);
+                                body.push(closing.clone());
                                 create_res.extend(
                                     create_v1(
+                                        transport,
                                         url,
                                         access_token,
                                         domain_id,
                                         boundary,
-                                        Body::from(body),
+                                        UploadBody::new(body, transfer),
+                                        transfer,
                                     )
                                     .await?,
                                 );
@@ -1178,15 +1658,22 @@ pub async fn upload_v1(
                     && (create_size + part_len + closing_len) as i64 > request_max_bytes
                 {
                     let mut body = std::mem::take(&mut create_batch);
-                    body.extend_from_slice(&closing);
+                    body.push(closing.clone());
                     create_res.extend(
-                        create_v1(url, access_token, domain_id, boundary, Body::from(body)).await?,
+                        create_v1(
+                            transport,
+                            url,
+                            access_token,
+                            domain_id,
+                            boundary,
+                            UploadBody::new(body, transfer),
+                            transfer,
+                        )
+                        .await?,
                     );
                     create_size = 0;
                 }
-                create_batch.extend_from_slice(header.as_bytes());
-                create_batch.extend_from_slice(bytes.as_ref());
-                create_batch.extend_from_slice("\r\n".as_bytes());
+                create_batch.extend(part_chunks(header, bytes));
                 create_size += part_len;
             }
             DomainAction::Update { id } => {
@@ -1200,19 +1687,29 @@ pub async fn upload_v1(
                 if multipart_enabled && !fits_alone {
                     if !update_batch.is_empty() {
                         let mut body = std::mem::take(&mut update_batch);
-                        body.extend_from_slice(&closing);
+                        body.push(closing.clone());
                         update_res.extend(
-                            update_v1(url, access_token, domain_id, boundary, Body::from(body))
-                                .await?,
+                            update_v1(
+                                transport,
+                                url,
+                                access_token,
+                                domain_id,
+                                boundary,
+                                UploadBody::new(body, transfer),
+                                transfer,
+                            )
+                            .await?,
                         );
                         update_size = 0;
                     }
                     match upload_domain_data_multipart_bytes(
+                        transport,
                         url,
                         access_token,
                         domain_id,
                         DomainAction::Update { id: id.clone() },
                         bytes.clone(),
+                        transfer,
                     )
                     .await
                     {
@@ -1221,18 +1718,17 @@ pub async fn upload_v1(
                         }
                         Err(e) => {
                             if is_unsupported_endpoint_error(&e) {
-                                let mut body = Vec::with_capacity(part_len + closing.len());
-                                body.extend_from_slice(header.as_bytes());
-                                body.extend_from_slice(bytes.as_ref());
-                                body.extend_from_slice("\r\n".as_bytes());
-                                body.extend_from_slice(&closing);
+                                let mut body = part_chunks(header, bytes);
+                                body.push(closing.clone());
                                 update_res.extend(
                                     update_v1(
+                                        transport,
                                         url,
                                         access_token,
                                         domain_id,
                                         boundary,
-                                        Body::from(body),
+                                        UploadBody::new(body, transfer),
+                                        transfer,
                                     )
                                     .await?,
                                 );
@@ -1248,15 +1744,22 @@ pub async fn upload_v1(
                     && (update_size + part_len + closing_len) as i64 > request_max_bytes
                 {
                     let mut body = std::mem::take(&mut update_batch);
-                    body.extend_from_slice(&closing);
+                    body.push(closing.clone());
                     update_res.extend(
-                        update_v1(url, access_token, domain_id, boundary, Body::from(body)).await?,
+                        update_v1(
+                            transport,
+                            url,
+                            access_token,
+                            domain_id,
+                            boundary,
+                            UploadBody::new(body, transfer),
+                            transfer,
+                        )
+                        .await?,
                     );
                     update_size = 0;
                 }
-                update_batch.extend_from_slice(header.as_bytes());
-                update_batch.extend_from_slice(bytes.as_ref());
-                update_batch.extend_from_slice("\r\n".as_bytes());
+                update_batch.extend(part_chunks(header, bytes));
                 update_size += part_len;
             }
         }
@@ -1264,15 +1767,35 @@ pub async fn upload_v1(
 
     if !create_batch.is_empty() {
         let mut body = create_batch;
-        body.extend_from_slice(&closing);
-        create_res
-            .extend(create_v1(url, access_token, domain_id, boundary, Body::from(body)).await?);
+        body.push(closing.clone());
+        create_res.extend(
+            create_v1(
+                transport,
+                url,
+                access_token,
+                domain_id,
+                boundary,
+                UploadBody::new(body, transfer),
+                transfer,
+            )
+            .await?,
+        );
     }
     if !update_batch.is_empty() {
         let mut body = update_batch;
-        body.extend_from_slice(&closing);
-        update_res
-            .extend(update_v1(url, access_token, domain_id, boundary, Body::from(body)).await?);
+        body.push(closing.clone());
+        update_res.extend(
+            update_v1(
+                transport,
+                url,
+                access_token,
+                domain_id,
+                boundary,
+                UploadBody::new(body, transfer),
+                transfer,
+            )
+            .await?,
+        );
     }
 
     let mut res = Vec::new();
@@ -1303,6 +1826,7 @@ fn parse_headers(
                         size: 0,
                         created_at: String::new(),
                         updated_at: String::new(),
+                        hash: None,
                     },
                     data: Vec::new(),
                 };
@@ -1327,6 +1851,7 @@ fn parse_headers(
                             "updated-at" => {
                                 parsed_domain_data.metadata.updated_at = value.to_string()
                             }
+                            "hash" => parsed_domain_data.metadata.hash = Some(value.to_string()),
                             _ => {}
                         }
                     }
@@ -1448,6 +1973,8 @@ mod tests {
     use super::*;
     use bytes::Bytes;
 
+    use crate::transport::test_support::{body_bytes, json_response};
+
     #[test]
     fn test_find_boundary_found() {
         let data = b"random--boundary--data";
@@ -1493,6 +2020,15 @@ mod tests {
         assert_eq!(domain_data.metadata.size, 42);
         assert_eq!(domain_data.metadata.created_at, "2024-01-01T00:00:00Z");
         assert_eq!(domain_data.metadata.updated_at, "2024-01-02T00:00:00Z");
+        assert_eq!(domain_data.metadata.hash, None);
+    }
+
+    #[test]
+    fn test_parse_headers_with_hash() {
+        let headers =
+            b"content-disposition: form-data; id=\"123\"; size=\"3\"; hash=\"sha256:abc\"\r\n\r\n";
+        let domain_data = super::parse_headers(headers).unwrap();
+        assert_eq!(domain_data.metadata.hash.as_deref(), Some("sha256:abc"));
     }
 
     #[test]
@@ -1670,4 +2206,356 @@ Content-Type: application/octet-stream
             "{\"test\": \"test\"}"
         );
     }
+
+    /// Domain server with a 64 byte request limit and 4 byte multipart parts.
+    struct MultipartServer {
+        cancel_on_part: Option<(i32, crate::progress::CancellationToken)>,
+        aborted: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    }
+
+    impl crate::transport::HttpExecutor for MultipartServer {
+        fn execute(&self, request: reqwest::Request) -> crate::transport::ExecuteFuture {
+            let query = request.url().query().unwrap_or_default().to_string();
+            let body = match (request.method().as_str(), query.as_str()) {
+                ("GET", _) => serde_json::json!({
+                    "upload": { "request_max_bytes": 64, "multipart": { "enabled": true } }
+                }),
+                ("POST", "uploads") => serde_json::json!({ "upload_id": "up", "part_size": 4 }),
+                ("PUT", _) => {
+                    if let Some((part, cancel)) = &self.cancel_on_part
+                        && query.ends_with(&format!("partNumber={part}"))
+                    {
+                        cancel.cancel();
+                    }
+                    serde_json::json!({ "etag": "e" })
+                }
+                ("DELETE", _) => {
+                    self.aborted
+                        .store(true, std::sync::atomic::Ordering::SeqCst);
+                    serde_json::json!({})
+                }
+                _ => serde_json::json!({
+                    "id": "id", "domain_id": "dom", "name": "scan", "data_type": "bin", "size": 10,
+                    "created_at": "2025-01-01T00:00:00Z", "updated_at": "2025-01-01T00:00:00Z",
+                }),
+            };
+            let res = json_response(200, &body);
+            Box::pin(async move { Ok(res) })
+        }
+    }
+
+    fn scan() -> Vec<UploadDomainData> {
+        vec![UploadDomainData {
+            action: DomainAction::Create {
+                name: "scan".to_string(),
+                data_type: "bin".to_string(),
+            },
+            data: vec![7; 10],
+        }]
+    }
+
+    #[tokio::test]
+    async fn test_multipart_upload_reports_progress() {
+        let transport = Transport::with_executor(MultipartServer {
+            cancel_on_part: None,
+            aborted: Default::default(),
+        });
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = events.clone();
+        let transfer = TransferControl::new().on_event(move |e| seen.lock().unwrap().push(e));
+
+        let res = upload_v1_with_progress(
+            &transport,
+            "http://multipart-progress",
+            "token",
+            "dom",
+            scan(),
+            &transfer,
+        )
+        .await
+        .unwrap();
+        assert_eq!(res.len(), 1);
+
+        let events = events.lock().unwrap();
+        let sent: Vec<_> = events
+            .iter()
+            .filter_map(|e| match e {
+                TransferEvent::BytesSent { sent, total } => Some((*sent, *total)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(sent, [(4, Some(10)), (8, Some(10)), (10, Some(10))]);
+        assert_eq!(
+            events[..2],
+            [
+                TransferEvent::Part {
+                    name: "scan".to_string(),
+                    part_number: 1,
+                    total_parts: Some(3),
+                    status: PartStatus::Uploading,
+                },
+                TransferEvent::Part {
+                    name: "scan".to_string(),
+                    part_number: 1,
+                    total_parts: Some(3),
+                    status: PartStatus::Uploaded,
+                },
+            ]
+        );
+        assert_eq!(
+            events.last(),
+            Some(&TransferEvent::ItemsDone {
+                done: 1,
+                total: Some(1)
+            })
+        );
+    }
+
+    /// Domain server without upload limits; records the bytes reported as sent once it read a
+    /// request body.
+    struct SingleRequestServer {
+        sent: std::sync::Arc<std::sync::atomic::AtomicU64>,
+        sent_when_read: std::sync::Arc<std::sync::Mutex<Vec<u64>>>,
+    }
+
+    impl crate::transport::HttpExecutor for SingleRequestServer {
+        fn execute(&self, mut request: reqwest::Request) -> crate::transport::ExecuteFuture {
+            let (status, body) = if request.url().path().ends_with("/info") {
+                (404, serde_json::json!({}))
+            } else {
+                body_bytes(&mut request);
+                self.sent_when_read
+                    .lock()
+                    .unwrap()
+                    .push(self.sent.load(std::sync::atomic::Ordering::SeqCst));
+                let meta = serde_json::json!({
+                    "id": "id", "domain_id": "dom", "name": "scan", "data_type": "bin",
+                    "size": 150_000, "created_at": "2025-01-01T00:00:00Z",
+                    "updated_at": "2025-01-01T00:00:00Z",
+                });
+                (200, serde_json::json!({ "data": [meta] }))
+            };
+            let res = json_response(status, &body);
+            Box::pin(async move { Ok(res) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_single_request_upload_reports_progress_while_sending() {
+        let sent = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
+        let sent_when_read = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let transport = Transport::with_executor(SingleRequestServer {
+            sent: sent.clone(),
+            sent_when_read: sent_when_read.clone(),
+        });
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = events.clone();
+        let transfer = TransferControl::new().on_event(move |e| {
+            if let TransferEvent::BytesSent { sent: bytes, .. } = e {
+                sent.store(bytes, std::sync::atomic::Ordering::SeqCst);
+            }
+            seen.lock().unwrap().push(e);
+        });
+        let data = vec![UploadDomainData {
+            action: DomainAction::Create {
+                name: "scan".to_string(),
+                data_type: "bin".to_string(),
+            },
+            data: vec![7; 150_000],
+        }];
+
+        upload_v1_with_progress(
+            &transport,
+            "http://single-request-progress",
+            "token",
+            "dom",
+            data,
+            &transfer,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(*sent_when_read.lock().unwrap(), [150_000]);
+        let sent: Vec<_> = events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|e| match e {
+                TransferEvent::BytesSent { sent, total } => Some((*sent, *total)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            sent,
+            [
+                (65_536, Some(150_000)),
+                (131_072, Some(150_000)),
+                (150_000, Some(150_000)),
+            ]
+        );
+    }
+
+    /// Domain server without upload limits that answers the first update with 503; records the
+    /// size of every update body it reads and checks it against its `Content-Length`.
+    struct FlakyUpdateServer {
+        updates: std::sync::Arc<std::sync::Mutex<Vec<usize>>>,
+    }
+
+    impl crate::transport::HttpExecutor for FlakyUpdateServer {
+        fn execute(&self, mut request: reqwest::Request) -> crate::transport::ExecuteFuture {
+            let (status, body) = if request.url().path().ends_with("/info") {
+                (404, serde_json::json!({}))
+            } else {
+                let content_length = request.headers()[reqwest::header::CONTENT_LENGTH].clone();
+                let len = body_bytes(&mut request).len();
+                assert_eq!(content_length, len.to_string());
+                let mut updates = self.updates.lock().unwrap();
+                updates.push(len);
+                if updates.len() == 1 {
+                    (503, serde_json::json!({ "message": "unavailable" }))
+                } else {
+                    let meta = serde_json::json!({
+                        "id": "id", "domain_id": "dom", "name": "scan", "data_type": "bin",
+                        "size": 150_000, "created_at": "2025-01-01T00:00:00Z",
+                        "updated_at": "2025-01-01T00:00:00Z",
+                    });
+                    (200, serde_json::json!({ "data": [meta] }))
+                }
+            };
+            let res = json_response(status, &body);
+            Box::pin(async move { Ok(res) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_is_retried_after_503() {
+        let updates = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let transport = Transport::with_executor(FlakyUpdateServer {
+            updates: updates.clone(),
+        })
+        .with_retry_policy(crate::retry::RetryPolicy {
+            initial_backoff: std::time::Duration::from_millis(1),
+            ..Default::default()
+        });
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = events.clone();
+        let transfer = TransferControl::new().on_event(move |e| seen.lock().unwrap().push(e));
+        let data = vec![UploadDomainData {
+            action: DomainAction::Update {
+                id: "id".to_string(),
+            },
+            data: vec![7; 150_000],
+        }];
+
+        let res = upload_v1_with_progress(
+            &transport,
+            "http://flaky-update",
+            "token",
+            "dom",
+            data,
+            &transfer,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(res.len(), 1);
+        let updates = updates.lock().unwrap();
+        assert_eq!(updates.len(), 2);
+        assert_eq!(updates[0], updates[1]);
+        let sent: Vec<_> = events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|e| match e {
+                TransferEvent::BytesSent { sent, .. } => Some(*sent),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(sent, [65_536, 131_072, 150_000]);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_multipart_upload_is_aborted() {
+        let transfer = TransferControl::new();
+        let aborted = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let transport = Transport::with_executor(MultipartServer {
+            cancel_on_part: Some((2, transfer.cancellation().clone())),
+            aborted: aborted.clone(),
+        });
+
+        let res = upload_v1_with_progress(
+            &transport,
+            "http://multipart-cancel",
+            "token",
+            "dom",
+            scan(),
+            &transfer,
+        )
+        .await;
+        assert!(matches!(res, Err(DomainError::Cancelled)));
+        assert!(aborted.load(std::sync::atomic::Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_part_buffer_cuts_across_chunks() {
+        let mut buffer = PartBuffer::default();
+        buffer.push(Bytes::from_static(b"abc"));
+        buffer.push(Bytes::new());
+        buffer.push(Bytes::from_static(b"defghij"));
+        assert_eq!(buffer.take(2), Bytes::from_static(b"ab"));
+        assert_eq!(buffer.take(4), Bytes::from_static(b"cdef"));
+        assert_eq!(buffer.take(4), Bytes::from_static(b"ghij"));
+        assert_eq!(buffer.len, 0);
+        assert!(buffer.take(4).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_upload_from_stream_of_unknown_size() {
+        let transport = Transport::with_executor(MultipartServer {
+            cancel_on_part: None,
+            aborted: Default::default(),
+        });
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = events.clone();
+        let transfer = TransferControl::new().on_event(move |e| seen.lock().unwrap().push(e));
+        let chunks = futures::stream::iter(
+            [&b"abc"[..], b"defgh", b"ij"].map(|chunk| Ok(Bytes::from_static(chunk))),
+        );
+
+        let meta = upload_v1_from_stream(
+            &transport,
+            "http://multipart-stream",
+            "token",
+            "dom",
+            DomainAction::Create {
+                name: "scan".to_string(),
+                data_type: "bin".to_string(),
+            },
+            None,
+            chunks,
+            &transfer,
+        )
+        .await
+        .unwrap();
+        assert_eq!(meta.id, "id");
+
+        let events = events.lock().unwrap();
+        let uploaded: Vec<_> = events
+            .iter()
+            .filter_map(|e| match e {
+                TransferEvent::Part {
+                    part_number,
+                    total_parts: None,
+                    status: PartStatus::Uploaded,
+                    ..
+                } => Some(*part_number),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(uploaded, [1, 2, 3]);
+        assert!(events.contains(&TransferEvent::BytesSent {
+            sent: 10,
+            total: None
+        }));
+    }
 }
@@ -1,5 +1,6 @@
 use futures::channel::{mpsc::SendError, oneshot::Canceled};
-use reqwest::StatusCode;
+use reqwest::{Response, StatusCode};
+use serde_json::Value;
 
 #[derive(Debug, thiserror::Error)]
 pub enum AuthError {
@@ -9,12 +10,83 @@ pub enum AuthError {
     Base64DecodeError(#[from] base64::DecodeError),
     #[error("JSON parse error: {0}")]
     JsonParseError(#[from] serde_json::Error),
+    #[error("OIDC token provider failed: {0}")]
+    TokenProvider(String),
 }
 
+/// Error response from the domain server, DDS or the auth API.
 #[derive(Debug, Clone)]
 pub struct AukiErrorResponse {
     pub status: StatusCode,
+    /// What the client was doing, followed by the raw response body.
     pub error: String,
+    /// Machine-readable error code from the JSON body.
+    pub code: Option<String>,
+    /// Human-readable message from the JSON body.
+    pub message: Option<String>,
+    /// Request id from the JSON body or the `x-request-id` header, useful when contacting support.
+    pub request_id: Option<String>,
+}
+
+impl AukiErrorResponse {
+    pub fn new(status: StatusCode, error: impl Into<String>) -> Self {
+        Self {
+            status,
+            error: error.into(),
+            code: None,
+            message: None,
+            request_id: None,
+        }
+    }
+
+    /// Reads a failed response. `context` describes the failed operation, e.g.
+    /// `"Failed to download data."`.
+    pub(crate) async fn from_response(response: Response, context: &str) -> Self {
+        let status = response.status();
+        let header_request_id = response
+            .headers()
+            .get("x-request-id")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let text = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+        let mut err = Self::new(status, format!("{} {}", context, text));
+        err.parse_body(&text);
+        if err.request_id.is_none() {
+            err.request_id = header_request_id;
+        }
+        err
+    }
+
+    /// Picks `code`, `message` and `request_id` out of a JSON error body, which may be flat
+    /// (`{"code": .., "message": ..}`) or nested under `"error"`.
+    fn parse_body(&mut self, body: &str) {
+        let Ok(Value::Object(mut obj)) = serde_json::from_str::<Value>(body) else {
+            return;
+        };
+        if let Some(Value::Object(inner)) = obj.get("error").cloned() {
+            obj.extend(inner);
+        }
+        let field = |keys: &[&str]| {
+            keys.iter().find_map(|k| match obj.get(*k) {
+                Some(Value::String(s)) if !s.is_empty() => Some(s.clone()),
+                Some(Value::Number(n)) => Some(n.to_string()),
+                _ => None,
+            })
+        };
+        self.code = field(&["code", "error_code", "errorCode"]);
+        self.message = field(&["message", "error", "detail", "msg"]);
+        self.request_id = field(&["request_id", "requestId", "trace_id"]);
+    }
+
+    fn mentions(&self, needles: &[&str]) -> bool {
+        [&self.code, &self.message].into_iter().flatten().any(|s| {
+            let s = s.to_ascii_lowercase();
+            needles.iter().any(|n| s.contains(n))
+        })
+    }
 }
 
 impl std::fmt::Display for AukiErrorResponse {
@@ -23,7 +95,14 @@ impl std::fmt::Display for AukiErrorResponse {
             f,
             "Auki response - status: {}, error: {}",
             self.status, self.error
-        )
+        )?;
+        if let Some(code) = &self.code {
+            write!(f, ", code: {}", code)?;
+        }
+        if let Some(request_id) = &self.request_id {
+            write!(f, ", request id: {}", request_id)?;
+        }
+        Ok(())
     }
 }
 
@@ -34,7 +113,21 @@ pub enum DomainError {
     #[error("Reqwest error: {0}")]
     ReqwestError(#[from] reqwest::Error),
     #[error("{0}")]
-    AukiErrorResponse(#[from] AukiErrorResponse),
+    AukiErrorResponse(AukiErrorResponse),
+    #[error("Not found: {0}")]
+    NotFound(AukiErrorResponse),
+    #[error("Forbidden: {0}")]
+    Forbidden(AukiErrorResponse),
+    #[error("Conflict: {0}")]
+    Conflict(AukiErrorResponse),
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(AukiErrorResponse),
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(AukiErrorResponse),
+    #[error("Token expired: {0}")]
+    TokenExpired(AukiErrorResponse),
+    #[error("Server error: {0}")]
+    ServerError(AukiErrorResponse),
     #[error("Invalid content-type header")]
     InvalidContentTypeHeader,
     #[error("Stream error: {0}")]
@@ -45,4 +138,141 @@ pub enum DomainError {
     AuthError(#[from] AuthError),
     #[error("Invalid request: {0}")]
     InvalidRequest(&'static str),
+    #[error("Cancelled")]
+    Cancelled,
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Classifies an error response by status and, for quota and expired tokens, by its code or
+/// message.
+impl From<AukiErrorResponse> for DomainError {
+    fn from(resp: AukiErrorResponse) -> Self {
+        if resp.status == StatusCode::PAYMENT_REQUIRED
+            || resp.mentions(&["quota", "credit", "insufficient_balance"])
+        {
+            return DomainError::QuotaExceeded(resp);
+        }
+        match resp.status {
+            StatusCode::UNAUTHORIZED if resp.mentions(&["expired"]) => {
+                DomainError::TokenExpired(resp)
+            }
+            StatusCode::FORBIDDEN => DomainError::Forbidden(resp),
+            StatusCode::NOT_FOUND => DomainError::NotFound(resp),
+            StatusCode::CONFLICT => DomainError::Conflict(resp),
+            StatusCode::PAYLOAD_TOO_LARGE => DomainError::PayloadTooLarge(resp),
+            s if s.is_server_error() => DomainError::ServerError(resp),
+            _ => DomainError::AukiErrorResponse(resp),
+        }
+    }
+}
+
+impl DomainError {
+    /// The error response behind any of the HTTP status variants.
+    pub fn response(&self) -> Option<&AukiErrorResponse> {
+        match self {
+            DomainError::AukiErrorResponse(r)
+            | DomainError::NotFound(r)
+            | DomainError::Forbidden(r)
+            | DomainError::Conflict(r)
+            | DomainError::PayloadTooLarge(r)
+            | DomainError::QuotaExceeded(r)
+            | DomainError::TokenExpired(r)
+            | DomainError::ServerError(r) => Some(r),
+            _ => None,
+        }
+    }
+
+    /// HTTP status of the error response, if the server answered.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            DomainError::ReqwestError(e) => e.status(),
+            _ => self.response().map(|r| r.status),
+        }
+    }
+
+    /// Variant name, used as the error name in the wasm binding.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DomainError::ReqwestError(_) => "ReqwestError",
+            DomainError::AukiErrorResponse(_) => "AukiErrorResponse",
+            DomainError::NotFound(_) => "NotFound",
+            DomainError::Forbidden(_) => "Forbidden",
+            DomainError::Conflict(_) => "Conflict",
+            DomainError::PayloadTooLarge(_) => "PayloadTooLarge",
+            DomainError::QuotaExceeded(_) => "QuotaExceeded",
+            DomainError::TokenExpired(_) => "TokenExpired",
+            DomainError::ServerError(_) => "ServerError",
+            DomainError::InvalidContentTypeHeader => "InvalidContentTypeHeader",
+            DomainError::StreamError(_) => "StreamError",
+            DomainError::StreamCancelled(_) => "StreamCancelled",
+            DomainError::AuthError(_) => "AuthError",
+            DomainError::InvalidRequest(_) => "InvalidRequest",
+            DomainError::Cancelled => "Cancelled",
+            DomainError::Io(_) => "Io",
+        }
+    }
+
+    /// Whether the failure is transient, so the same request may succeed if sent again:
+    /// connection failures, timeouts and 408/429/502/503/504 responses.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            DomainError::ReqwestError(e) => e.is_connect() || e.is_timeout(),
+            _ => self
+                .response()
+                .is_some_and(|r| crate::retry::is_retryable_status(r.status)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(status: u16, body: &str) -> DomainError {
+        let mut resp = AukiErrorResponse::new(StatusCode::from_u16(status).unwrap(), body);
+        resp.parse_body(body);
+        resp.into()
+    }
+
+    #[test]
+    fn parses_flat_and_nested_bodies() {
+        let err = error(
+            404,
+            r#"{"code":"data_not_found","message":"no such data","request_id":"req-1"}"#,
+        );
+        assert!(matches!(err, DomainError::NotFound(_)));
+        let resp = err.response().unwrap();
+        assert_eq!(resp.code.as_deref(), Some("data_not_found"));
+        assert_eq!(resp.message.as_deref(), Some("no such data"));
+        assert_eq!(resp.request_id.as_deref(), Some("req-1"));
+
+        let err = error(409, r#"{"error":{"code":"exists","message":"taken"}}"#);
+        assert!(matches!(err, DomainError::Conflict(_)));
+        assert_eq!(err.response().unwrap().code.as_deref(), Some("exists"));
+    }
+
+    #[test]
+    fn classifies_by_status_and_code() {
+        assert!(matches!(error(403, "nope"), DomainError::Forbidden(_)));
+        assert!(matches!(error(413, ""), DomainError::PayloadTooLarge(_)));
+        assert!(matches!(error(402, ""), DomainError::QuotaExceeded(_)));
+        assert!(matches!(
+            error(403, r#"{"code":"credits_exhausted"}"#),
+            DomainError::QuotaExceeded(_)
+        ));
+        assert!(matches!(
+            error(401, r#"{"message":"token expired"}"#),
+            DomainError::TokenExpired(_)
+        ));
+        assert!(matches!(
+            error(401, "bad"),
+            DomainError::AukiErrorResponse(_)
+        ));
+        let server = error(503, "");
+        assert!(matches!(server, DomainError::ServerError(_)));
+        assert!(server.is_retryable());
+        assert_eq!(server.status(), Some(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!error(500, "").is_retryable());
+    }
 }
@@ -0,0 +1,213 @@
+//! Queries that run against many domains at once.
+//!
+//! [`DomainClient::fan_out_metadata`] and [`DomainClient::fan_out_download`] authenticate and
+//! query up to `concurrency` domains at a time, wherever their domain servers are, and merge the
+//! results into one stream. Every item is tagged with the domain it came from, and a domain that
+//! fails shows up as a [`DomainFailure`] in the stream instead of ending it.
+
+use futures::{Stream, StreamExt};
+
+use crate::domain_client::{DomainClient, ListDomainsQuery};
+use crate::errors::DomainError;
+
+/// Domains queried at once when the caller has no better idea.
+pub const DEFAULT_FAN_OUT_CONCURRENCY: usize = 8;
+
+/// The domains to query: given by id, or every domain a [`ListDomainsQuery`] returns.
+#[derive(Debug, Clone)]
+pub enum DomainSelection {
+    Ids(Vec<String>),
+    Query(ListDomainsQuery),
+}
+
+impl From<Vec<String>> for DomainSelection {
+    fn from(ids: Vec<String>) -> Self {
+        DomainSelection::Ids(ids)
+    }
+}
+
+impl From<ListDomainsQuery> for DomainSelection {
+    fn from(query: ListDomainsQuery) -> Self {
+        DomainSelection::Query(query)
+    }
+}
+
+impl DomainSelection {
+    pub(crate) async fn resolve(self, client: &DomainClient) -> Result<Vec<String>, DomainError> {
+        match self {
+            DomainSelection::Ids(ids) => Ok(ids),
+            DomainSelection::Query(query) => Ok(client
+                .list_domains(&query)
+                .await?
+                .domains
+                .into_iter()
+                .map(|domain| domain.id)
+                .collect()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DomainItem<T> {
+    pub domain_id: String,
+    pub item: T,
+}
+
+/// A domain that could not be queried, or whose results broke off.
+#[derive(Debug)]
+pub struct DomainFailure {
+    pub domain_id: String,
+    pub error: DomainError,
+}
+
+pub type FanOutResult<T> = Result<DomainItem<T>, DomainFailure>;
+
+/// Everything a fan-out stream returned.
+#[derive(Debug)]
+pub struct FanOutReport<T> {
+    pub items: Vec<DomainItem<T>>,
+    pub failures: Vec<DomainFailure>,
+}
+
+impl<T> FanOutReport<T> {
+    /// Drains `results`, keeping items and failures apart.
+    pub async fn collect(results: impl Stream<Item = FanOutResult<T>>) -> Self {
+        let mut report = Self {
+            items: Vec::new(),
+            failures: Vec::new(),
+        };
+        futures::pin_mut!(results);
+        while let Some(result) = results.next().await {
+            match result {
+                Ok(item) => report.items.push(item),
+                Err(failure) => report.failures.push(failure),
+            }
+        }
+        report
+    }
+}
+
+/// Results of one domain.
+#[cfg(not(target_family = "wasm"))]
+pub(crate) type DomainStream<'a, T> = futures::stream::BoxStream<'a, Result<T, DomainError>>;
+/// Results of one domain.
+#[cfg(target_family = "wasm")]
+pub(crate) type DomainStream<'a, T> = futures::stream::LocalBoxStream<'a, Result<T, DomainError>>;
+
+/// Opens the stream of every domain lazily, polling at most `concurrency` of them at a time,
+/// and tags their results.
+#[allow(clippy::result_large_err)]
+pub(crate) fn fan_out<'a, T: 'a>(
+    domain_ids: Vec<String>,
+    concurrency: usize,
+    open: impl Fn(String) -> DomainStream<'a, T> + 'a,
+) -> impl Stream<Item = FanOutResult<T>> + 'a {
+    futures::stream::iter(domain_ids)
+        .map(move |domain_id| {
+            open(domain_id.clone()).map(move |result| match result {
+                Ok(item) => Ok(DomainItem {
+                    domain_id: domain_id.clone(),
+                    item,
+                }),
+                Err(error) => Err(DomainFailure {
+                    domain_id: domain_id.clone(),
+                    error,
+                }),
+            })
+        })
+        .flatten_unordered(concurrency.max(1))
+}
+
+#[cfg(not(target_family = "wasm"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::domain_data::DownloadQuery;
+    use crate::transport::test_support::{dds_token, domain_auth, json_response, jwt};
+    use crate::transport::{ExecuteFuture, HttpExecutor, Transport};
+
+    /// DDS listing domains `a`, `b` and `gone`, each on its own domain server. `gone` cannot be
+    /// authenticated. Counts the domain servers queried at the same time.
+    #[derive(Default)]
+    struct Servers {
+        active: std::sync::Arc<AtomicUsize>,
+        peak: std::sync::Arc<AtomicUsize>,
+    }
+
+    impl HttpExecutor for Servers {
+        fn execute(&self, request: reqwest::Request) -> ExecuteFuture {
+            let host = request.url().host_str().unwrap_or_default().to_string();
+            let path = request.url().path().to_string();
+            let (status, body) = match (host.as_str(), path.as_str()) {
+                (_, "/service/domains-access-token") => (200, dds_token()),
+                ("dds", "/api/v1/domains") => (
+                    200,
+                    serde_json::json!({ "domains": [domain_auth("a", "a"), domain_auth("b", "b"), domain_auth("gone", "gone")] }),
+                ),
+                ("dds", "/api/v1/domains/gone/auth") => {
+                    (404, serde_json::json!({ "message": "no such domain" }))
+                }
+                ("dds", path) => {
+                    let id = path.split('/').nth(4).unwrap();
+                    (200, domain_auth(id, id))
+                }
+                (id, _) => {
+                    let meta = serde_json::json!({
+                        "id": format!("{id}-1"), "domain_id": id, "name": "manifest",
+                        "data_type": "refined_manifest", "size": 1,
+                        "created_at": "2025-01-01T00:00:00Z", "updated_at": "2025-01-01T00:00:00Z",
+                    });
+                    (200, serde_json::json!({ "data": [meta] }))
+                }
+            };
+            let (active, peak) = (self.active.clone(), self.peak.clone());
+            let on_domain_server = host != "dds" && host != "api";
+            Box::pin(async move {
+                if on_domain_server {
+                    peak.fetch_max(active.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                    active.fetch_sub(1, Ordering::SeqCst);
+                }
+                Ok(json_response(status, &body))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn collects_items_and_failures_per_domain() {
+        let servers = Servers::default();
+        let peak = servers.peak.clone();
+        let client = DomainClient::new_with_transport(
+            "http://api",
+            "http://dds",
+            "client",
+            Transport::with_executor(servers),
+        )
+        .with_oidc_access_token(&jwt());
+        let query = DownloadQuery {
+            ids: vec![],
+            name: None,
+            data_type: Some("refined_manifest".into()),
+        };
+        let domains = ListDomainsQuery {
+            portal_id: None,
+            portal_short_id: None,
+            org: "own".into(),
+            domain_server_id: None,
+        };
+
+        let results = client.fan_out_metadata(domains, &query, 1).await.unwrap();
+        let report = FanOutReport::collect(results).await;
+
+        let mut ids: Vec<_> = report.items.iter().map(|i| i.item.id.as_str()).collect();
+        ids.sort();
+        assert_eq!(ids, ["a-1", "b-1"]);
+        assert!(report.items.iter().all(|i| i.item.domain_id == i.domain_id));
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].domain_id, "gone");
+        assert!(matches!(report.failures[0].error, DomainError::NotFound(_)));
+        assert_eq!(peak.load(Ordering::SeqCst), 1);
+    }
+}
@@ -1,10 +1,27 @@
 pub mod auth;
+pub mod bulk_delete;
 pub mod config;
+#[cfg(not(target_family = "wasm"))]
+pub mod dedup;
 pub mod discovery;
 pub mod domain_client;
 pub mod domain_data;
 pub mod errors;
+pub mod fanout;
+pub mod listing;
+pub mod oidc;
+pub mod portals;
+pub mod progress;
+pub mod propagation;
 pub mod reconstruction;
+pub mod retry;
+#[cfg(not(target_family = "wasm"))]
+pub mod sync;
+pub mod token_refresh;
+pub mod token_store;
+pub mod transport;
+pub mod upload_queue;
+pub mod watch;
 
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -13,17 +30,29 @@ pub mod wasm;
 
 #[cfg(feature = "uniffi")]
 use crate::{
-    discovery::{DomainServer, DomainWithServer, ListDomainsResponse},
+    bulk_delete::{DeleteFailure, DeleteReport},
+    dedup::UploadReport,
+    discovery::{DomainServer, DomainWithServer, ListDomainsResponse, Portal, UpdateDomainRequest},
     domain_client::ListDomainsQuery,
     domain_data::{DomainAction, DomainData, DomainDataMetadata, DownloadQuery, UploadDomainData},
     errors::DomainError,
+    listing::{ListQuery, MetadataPage, MetadataSort},
+    portals::{PlacePortalRequest, PortalPlacement, Pose, Quaternion, Vector3},
+    progress::{CancellationToken, PartStatus, TransferEvent},
+    reconstruction::{Job, JobRequest, JobStatus},
+    upload_queue::{QueuedUpload, QueuedUploadState},
+    watch::{DomainDataEvent, DomainDataEventKind},
 };
 
 #[cfg(feature = "uniffi")]
 pub mod uniffi;
 
 #[cfg(feature = "uniffi")]
-use crate::uniffi::{DomainClient, new_with_app_credential, new_with_user_credential};
+use crate::uniffi::{
+    DomainClient, DomainDataDownload, DomainDataUpload, DomainDataWatcher, JobProgressListener,
+    OidcTokenProvider, TransferListener, UploadQueue, WatchHandle, new_with_app_credential,
+    new_with_user_credential, restore_session,
+};
 
 #[cfg(feature = "uniffi")]
 ::uniffi::include_scaffolding!("domain-client");
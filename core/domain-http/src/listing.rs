@@ -0,0 +1,328 @@
+use chrono::DateTime;
+use futures::{Stream, TryStreamExt, stream};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::future::Future;
+
+use crate::domain_data::DomainDataMetadata;
+use crate::errors::{AukiErrorResponse, DomainError};
+use crate::transport::{SendVia, Transport};
+
+/// Page size used when [`ListQuery::limit`] is not set.
+pub const DEFAULT_PAGE_LIMIT: u32 = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataSort {
+    CreatedAtAsc,
+    CreatedAtDesc,
+    UpdatedAtAsc,
+    UpdatedAtDesc,
+    NameAsc,
+    NameDesc,
+    SizeAsc,
+    SizeDesc,
+}
+
+impl MetadataSort {
+    /// Value of the `sort` query parameter; a leading `-` means descending.
+    fn as_param(self) -> &'static str {
+        match self {
+            MetadataSort::CreatedAtAsc => "created_at",
+            MetadataSort::CreatedAtDesc => "-created_at",
+            MetadataSort::UpdatedAtAsc => "updated_at",
+            MetadataSort::UpdatedAtDesc => "-updated_at",
+            MetadataSort::NameAsc => "name",
+            MetadataSort::NameDesc => "-name",
+            MetadataSort::SizeAsc => "size",
+            MetadataSort::SizeDesc => "-size",
+        }
+    }
+}
+
+/// Filters and paging for listing domain data metadata. Timestamps are RFC 3339 strings, bounds
+/// are exclusive and all filters are combined with AND.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ListQuery {
+    /// Maximum number of items per page, [`DEFAULT_PAGE_LIMIT`] when unset.
+    pub limit: Option<u32>,
+    /// Cursor returned as `next_cursor` by the previous page.
+    pub cursor: Option<String>,
+    pub sort: Option<MetadataSort>,
+    pub created_after: Option<String>,
+    pub created_before: Option<String>,
+    pub updated_after: Option<String>,
+    pub updated_before: Option<String>,
+    pub name_prefix: Option<String>,
+    /// Only items whose data type is one of these; all types when empty.
+    pub data_types: Vec<String>,
+}
+
+impl ListQuery {
+    pub(crate) fn params(&self) -> Vec<(&'static str, String)> {
+        let mut params = vec![(
+            "limit",
+            self.limit.unwrap_or(DEFAULT_PAGE_LIMIT).to_string(),
+        )];
+        let optional = [
+            ("cursor", &self.cursor),
+            ("created_after", &self.created_after),
+            ("created_before", &self.created_before),
+            ("updated_after", &self.updated_after),
+            ("updated_before", &self.updated_before),
+            ("name_prefix", &self.name_prefix),
+        ];
+        for (key, value) in optional {
+            if let Some(value) = value {
+                params.push((key, value.clone()));
+            }
+        }
+        if let Some(sort) = self.sort {
+            params.push(("sort", sort.as_param().to_string()));
+        }
+        if !self.data_types.is_empty() {
+            params.push(("data_types", self.data_types.join(",")));
+        }
+        params
+    }
+
+    /// Applies the filters locally, so servers that ignore some parameters still return
+    /// matching items only.
+    pub fn matches(&self, item: &DomainDataMetadata) -> bool {
+        let after = |bound: &Option<String>, ts: &str| {
+            bound
+                .as_deref()
+                .is_none_or(|b| compare_timestamps(ts, b) == Ordering::Greater)
+        };
+        let before = |bound: &Option<String>, ts: &str| {
+            bound
+                .as_deref()
+                .is_none_or(|b| compare_timestamps(ts, b) == Ordering::Less)
+        };
+        after(&self.created_after, &item.created_at)
+            && before(&self.created_before, &item.created_at)
+            && after(&self.updated_after, &item.updated_at)
+            && before(&self.updated_before, &item.updated_at)
+            && self
+                .name_prefix
+                .as_deref()
+                .is_none_or(|p| item.name.starts_with(p))
+            && (self.data_types.is_empty() || self.data_types.contains(&item.data_type))
+    }
+}
+
+/// Orders RFC 3339 timestamps by the instant they denote, whatever their offset or precision.
+/// Timestamps that do not parse are compared as strings.
+fn compare_timestamps(a: &str, b: &str) -> Ordering {
+    match (
+        DateTime::parse_from_rfc3339(a),
+        DateTime::parse_from_rfc3339(b),
+    ) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+/// One page of metadata. `next_cursor` is `None` on the last page.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MetadataPage {
+    pub data: Vec<DomainDataMetadata>,
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+/// Fetches one page of metadata. Servers without pagination return everything in one page.
+pub async fn list_metadata_page_v1(
+    transport: &Transport,
+    url: &str,
+    client_id: &str,
+    access_token: &str,
+    domain_id: &str,
+    query: &ListQuery,
+) -> Result<MetadataPage, DomainError> {
+    let response = transport
+        .get(format!("{}/api/v1/domains/{}/data", url, domain_id))
+        .bearer_auth(access_token)
+        .header("Accept", "application/json")
+        .header("posemesh-client-id", client_id)
+        .query(&query.params())
+        .send_via(transport)
+        .await?;
+
+    if response.status().is_success() {
+        let mut page = response.json::<MetadataPage>().await?;
+        page.data.retain(|item| query.matches(item));
+        // A cursor that does not move would loop forever.
+        if page.next_cursor.is_some() && page.next_cursor == query.cursor {
+            page.next_cursor = None;
+        }
+        Ok(page)
+    } else {
+        Err(
+            AukiErrorResponse::from_response(response, "Failed to list metadata.")
+                .await
+                .into(),
+        )
+    }
+}
+
+/// Streams every item matching `query`, fetching pages with `fetch_page` as the stream is polled.
+pub fn paginate<F, Fut>(
+    query: ListQuery,
+    fetch_page: F,
+) -> impl Stream<Item = Result<DomainDataMetadata, DomainError>>
+where
+    F: FnMut(ListQuery) -> Fut,
+    Fut: Future<Output = Result<MetadataPage, DomainError>>,
+{
+    stream::try_unfold(
+        (Some(query), fetch_page),
+        |(query, mut fetch_page)| async move {
+            let Some(query) = query else {
+                return Ok::<_, DomainError>(None);
+            };
+            let page = fetch_page(query.clone()).await?;
+            let next = page.next_cursor.map(|cursor| ListQuery {
+                cursor: Some(cursor),
+                ..query
+            });
+            Ok(Some((
+                stream::iter(page.data.into_iter().map(Ok)),
+                (next, fetch_page),
+            )))
+        },
+    )
+    .try_flatten()
+}
+
+/// Streams every item matching `query` from one domain server with a fixed access token.
+pub fn list_metadata_v1<'a>(
+    transport: &'a Transport,
+    url: &'a str,
+    client_id: &'a str,
+    access_token: &'a str,
+    domain_id: &'a str,
+    query: ListQuery,
+) -> impl Stream<Item = Result<DomainDataMetadata, DomainError>> + 'a {
+    paginate(query, move |page_query| async move {
+        list_metadata_page_v1(
+            transport,
+            url,
+            client_id,
+            access_token,
+            domain_id,
+            &page_query,
+        )
+        .await
+    })
+}
+
+#[cfg(not(target_family = "wasm"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::test_support::json_response;
+    use crate::transport::{ExecuteFuture, HttpExecutor};
+    use futures::StreamExt;
+    use reqwest::Request;
+    use std::sync::{Arc, Mutex};
+
+    fn item(id: &str, data_type: &str, created_at: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": id, "domain_id": "dom", "name": format!("scan-{id}"), "data_type": data_type,
+            "size": 1, "created_at": created_at, "updated_at": created_at,
+        })
+    }
+
+    /// Serves two pages keyed by cursor and records the query strings it saw.
+    struct Pages {
+        queries: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl HttpExecutor for Pages {
+        fn execute(&self, request: Request) -> ExecuteFuture {
+            let query = request.url().query().unwrap_or_default().to_string();
+            self.queries.lock().unwrap().push(query.clone());
+            let body = if query.contains("cursor=c2") {
+                serde_json::json!({ "data": [item("3", "mesh", "2025-01-03T00:00:00Z")] })
+            } else {
+                serde_json::json!({
+                    "data": [
+                        item("1", "mesh", "2025-01-01T00:00:00Z"),
+                        // Old servers ignore filters; this one is dropped locally.
+                        item("2", "image", "2025-01-02T00:00:00Z"),
+                    ],
+                    "next_cursor": "c2",
+                })
+            };
+            let res = json_response(200, &body);
+            Box::pin(async move { Ok(res) })
+        }
+    }
+
+    #[tokio::test]
+    async fn streams_all_pages_with_filters() {
+        let queries = Arc::new(Mutex::new(Vec::new()));
+        let transport = Transport::with_executor(Pages {
+            queries: queries.clone(),
+        });
+        let query = ListQuery {
+            limit: Some(2),
+            sort: Some(MetadataSort::CreatedAtDesc),
+            data_types: vec!["mesh".into()],
+            name_prefix: Some("scan-".into()),
+            ..Default::default()
+        };
+
+        let items: Vec<_> = list_metadata_v1(&transport, "http://domain", "c", "t", "dom", query)
+            .map(|item| item.unwrap().id)
+            .collect()
+            .await;
+        assert_eq!(items, ["1", "3"]);
+
+        let queries = queries.lock().unwrap();
+        assert_eq!(queries.len(), 2);
+        assert!(queries[0].contains("limit=2"));
+        assert!(queries[0].contains("sort=-created_at"));
+        assert!(queries[0].contains("data_types=mesh"));
+        assert!(queries[0].contains("name_prefix=scan-"));
+        assert!(queries[1].contains("cursor=c2"));
+    }
+
+    #[test]
+    fn matches_time_bounds() {
+        let meta: DomainDataMetadata =
+            serde_json::from_value(item("1", "mesh", "2025-01-02T00:00:00Z")).unwrap();
+        let query = ListQuery {
+            created_after: Some("2025-01-01T00:00:00Z".into()),
+            updated_before: Some("2025-01-03T00:00:00Z".into()),
+            ..Default::default()
+        };
+        assert!(query.matches(&meta));
+        let query = ListQuery {
+            created_after: Some("2025-01-02T00:00:00Z".into()),
+            ..Default::default()
+        };
+        assert!(!query.matches(&meta));
+    }
+
+    #[test]
+    fn matches_fractional_seconds_and_offsets() {
+        let meta: DomainDataMetadata =
+            serde_json::from_value(item("1", "mesh", "2025-01-02T00:00:00.5Z")).unwrap();
+        let query = ListQuery {
+            created_after: Some("2025-01-02T00:00:00Z".into()),
+            // 2025-01-01T23:00:00Z and 2025-01-02T00:30:00Z.
+            updated_after: Some("2025-01-02T01:00:00+02:00".into()),
+            updated_before: Some("2025-01-01T22:30:00-02:00".into()),
+            ..Default::default()
+        };
+        assert!(query.matches(&meta));
+        let query = ListQuery {
+            created_before: Some("2025-01-02T00:00:00.25Z".into()),
+            ..Default::default()
+        };
+        assert!(!query.matches(&meta));
+    }
+}
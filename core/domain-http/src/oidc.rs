@@ -0,0 +1,176 @@
+//! OIDC access tokens supplied by the application on demand, for clients that outlive a token.
+
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+
+use futures::lock::Mutex;
+use posemesh_utils::now_unix_secs;
+
+use crate::auth::{REFRESH_CACHE_TIME, parse_jwt};
+use crate::errors::DomainError;
+
+#[cfg(not(target_family = "wasm"))]
+pub type OidcTokenFuture = futures::future::BoxFuture<'static, Result<String, DomainError>>;
+#[cfg(target_family = "wasm")]
+pub type OidcTokenFuture = futures::future::LocalBoxFuture<'static, Result<String, DomainError>>;
+
+/// Returns a fresh OIDC access token, e.g. by running the app's refresh flow.
+#[cfg(not(target_family = "wasm"))]
+pub type OidcTokenProvider = Arc<dyn Fn() -> OidcTokenFuture + Send + Sync>;
+/// Returns a fresh OIDC access token, e.g. by running the app's refresh flow.
+#[cfg(target_family = "wasm")]
+pub type OidcTokenProvider = Arc<dyn Fn() -> OidcTokenFuture>;
+
+/// Wraps an async closure into an [`OidcTokenProvider`].
+#[cfg(not(target_family = "wasm"))]
+pub fn oidc_token_provider<F, Fut>(provider: F) -> OidcTokenProvider
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<String, DomainError>> + Send + 'static,
+{
+    Arc::new(move || Box::pin(provider()) as OidcTokenFuture)
+}
+
+/// Wraps an async closure into an [`OidcTokenProvider`].
+#[cfg(target_family = "wasm")]
+pub fn oidc_token_provider<F, Fut>(provider: F) -> OidcTokenProvider
+where
+    F: Fn() -> Fut + 'static,
+    Fut: Future<Output = Result<String, DomainError>> + 'static,
+{
+    #[allow(clippy::arc_with_non_send_sync)]
+    Arc::new(move || Box::pin(provider()) as OidcTokenFuture)
+}
+
+/// A provider and the last token it returned, shared by clones of a client.
+#[derive(Clone)]
+pub(crate) struct OidcTokenSource {
+    provider: OidcTokenProvider,
+    token: Arc<Mutex<Option<String>>>,
+}
+
+impl fmt::Debug for OidcTokenSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OidcTokenSource").finish_non_exhaustive()
+    }
+}
+
+impl OidcTokenSource {
+    pub(crate) fn new(provider: OidcTokenProvider) -> Self {
+        Self {
+            provider,
+            token: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    // Returns the cached token unless it expires within REFRESH_CACHE_TIME. Tokens that are not JWTs have no known expiry and are kept until invalidated.
+    // The lock is held while the provider runs, so concurrent calls share one refresh.
+    pub(crate) async fn token(&self) -> Result<String, DomainError> {
+        let mut token = self.token.lock().await;
+        if let Some(cached) = token.as_ref() {
+            let fresh = parse_jwt(cached)
+                .map(|claim| claim.exp > now_unix_secs() + REFRESH_CACHE_TIME)
+                .unwrap_or(true);
+            if fresh {
+                return Ok(cached.clone());
+            }
+        }
+        let fresh = (self.provider)().await?;
+        *token = Some(fresh.clone());
+        Ok(fresh)
+    }
+
+    /// Forgets the cached token, so the next call asks the provider again.
+    pub(crate) async fn invalidate(&self) {
+        *self.token.lock().await = None;
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::transport::test_support::{dds_token, domain_auth, json_response, jwt_expiring_at};
+
+    #[tokio::test]
+    async fn caches_until_invalidated() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let source = OidcTokenSource::new(oidc_token_provider(move || {
+            let n = counter.fetch_add(1, Ordering::SeqCst);
+            async move { Ok(format!("opaque-{n}")) }
+        }));
+
+        assert_eq!(source.token().await.unwrap(), "opaque-0");
+        assert_eq!(source.token().await.unwrap(), "opaque-0");
+        source.invalidate().await;
+        assert_eq!(source.token().await.unwrap(), "opaque-1");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    /// Auth API, DDS and a domain server that rejects the first download with 401.
+    struct Servers {
+        downloads: AtomicUsize,
+    }
+
+    impl crate::transport::HttpExecutor for Servers {
+        fn execute(&self, request: reqwest::Request) -> crate::transport::ExecuteFuture {
+            let (status, body) = match request.url().path() {
+                "/service/domains-access-token" => (200, dds_token()),
+                "/api/v1/domains/dom/auth" => (200, domain_auth("dom", "domain")),
+                _ if self.downloads.fetch_add(1, Ordering::SeqCst) == 0 => {
+                    (401, serde_json::json!({ "message": "invalid token" }))
+                }
+                _ => (200, serde_json::json!({})),
+            };
+            let res = json_response(status, &body);
+            Box::pin(async move { Ok(res) })
+        }
+    }
+
+    #[tokio::test]
+    async fn refreshes_after_unauthorized() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let transport = crate::transport::Transport::with_executor(Servers {
+            downloads: AtomicUsize::new(0),
+        });
+        let client = crate::domain_client::DomainClient::new_with_transport(
+            "http://api",
+            "http://dds",
+            "client",
+            transport,
+        )
+        .with_oidc_token_provider(oidc_token_provider(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async move { Ok(jwt_expiring_at(now_unix_secs() + 3600)) }
+        }));
+
+        client
+            .download_domain_data_by_id("dom", "id")
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        client
+            .download_domain_data_by_id("dom", "id")
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn refreshes_tokens_about_to_expire() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let source = OidcTokenSource::new(oidc_token_provider(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async move { Ok(jwt_expiring_at(now_unix_secs() + 10)) }
+        }));
+        source.token().await.unwrap();
+        source.token().await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,277 @@
+//! Placements of portals (lighthouses) within a domain.
+//!
+//! [`Pose`], [`Vector3`] and [`Quaternion`] serialize like the SDK interface types of the same
+//! name: `{ "position": { "x", "y", "z" }, "rotation": { "x", "y", "z", "w" } }`, in metres and
+//! in the domain's coordinate system.
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{AukiErrorResponse, DomainError};
+use crate::transport::{SendVia, Transport};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Vector3 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Quaternion {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+/// The identity rotation.
+impl Default for Quaternion {
+    fn default() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            w: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Pose {
+    pub position: Vector3,
+    pub rotation: Quaternion,
+}
+
+/// A portal placed in a domain.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PortalPlacement {
+    pub id: String,
+    /// The id printed on the physical portal.
+    pub short_id: String,
+    pub pose: Pose,
+    /// Edge length of the printed portal in metres.
+    pub size: f32,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+/// Where to place a portal.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PlacePortalRequest {
+    pub pose: Pose,
+    /// Edge length of the printed portal in metres.
+    pub size: f32,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListPortalsResponse {
+    #[serde(alias = "data")]
+    lighthouses: Vec<PortalPlacement>,
+}
+
+fn portals_url(domain_server_url: &str, domain_id: &str) -> String {
+    format!(
+        "{}/api/v1/domains/{}/lighthouses",
+        domain_server_url, domain_id
+    )
+}
+
+pub async fn list_portals_v1(
+    transport: &Transport,
+    domain_server_url: &str,
+    client_id: &str,
+    access_token: &str,
+    domain_id: &str,
+) -> Result<Vec<PortalPlacement>, DomainError> {
+    let response = transport
+        .get(portals_url(domain_server_url, domain_id))
+        .bearer_auth(access_token)
+        .header("posemesh-client-id", client_id)
+        .send_via(transport)
+        .await?;
+
+    if response.status().is_success() {
+        Ok(response.json::<ListPortalsResponse>().await?.lighthouses)
+    } else {
+        Err(
+            AukiErrorResponse::from_response(response, "Failed to list portals.")
+                .await
+                .into(),
+        )
+    }
+}
+
+pub async fn get_portal_v1(
+    transport: &Transport,
+    domain_server_url: &str,
+    client_id: &str,
+    access_token: &str,
+    domain_id: &str,
+    short_id: &str,
+) -> Result<PortalPlacement, DomainError> {
+    let response = transport
+        .get(format!(
+            "{}/{}",
+            portals_url(domain_server_url, domain_id),
+            short_id
+        ))
+        .bearer_auth(access_token)
+        .header("posemesh-client-id", client_id)
+        .send_via(transport)
+        .await?;
+
+    if response.status().is_success() {
+        Ok(response.json::<PortalPlacement>().await?)
+    } else {
+        Err(
+            AukiErrorResponse::from_response(response, "Failed to get portal.")
+                .await
+                .into(),
+        )
+    }
+}
+
+/// Places the portal `short_id` in the domain, or moves it if it is placed already.
+pub async fn place_portal_v1(
+    transport: &Transport,
+    domain_server_url: &str,
+    client_id: &str,
+    access_token: &str,
+    domain_id: &str,
+    short_id: &str,
+    request: &PlacePortalRequest,
+) -> Result<PortalPlacement, DomainError> {
+    let response = transport
+        .put(format!(
+            "{}/{}",
+            portals_url(domain_server_url, domain_id),
+            short_id
+        ))
+        .bearer_auth(access_token)
+        .header("posemesh-client-id", client_id)
+        .json(request)
+        .send_via(transport)
+        .await?;
+
+    if response.status().is_success() {
+        Ok(response.json::<PortalPlacement>().await?)
+    } else {
+        Err(
+            AukiErrorResponse::from_response(response, "Failed to place portal.")
+                .await
+                .into(),
+        )
+    }
+}
+
+/// Removes the placement of the portal `short_id` from the domain.
+pub async fn remove_portal_v1(
+    transport: &Transport,
+    domain_server_url: &str,
+    client_id: &str,
+    access_token: &str,
+    domain_id: &str,
+    short_id: &str,
+) -> Result<(), DomainError> {
+    let response = transport
+        .delete(format!(
+            "{}/{}",
+            portals_url(domain_server_url, domain_id),
+            short_id
+        ))
+        .bearer_auth(access_token)
+        .header("posemesh-client-id", client_id)
+        .send_via(transport)
+        .await?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(
+            AukiErrorResponse::from_response(response, "Failed to remove portal.")
+                .await
+                .into(),
+        )
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    use crate::transport::test_support::json_response;
+    use crate::transport::{ExecuteFuture, HttpExecutor};
+
+    /// Domain server that echoes placed portals back and lists the last one.
+    #[derive(Default)]
+    struct Server(Arc<Mutex<Option<serde_json::Value>>>);
+
+    impl HttpExecutor for Server {
+        fn execute(&self, request: reqwest::Request) -> ExecuteFuture {
+            let mut placed = self.0.lock().unwrap();
+            let body = match (request.method().as_str(), request.url().path()) {
+                ("PUT", "/api/v1/domains/dom/lighthouses/ABC") => {
+                    let body = request.body().and_then(|b| b.as_bytes()).unwrap();
+                    let mut portal: serde_json::Value = serde_json::from_slice(body).unwrap();
+                    portal["id"] = "p1".into();
+                    portal["short_id"] = "ABC".into();
+                    *placed = Some(portal.clone());
+                    portal
+                }
+                ("GET", "/api/v1/domains/dom/lighthouses") => {
+                    serde_json::json!({ "lighthouses": placed.iter().collect::<Vec<_>>() })
+                }
+                other => panic!("unexpected request {other:?}"),
+            };
+            let res = json_response(200, &body);
+            Box::pin(async move { Ok(res) })
+        }
+    }
+
+    #[tokio::test]
+    async fn places_and_lists_portals() {
+        let transport = Transport::with_executor(Server::default());
+        let request = PlacePortalRequest {
+            pose: Pose {
+                position: Vector3 {
+                    x: 1.0,
+                    y: 0.5,
+                    z: -2.0,
+                },
+                rotation: Quaternion::default(),
+            },
+            size: 0.1,
+        };
+        let placed = place_portal_v1(
+            &transport,
+            "http://domain",
+            "client",
+            "token",
+            "dom",
+            "ABC",
+            &request,
+        )
+        .await
+        .unwrap();
+        assert_eq!(placed.pose, request.pose);
+
+        let portals = list_portals_v1(&transport, "http://domain", "client", "token", "dom")
+            .await
+            .unwrap();
+        assert_eq!(portals, [placed]);
+    }
+
+    #[test]
+    fn pose_matches_sdk_interface() {
+        let pose: Pose = serde_json::from_str(
+            r#"{"position":{"x":1,"y":2,"z":3},"rotation":{"x":0,"y":0,"z":0,"w":1}}"#,
+        )
+        .unwrap();
+        assert_eq!(pose.position.z, 3.0);
+        assert_eq!(pose.rotation, Quaternion::default());
+    }
+}
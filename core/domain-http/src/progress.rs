@@ -0,0 +1,264 @@
+//! Progress events and cancellation for uploads and downloads.
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use futures::future::{Either, select};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::DomainError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PartStatus {
+    Uploading,
+    Uploaded,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TransferEvent {
+    /// Upload bytes accepted so far, out of `total` when known in advance.
+    BytesSent { sent: u64, total: Option<u64> },
+    /// Download bytes received so far.
+    BytesReceived { received: u64 },
+    /// Items fully uploaded or downloaded so far, out of `total` when known in advance.
+    ItemsDone { done: u64, total: Option<u64> },
+    /// A part of a multipart upload changed state. `name` is the data name, or its id for
+    /// updates. `total_parts` is unknown for streams of unknown size.
+    Part {
+        name: String,
+        part_number: u32,
+        total_parts: Option<u32>,
+        status: PartStatus,
+    },
+}
+
+/// Cancels the transfers it was passed to. Clones share the same state.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<CancelState>);
+
+#[derive(Debug, Default)]
+struct CancelState {
+    cancelled: AtomicBool,
+    wakers: Mutex<Vec<Waker>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::SeqCst);
+        for waker in self.0.wakers.lock().unwrap().drain(..) {
+            waker.wake();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once [`CancellationToken::cancel`] has been called.
+    pub fn cancelled(&self) -> Cancelled {
+        Cancelled(self.0.clone())
+    }
+}
+
+/// Future returned by [`CancellationToken::cancelled`].
+#[derive(Debug)]
+pub struct Cancelled(Arc<CancelState>);
+
+impl Future for Cancelled {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0.cancelled.load(Ordering::SeqCst) {
+            return Poll::Ready(());
+        }
+        let mut wakers = self.0.wakers.lock().unwrap();
+        // Checked again under the lock, so a concurrent cancel cannot be missed.
+        if self.0.cancelled.load(Ordering::SeqCst) {
+            return Poll::Ready(());
+        }
+        if !wakers.iter().any(|w| w.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+pub type ProgressCallback = Arc<dyn Fn(TransferEvent) + Send + Sync>;
+#[cfg(target_family = "wasm")]
+pub type ProgressCallback = Arc<dyn Fn(TransferEvent)>;
+
+/// Progress callback and cancellation token of one transfer. Byte and item counts accumulate
+/// over everything sent with the same control, so use a new one per operation.
+#[derive(Clone, Default)]
+pub struct TransferControl {
+    on_event: Option<ProgressCallback>,
+    cancel: CancellationToken,
+    sent: Arc<AtomicU64>,
+    received: Arc<AtomicU64>,
+    items: Arc<AtomicU64>,
+    // Totals plus one, zero while unknown.
+    total_bytes: Arc<AtomicU64>,
+    total_items: Arc<AtomicU64>,
+}
+
+impl fmt::Debug for TransferControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransferControl")
+            .field("cancel", &self.cancel)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TransferControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    pub fn on_event(mut self, on_event: impl Fn(TransferEvent) + Send + Sync + 'static) -> Self {
+        self.on_event = Some(Arc::new(on_event));
+        self
+    }
+
+    #[cfg(target_family = "wasm")]
+    pub fn on_event(mut self, on_event: impl Fn(TransferEvent) + 'static) -> Self {
+        #[allow(clippy::arc_with_non_send_sync)]
+        let on_event: ProgressCallback = Arc::new(on_event);
+        self.on_event = Some(on_event);
+        self
+    }
+
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.cancel
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    pub(crate) fn emit(&self, event: TransferEvent) {
+        if let Some(on_event) = &self.on_event {
+            on_event(event);
+        }
+    }
+
+    /// Records how much the operation is going to send, when known before it starts.
+    pub(crate) fn set_totals(&self, bytes: Option<u64>, items: Option<u64>) {
+        let encode = |total: Option<u64>| total.map_or(0, |t| t.saturating_add(1));
+        self.total_bytes.store(encode(bytes), Ordering::SeqCst);
+        self.total_items.store(encode(items), Ordering::SeqCst);
+    }
+
+    fn total(total: &AtomicU64) -> Option<u64> {
+        total.load(Ordering::SeqCst).checked_sub(1)
+    }
+
+    pub(crate) fn bytes_sent(&self, bytes: u64) {
+        let sent = self.sent.fetch_add(bytes, Ordering::SeqCst) + bytes;
+        self.emit(TransferEvent::BytesSent {
+            sent,
+            total: Self::total(&self.total_bytes),
+        });
+    }
+
+    pub(crate) fn bytes_received(&self, bytes: u64) {
+        let received = self.received.fetch_add(bytes, Ordering::SeqCst) + bytes;
+        self.emit(TransferEvent::BytesReceived { received });
+    }
+
+    pub(crate) fn items_done(&self, items: u64) {
+        let done = self.items.fetch_add(items, Ordering::SeqCst) + items;
+        self.emit(TransferEvent::ItemsDone {
+            done,
+            total: Self::total(&self.total_items),
+        });
+    }
+
+    /// Fails with [`DomainError::Cancelled`] once cancelled.
+    pub(crate) fn check(&self) -> Result<(), DomainError> {
+        if self.is_cancelled() {
+            Err(DomainError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Runs `future` until it completes or the transfer is cancelled, whichever comes first.
+    pub(crate) async fn run<T>(
+        &self,
+        future: impl Future<Output = Result<T, DomainError>>,
+    ) -> Result<T, DomainError> {
+        self.check()?;
+        futures::pin_mut!(future);
+        match select(future, self.cancel.cancelled()).await {
+            Either::Left((result, _)) => result,
+            Either::Right(_) => Err(DomainError::Cancelled),
+        }
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn run_stops_when_cancelled() {
+        let control = TransferControl::new();
+        let cancel = control.cancellation().clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            cancel.cancel();
+        });
+        let result = control
+            .run(futures::future::pending::<Result<(), DomainError>>())
+            .await;
+        assert!(matches!(result, Err(DomainError::Cancelled)));
+        assert!(matches!(control.check(), Err(DomainError::Cancelled)));
+    }
+
+    #[test]
+    fn counts_accumulate() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let seen = events.clone();
+        let control = TransferControl::new().on_event(move |e| seen.lock().unwrap().push(e));
+        control.set_totals(Some(10), None);
+        control.bytes_sent(3);
+        control.bytes_sent(7);
+        control.items_done(1);
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                TransferEvent::BytesSent {
+                    sent: 3,
+                    total: Some(10)
+                },
+                TransferEvent::BytesSent {
+                    sent: 10,
+                    total: Some(10)
+                },
+                TransferEvent::ItemsDone {
+                    done: 1,
+                    total: None
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,35 @@
+use reqwest::RequestBuilder;
+use std::sync::OnceLock;
+
+/// Produces extra headers (e.g. W3C `traceparent`/`tracestate`) for the current execution context.
+pub type HeaderInjector = dyn Fn() -> Vec<(String, String)> + Send + Sync;
+
+static HEADER_INJECTOR: OnceLock<Box<HeaderInjector>> = OnceLock::new();
+
+/// Installs a process-wide hook that is invoked for every domain data request to attach
+/// trace-context headers. Returns `false` if a hook was already installed.
+///
+/// The hook runs on the task that builds the request, so implementations can read the
+/// current `tracing` span to derive the headers.
+pub fn set_header_injector<F>(injector: F) -> bool
+where
+    F: Fn() -> Vec<(String, String)> + Send + Sync + 'static,
+{
+    HEADER_INJECTOR.set(Box::new(injector)).is_ok()
+}
+
+pub(crate) trait InjectHeaders {
+    /// Attaches the headers produced by the installed injector, if any.
+    fn inject_headers(self) -> Self;
+}
+
+impl InjectHeaders for RequestBuilder {
+    fn inject_headers(self) -> Self {
+        match HEADER_INJECTOR.get() {
+            Some(injector) => injector()
+                .into_iter()
+                .fold(self, |builder, (key, value)| builder.header(key, value)),
+            None => self,
+        }
+    }
+}
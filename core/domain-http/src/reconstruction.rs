@@ -1,14 +1,21 @@
-use reqwest::{Client, Response};
+use std::future::Future;
+use std::time::Duration;
+
+#[cfg(target_family = "wasm")]
+use posemesh_utils::sleep;
 use serde::{Deserialize, Serialize};
+#[cfg(not(target_family = "wasm"))]
+use tokio::time::sleep;
 
 use crate::errors::{AukiErrorResponse, DomainError};
+use crate::transport::{SendVia, Transport};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JobRequest {
     pub data_ids: Vec<String>,
     #[serde(default = "default_processing_type")]
     pub processing_type: String,
-    #[serde(default = "default_api_key")]
+    /// Credential the domain server uses to authenticate against the reconstruction server.
     pub server_api_key: String,
     pub server_url: String,
 }
@@ -17,29 +24,99 @@ fn default_processing_type() -> String {
     "local_and_global_refinement".to_string()
 }
 
-fn default_api_key() -> String {
-    "aukilabs123".to_string()
-}
-
-impl Default for JobRequest {
-    fn default() -> Self {
+impl JobRequest {
+    /// A job with the default processing type.
+    pub fn new(
+        server_url: impl Into<String>,
+        server_api_key: impl Into<String>,
+        data_ids: Vec<String>,
+    ) -> Self {
         Self {
-            data_ids: vec![],
+            data_ids,
             processing_type: default_processing_type(),
-            server_api_key: default_api_key(),
-            server_url: "".to_string(),
+            server_api_key: server_api_key.into(),
+            server_url: server_url.into(),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    #[default]
+    #[serde(alias = "queued", alias = "created")]
+    Pending,
+    #[serde(alias = "processing", alias = "started", alias = "in_progress")]
+    Running,
+    #[serde(alias = "completed", alias = "done", alias = "success")]
+    Succeeded,
+    #[serde(alias = "error")]
+    Failed,
+    #[serde(alias = "canceled")]
+    Cancelled,
+    /// A status this version does not know about.
+    #[serde(other)]
+    Unknown,
+}
+
+impl JobStatus {
+    /// Whether the job will not change anymore.
+    pub fn is_terminal(self) -> bool {
+        matches!(
+            self,
+            JobStatus::Succeeded | JobStatus::Failed | JobStatus::Cancelled
+        )
+    }
+}
+
+/// A reconstruction job as reported by the domain server.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Job {
+    #[serde(alias = "id")]
+    pub job_id: String,
+    #[serde(default)]
+    pub status: JobStatus,
+    #[serde(default)]
+    pub processing_type: Option<String>,
+    /// Completion between 0 and 1, when the server reports it.
+    #[serde(default)]
+    pub progress: Option<f64>,
+    /// Input data ids.
+    #[serde(default)]
+    pub data_ids: Vec<String>,
+    /// Data ids written by the job, usually filled in once it succeeded.
+    #[serde(default)]
+    pub result_data_ids: Vec<String>,
+    /// Failure reason of a failed job.
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListJobsResponse {
+    #[serde(alias = "data")]
+    jobs: Vec<Job>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JobResultsResponse {
+    #[serde(alias = "result_data_ids")]
+    data_ids: Vec<String>,
+}
+
 pub async fn forward_job_request_v1(
+    transport: &Transport,
     domain_server_url: &str,
     client_id: &str,
     access_token: &str,
     domain_id: &str,
     request: &JobRequest,
-) -> Result<Response, DomainError> {
-    let response = Client::new()
+) -> Result<Job, DomainError> {
+    let response = transport
         .post(format!(
             "{}/api/v1/domains/{}/process",
             domain_server_url, domain_id
@@ -47,22 +124,160 @@ pub async fn forward_job_request_v1(
         .bearer_auth(access_token)
         .header("posemesh-client-id", client_id)
         .json(&request)
-        .send()
+        .send_via(transport)
         .await?;
 
     if response.status().is_success() {
-        Ok(response)
+        Ok(response.json::<Job>().await?)
     } else {
-        let status = response.status();
-        let text = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        Err(AukiErrorResponse {
-            status,
-            error: format!("Failed to process domain. {}", text),
+        Err(
+            AukiErrorResponse::from_response(response, "Failed to process domain.")
+                .await
+                .into(),
+        )
+    }
+}
+
+pub async fn get_job_v1(
+    transport: &Transport,
+    domain_server_url: &str,
+    client_id: &str,
+    access_token: &str,
+    domain_id: &str,
+    job_id: &str,
+) -> Result<Job, DomainError> {
+    let response = transport
+        .get(format!(
+            "{}/api/v1/domains/{}/jobs/{}",
+            domain_server_url, domain_id, job_id
+        ))
+        .bearer_auth(access_token)
+        .header("posemesh-client-id", client_id)
+        .send_via(transport)
+        .await?;
+
+    if response.status().is_success() {
+        Ok(response.json::<Job>().await?)
+    } else {
+        Err(
+            AukiErrorResponse::from_response(response, "Failed to get job.")
+                .await
+                .into(),
+        )
+    }
+}
+
+pub async fn list_jobs_v1(
+    transport: &Transport,
+    domain_server_url: &str,
+    client_id: &str,
+    access_token: &str,
+    domain_id: &str,
+) -> Result<Vec<Job>, DomainError> {
+    let response = transport
+        .get(format!(
+            "{}/api/v1/domains/{}/jobs",
+            domain_server_url, domain_id
+        ))
+        .bearer_auth(access_token)
+        .header("posemesh-client-id", client_id)
+        .send_via(transport)
+        .await?;
+
+    if response.status().is_success() {
+        Ok(response.json::<ListJobsResponse>().await?.jobs)
+    } else {
+        Err(
+            AukiErrorResponse::from_response(response, "Failed to list jobs.")
+                .await
+                .into(),
+        )
+    }
+}
+
+pub async fn cancel_job_v1(
+    transport: &Transport,
+    domain_server_url: &str,
+    client_id: &str,
+    access_token: &str,
+    domain_id: &str,
+    job_id: &str,
+) -> Result<(), DomainError> {
+    let response = transport
+        .post(format!(
+            "{}/api/v1/domains/{}/jobs/{}/cancel",
+            domain_server_url, domain_id, job_id
+        ))
+        .bearer_auth(access_token)
+        .header("posemesh-client-id", client_id)
+        .send_via(transport)
+        .await?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(
+            AukiErrorResponse::from_response(response, "Failed to cancel job.")
+                .await
+                .into(),
+        )
+    }
+}
+
+pub async fn get_job_results_v1(
+    transport: &Transport,
+    domain_server_url: &str,
+    client_id: &str,
+    access_token: &str,
+    domain_id: &str,
+    job_id: &str,
+) -> Result<Vec<String>, DomainError> {
+    let response = transport
+        .get(format!(
+            "{}/api/v1/domains/{}/jobs/{}/results",
+            domain_server_url, domain_id, job_id
+        ))
+        .bearer_auth(access_token)
+        .header("posemesh-client-id", client_id)
+        .send_via(transport)
+        .await?;
+
+    if response.status().is_success() {
+        Ok(response.json::<JobResultsResponse>().await?.data_ids)
+    } else {
+        Err(
+            AukiErrorResponse::from_response(response, "Failed to get job results.")
+                .await
+                .into(),
+        )
+    }
+}
+
+/// Polls `get_job` every `poll_interval` until the job reaches a terminal status and returns
+/// it. `on_progress` is called whenever the status or progress changes. A failed or cancelled job
+/// is returned as `Ok`; check [`Job::status`]. A [`JobStatus::Unknown`] status is returned too,
+/// as it may be terminal and this version cannot tell.
+pub async fn wait_for_job<G, Fut>(
+    mut get_job: G,
+    poll_interval: Duration,
+    mut on_progress: impl FnMut(&Job),
+) -> Result<Job, DomainError>
+where
+    G: FnMut() -> Fut,
+    Fut: Future<Output = Result<Job, DomainError>>,
+{
+    let mut last: Option<(JobStatus, Option<f64>)> = None;
+    loop {
+        let job = get_job().await?;
+        let state = (job.status, job.progress);
+        if last != Some(state) {
+            on_progress(&job);
+            last = Some(state);
+        }
+        if job.status.is_terminal() || job.status == JobStatus::Unknown {
+            return Ok(job);
         }
-        .into())
+        sleep(poll_interval).await;
     }
 }
 
@@ -72,15 +287,84 @@ mod tests {
 
     #[test]
     fn test_job_request_structure() {
-        let json =
-            r#"{"data_ids":["test-id-1", "test-id-2"], "server_url": "https://example.com"}"#;
+        let json = r#"{"data_ids":["test-id-1", "test-id-2"], "server_url": "https://example.com", "server_api_key": "key"}"#;
         assert!(json.contains("test-id-1"));
         assert!(json.contains("https://example.com"));
 
         let deserialized: JobRequest = serde_json::from_str(json).unwrap();
         assert_eq!(deserialized.data_ids.len(), 2);
         assert_eq!(deserialized.processing_type, "local_and_global_refinement");
-        assert_eq!(deserialized.server_api_key, "aukilabs123");
-        assert_eq!(deserialized.server_url, "https://example.com")
+        assert_eq!(deserialized.server_api_key, "key");
+        assert_eq!(deserialized.server_url, "https://example.com");
+
+        let missing_key = r#"{"data_ids":[], "server_url": "https://example.com"}"#;
+        assert!(serde_json::from_str::<JobRequest>(missing_key).is_err());
+    }
+
+    #[test]
+    fn test_job_status_aliases() {
+        let job: Job = serde_json::from_str(
+            r#"{"id": "j1", "status": "completed", "result_data_ids": ["r"]}"#,
+        )
+        .unwrap();
+        assert_eq!(job.job_id, "j1");
+        assert_eq!(job.status, JobStatus::Succeeded);
+        assert!(job.status.is_terminal());
+        let job: Job = serde_json::from_str(r#"{"job_id": "j2", "status": "paused"}"#).unwrap();
+        assert_eq!(job.status, JobStatus::Unknown);
+        let job: Job = serde_json::from_str(r#"{"job_id": "j3"}"#).unwrap();
+        assert_eq!(job.status, JobStatus::Pending);
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    #[tokio::test]
+    async fn test_wait_for_job_reports_changes() {
+        let mut states = vec![
+            (JobStatus::Pending, None),
+            (JobStatus::Running, Some(0.5)),
+            (JobStatus::Running, Some(0.5)),
+            (JobStatus::Succeeded, Some(1.0)),
+        ]
+        .into_iter();
+        let mut seen = Vec::new();
+        let job = wait_for_job(
+            || {
+                let (status, progress) = states.next().unwrap();
+                futures::future::ready(Ok(Job {
+                    job_id: "j".into(),
+                    status,
+                    progress,
+                    ..Default::default()
+                }))
+            },
+            Duration::from_millis(1),
+            |job| seen.push(job.status),
+        )
+        .await
+        .unwrap();
+        assert_eq!(job.status, JobStatus::Succeeded);
+        assert_eq!(
+            seen,
+            [JobStatus::Pending, JobStatus::Running, JobStatus::Succeeded]
+        );
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    #[tokio::test]
+    async fn test_wait_for_job_stops_on_unknown_status() {
+        let job = wait_for_job(
+            || {
+                futures::future::ready(Ok(Job {
+                    job_id: "j".into(),
+                    status: serde_json::from_str(r#""expired""#).unwrap(),
+                    ..Default::default()
+                }))
+            },
+            Duration::from_millis(1),
+            |_| {},
+        )
+        .await
+        .unwrap();
+        assert_eq!(job.status, JobStatus::Unknown);
     }
 }
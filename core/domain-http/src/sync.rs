@@ -0,0 +1,772 @@
+//! Incremental mirroring of a domain into a local directory.
+//!
+//! Items are stored as `<dir>/<data_type>/<name>` and tracked in `<dir>/.posemesh-index.json`,
+//! which maps each data id to its local path, the `updated_at` seen on the server and the SHA-256
+//! of the file as last synced. A run only downloads items whose `updated_at` (or hash) changed
+//! since the previous run, removes local copies of items deleted on the server and, with
+//! [`SyncOptions::push`], uploads files that were edited or added locally.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::dedup::{same_hash, sha256_hex};
+use crate::domain_data::{
+    DomainAction, DomainDataMetadata, DownloadQuery, UploadDomainData, download_metadata_v1,
+    download_v1_stream, upload_v1,
+};
+use crate::errors::DomainError;
+use crate::transport::Transport;
+
+/// Name of the index file kept in the root of a synced directory.
+pub const INDEX_FILE: &str = ".posemesh-index.json";
+
+/// Ids per download request; they are sent in the query string.
+const DOWNLOAD_BATCH: usize = 50;
+
+#[derive(Debug, Error)]
+pub enum SyncError {
+    #[error(transparent)]
+    Domain(#[from] DomainError),
+    #[error("I/O error on {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("invalid sync index {path}: {source}")]
+    Index {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("directory is a mirror of domain {found}, not {expected}")]
+    DomainMismatch { expected: String, found: String },
+}
+
+fn io_err(path: &Path) -> impl FnOnce(std::io::Error) -> SyncError + '_ {
+    move |source| SyncError::Io {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SyncOptions {
+    /// Also upload files that changed locally and files added under `<data_type>/<name>`.
+    pub push: bool,
+    /// Only mirror these data types; all types when empty.
+    pub data_types: Vec<String>,
+}
+
+/// What a sync run did. All entries are data ids.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SyncReport {
+    pub downloaded: Vec<String>,
+    pub uploaded: Vec<String>,
+    /// Deleted on the server and removed locally.
+    pub removed: Vec<String>,
+    /// Changed both locally and on the server; left untouched.
+    pub conflicts: Vec<String>,
+    pub unchanged: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexEntry {
+    /// Path relative to the sync root, `/`-separated.
+    pub path: String,
+    pub name: String,
+    pub data_type: String,
+    pub updated_at: String,
+    /// Hex SHA-256 of the file content as last synced.
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncIndex {
+    pub domain_id: String,
+    /// Entries keyed by data id.
+    pub items: BTreeMap<String, IndexEntry>,
+}
+
+impl SyncIndex {
+    /// Loads the index of `dir`, or an empty one if the directory was never synced.
+    pub async fn load(dir: &Path) -> Result<Self, SyncError> {
+        let path = dir.join(INDEX_FILE);
+        match tokio::fs::read(&path).await {
+            Ok(bytes) => {
+                serde_json::from_slice(&bytes).map_err(|source| SyncError::Index { path, source })
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(io_err(&path)(e)),
+        }
+    }
+
+    async fn save(&self, dir: &Path) -> Result<(), SyncError> {
+        let path = dir.join(INDEX_FILE);
+        let bytes = serde_json::to_vec_pretty(self).map_err(|source| SyncError::Index {
+            path: path.clone(),
+            source,
+        })?;
+        write_atomic(&path, &bytes).await
+    }
+}
+
+/// Makes a name usable as a single path component on all platforms.
+pub fn sanitize(component: &str) -> String {
+    let cleaned: String = component
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    // Hidden names are reserved for the index and partial downloads.
+    match cleaned.trim_start_matches('.') {
+        "" => "_".to_string(),
+        _ if cleaned.starts_with('.') => format!("_{cleaned}"),
+        _ => cleaned,
+    }
+}
+
+async fn write_atomic(path: &Path, bytes: &[u8]) -> Result<(), SyncError> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(io_err(parent))?;
+    }
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = path.with_file_name(format!(".{file_name}.partial"));
+    tokio::fs::write(&tmp, bytes).await.map_err(io_err(&tmp))?;
+    tokio::fs::rename(&tmp, path).await.map_err(io_err(path))
+}
+
+/// Hash of the local file, or `None` if it does not exist. Read in chunks, as scans can be large.
+async fn local_hash(path: &Path) -> Result<Option<String>, SyncError> {
+    use sha2::{Digest, Sha256};
+    use tokio::io::AsyncReadExt;
+
+    let mut file = match tokio::fs::File::open(path).await {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(io_err(path)(e)),
+    };
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer).await.map_err(io_err(path))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(Some(hex::encode(hasher.finalize())))
+}
+
+/// Files at `<dir>/<data_type>/<name>`, skipping hidden entries.
+async fn local_files(dir: &Path) -> Result<Vec<(String, String)>, SyncError> {
+    let mut files = Vec::new();
+    let mut types = match tokio::fs::read_dir(dir).await {
+        Ok(types) => types,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(files),
+        Err(e) => return Err(io_err(dir)(e)),
+    };
+    while let Some(type_dir) = types.next_entry().await.map_err(io_err(dir))? {
+        let data_type = type_dir.file_name().to_string_lossy().into_owned();
+        if data_type.starts_with('.') || !type_dir.path().is_dir() {
+            continue;
+        }
+        let type_path = type_dir.path();
+        let mut entries = tokio::fs::read_dir(&type_path)
+            .await
+            .map_err(io_err(&type_path))?;
+        while let Some(entry) = entries.next_entry().await.map_err(io_err(&type_path))? {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !name.starts_with('.') && entry.path().is_file() {
+                files.push((data_type.clone(), name));
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Mirrors `domain_id` into `dir`, downloading only what changed since the last run. See the
+/// [module docs](self) for the layout.
+pub async fn sync_dir_v1(
+    transport: &Transport,
+    url: &str,
+    client_id: &str,
+    access_token: &str,
+    domain_id: &str,
+    dir: &Path,
+    options: &SyncOptions,
+) -> Result<SyncReport, SyncError> {
+    let mut index = SyncIndex::load(dir).await?;
+    if index.domain_id.is_empty() {
+        index.domain_id = domain_id.to_string();
+    } else if index.domain_id != domain_id {
+        return Err(SyncError::DomainMismatch {
+            expected: domain_id.to_string(),
+            found: index.domain_id,
+        });
+    }
+
+    let query = DownloadQuery {
+        ids: vec![],
+        name: None,
+        data_type: None,
+    };
+    let remote: Vec<DomainDataMetadata> =
+        download_metadata_v1(transport, url, client_id, access_token, domain_id, &query)
+            .await?
+            .into_iter()
+            .filter(|m| options.data_types.is_empty() || options.data_types.contains(&m.data_type))
+            .collect();
+
+    let mut report = SyncReport::default();
+    let mut to_download = Vec::new();
+    let mut to_update = Vec::new();
+    let mut claimed: HashSet<String> = index.items.values().map(|e| e.path.clone()).collect();
+
+    for meta in &remote {
+        let Some(entry) = index.items.get_mut(&meta.id) else {
+            to_download.push(meta.id.clone());
+            continue;
+        };
+        let current = local_hash(&dir.join(&entry.path)).await?;
+        let remote_changed = entry.updated_at != meta.updated_at;
+        match current {
+            // Deleted locally: restore it, a mirror has no way to delete remotely.
+            None => to_download.push(meta.id.clone()),
+            Some(current) if current == entry.sha256 => {
+                if !remote_changed {
+                    report.unchanged += 1;
+                } else if same_hash(meta.hash.as_deref(), &current) {
+                    // Metadata-only change.
+                    entry.updated_at = meta.updated_at.clone();
+                    report.unchanged += 1;
+                } else {
+                    to_download.push(meta.id.clone());
+                }
+            }
+            // Edited locally.
+            Some(current) => {
+                if same_hash(meta.hash.as_deref(), &current) {
+                    // Both sides already hold the same content.
+                    entry.updated_at = meta.updated_at.clone();
+                    entry.sha256 = current;
+                    report.unchanged += 1;
+                } else if remote_changed {
+                    report.conflicts.push(meta.id.clone());
+                } else if options.push {
+                    to_update.push(meta.id.clone());
+                } else {
+                    report.unchanged += 1;
+                }
+            }
+        }
+    }
+
+    // Items gone from the server are removed locally unless they have unsynced edits.
+    let remote_ids: HashSet<&str> = remote.iter().map(|m| m.id.as_str()).collect();
+    let gone: Vec<String> = index
+        .items
+        .iter()
+        .filter(|(id, e)| {
+            !remote_ids.contains(id.as_str())
+                && (options.data_types.is_empty() || options.data_types.contains(&e.data_type))
+        })
+        .map(|(id, _)| id.clone())
+        .collect();
+    for id in gone {
+        let entry = &index.items[&id];
+        let path = dir.join(&entry.path);
+        match local_hash(&path).await? {
+            Some(hash) if hash != entry.sha256 => {
+                report.conflicts.push(id);
+                continue;
+            }
+            Some(_) => tokio::fs::remove_file(&path).await.map_err(io_err(&path))?,
+            None => {}
+        }
+        let entry = index.items.remove(&id).unwrap();
+        claimed.remove(&entry.path);
+        report.removed.push(id);
+    }
+
+    for batch in to_download.chunks(DOWNLOAD_BATCH) {
+        let query = DownloadQuery {
+            ids: batch.to_vec(),
+            name: None,
+            data_type: None,
+        };
+        let mut rx =
+            download_v1_stream(transport, url, client_id, access_token, domain_id, &query).await?;
+        while let Some(data) = rx.next().await {
+            let data = data?;
+            let meta = data.metadata;
+            let path = match index.items.get(&meta.id) {
+                Some(entry) => entry.path.clone(),
+                None => {
+                    let mut path =
+                        format!("{}/{}", sanitize(&meta.data_type), sanitize(&meta.name));
+                    // Names are not unique, and untracked local files must not be overwritten;
+                    // later duplicates get the id appended.
+                    let on_disk = tokio::fs::try_exists(dir.join(&path))
+                        .await
+                        .map_err(io_err(&dir.join(&path)))?;
+                    if claimed.contains(&path) || on_disk {
+                        path = format!("{path}.{}", sanitize(&meta.id));
+                    }
+                    claimed.insert(path.clone());
+                    path
+                }
+            };
+            write_atomic(&dir.join(&path), &data.data).await?;
+            index.items.insert(
+                meta.id.clone(),
+                IndexEntry {
+                    path,
+                    name: meta.name,
+                    data_type: meta.data_type,
+                    updated_at: meta.updated_at,
+                    sha256: sha256_hex(&data.data),
+                },
+            );
+            report.downloaded.push(meta.id);
+        }
+    }
+
+    if options.push {
+        push(
+            transport,
+            url,
+            access_token,
+            domain_id,
+            dir,
+            options,
+            &mut index,
+            to_update,
+            &claimed,
+            &mut report,
+        )
+        .await?;
+    }
+
+    index.save(dir).await?;
+    Ok(report)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn push(
+    transport: &Transport,
+    url: &str,
+    access_token: &str,
+    domain_id: &str,
+    dir: &Path,
+    options: &SyncOptions,
+    index: &mut SyncIndex,
+    to_update: Vec<String>,
+    claimed: &HashSet<String>,
+    report: &mut SyncReport,
+) -> Result<(), SyncError> {
+    let mut uploads = Vec::new();
+    // Local path of every upload, in the order the server returns metadata for them.
+    let mut update_paths = HashMap::new();
+    let mut create_paths = HashMap::new();
+
+    for id in to_update {
+        let path = index.items[&id].path.clone();
+        let data = tokio::fs::read(dir.join(&path))
+            .await
+            .map_err(io_err(&dir.join(&path)))?;
+        update_paths.insert(id.clone(), (path, sha256_hex(&data)));
+        uploads.push(UploadDomainData {
+            action: DomainAction::Update { id },
+            data,
+        });
+    }
+    for (data_type, name) in local_files(dir).await? {
+        let path = format!("{data_type}/{name}");
+        if claimed.contains(&path)
+            || !(options.data_types.is_empty() || options.data_types.contains(&data_type))
+        {
+            continue;
+        }
+        let data = tokio::fs::read(dir.join(&path))
+            .await
+            .map_err(io_err(&dir.join(&path)))?;
+        create_paths.insert((name.clone(), data_type.clone()), (path, sha256_hex(&data)));
+        uploads.push(UploadDomainData {
+            action: DomainAction::Create { name, data_type },
+            data,
+        });
+    }
+    if uploads.is_empty() {
+        return Ok(());
+    }
+
+    for meta in upload_v1(transport, url, access_token, domain_id, uploads).await? {
+        let local = update_paths
+            .remove(&meta.id)
+            .or_else(|| create_paths.remove(&(meta.name.clone(), meta.data_type.clone())));
+        let Some((path, sha256)) = local else {
+            continue;
+        };
+        index.items.insert(
+            meta.id.clone(),
+            IndexEntry {
+                path,
+                name: meta.name,
+                data_type: meta.data_type,
+                updated_at: meta.updated_at,
+                sha256,
+            },
+        );
+        report.uploaded.push(meta.id);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::test_support::{body_bytes, json_response};
+    use crate::transport::{ExecuteFuture, HttpExecutor};
+    use reqwest::{Method, Request, Response};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct Server {
+        /// id -> (name, data_type, updated_at, bytes)
+        items: BTreeMap<String, (String, String, String, Vec<u8>)>,
+        data_requests: usize,
+        next_id: u32,
+    }
+
+    impl Server {
+        fn meta(&self, id: &str) -> serde_json::Value {
+            let (name, data_type, updated_at, data) = &self.items[id];
+            serde_json::json!({
+                "id": id, "domain_id": "dom", "name": name, "data_type": data_type,
+                "size": data.len(), "created_at": "2025-01-01T00:00:00Z", "updated_at": updated_at,
+            })
+        }
+    }
+
+    /// Field of the first content-disposition in a multipart body, e.g. `id="..."`.
+    fn field(body: &str, key: &str) -> String {
+        let start = body.find(&format!("{key}=\"")).unwrap() + key.len() + 2;
+        body[start..].split('"').next().unwrap().to_string()
+    }
+
+    /// Single-part body contents (between the headers and the closing boundary).
+    fn part_data(body: &str) -> Vec<u8> {
+        let start = body.find("\r\n\r\n").unwrap() + 4;
+        let end = body.rfind("\r\n--boundary--").unwrap();
+        body.as_bytes()[start..end].to_vec()
+    }
+
+    struct Mock(Arc<Mutex<Server>>);
+
+    impl HttpExecutor for Mock {
+        fn execute(&self, mut request: Request) -> ExecuteFuture {
+            let mut server = self.0.lock().unwrap();
+            let path = request.url().path().to_string();
+            let json = |status: u16, body: serde_json::Value| json_response(status, &body);
+            let res = if path.ends_with("/info") {
+                json(404, serde_json::json!({}))
+            } else if request.method() == Method::GET
+                && request.headers()["accept"] == "application/json"
+            {
+                let data: Vec<_> = server.items.keys().map(|id| server.meta(id)).collect();
+                json(200, serde_json::json!({ "data": data }))
+            } else if request.method() == Method::GET {
+                server.data_requests += 1;
+                let query = request.url().query().unwrap_or_default().to_string();
+                let ids = query.trim_start_matches("ids=").replace("%2C", ",");
+                let mut body = Vec::new();
+                for id in ids.split(',') {
+                    let (name, data_type, updated_at, data) = &server.items[id];
+                    body.extend_from_slice(format!(
+                        "--b\r\nContent-Disposition: form-data; name=\"{name}\"; data-type=\"{data_type}\"; id=\"{id}\"; domain-id=\"dom\"; size=\"{}\"; created-at=\"2025-01-01T00:00:00Z\"; updated-at=\"{updated_at}\"\r\n\r\n",
+                        data.len()
+                    ).as_bytes());
+                    body.extend_from_slice(data);
+                    body.extend_from_slice(b"\r\n");
+                }
+                body.extend_from_slice(b"--b--\r\n");
+                Response::from(
+                    http::Response::builder()
+                        .status(200)
+                        .header("content-type", "multipart/form-data; boundary=b")
+                        .body(body)
+                        .unwrap(),
+                )
+            } else {
+                let body = String::from_utf8_lossy(&body_bytes(&mut request)).into_owned();
+                let id = if request.method() == Method::PUT {
+                    field(&body, "id")
+                } else {
+                    server.next_id += 1;
+                    format!("new-{}", server.next_id)
+                };
+                let (name, data_type) = match server.items.get(&id) {
+                    Some((name, data_type, ..)) => (name.clone(), data_type.clone()),
+                    None => (field(&body, "name"), field(&body, "data-type")),
+                };
+                server.items.insert(
+                    id.clone(),
+                    (
+                        name,
+                        data_type,
+                        "2025-03-01T00:00:00Z".into(),
+                        part_data(&body),
+                    ),
+                );
+                json(200, serde_json::json!({ "data": [server.meta(&id)] }))
+            };
+            Box::pin(async move { Ok(res) })
+        }
+    }
+
+    fn insert(
+        server: &Arc<Mutex<Server>>,
+        id: &str,
+        name: &str,
+        data_type: &str,
+        ts: &str,
+        data: &[u8],
+    ) {
+        server.lock().unwrap().items.insert(
+            id.into(),
+            (name.into(), data_type.into(), ts.into(), data.to_vec()),
+        );
+    }
+
+    #[tokio::test]
+    async fn syncs_incrementally_and_pushes_local_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = Arc::new(Mutex::new(Server::default()));
+        insert(
+            &server,
+            "1",
+            "scan.ply",
+            "mesh",
+            "2025-01-01T00:00:00Z",
+            b"hello",
+        );
+        insert(
+            &server,
+            "2",
+            "../photo.png",
+            "image",
+            "2025-01-01T00:00:00Z",
+            b"world",
+        );
+        let transport = Transport::with_executor(Mock(server.clone()));
+        let sync = |options: SyncOptions| {
+            let transport = transport.clone();
+            let dir = dir.path().to_path_buf();
+            async move {
+                sync_dir_v1(
+                    &transport,
+                    "http://sync-test",
+                    "c",
+                    "t",
+                    "dom",
+                    &dir,
+                    &options,
+                )
+                .await
+                .unwrap()
+            }
+        };
+
+        let report = sync(SyncOptions::default()).await;
+        assert_eq!(report.downloaded, ["1", "2"]);
+        assert_eq!(
+            std::fs::read(dir.path().join("mesh/scan.ply")).unwrap(),
+            b"hello"
+        );
+        assert_eq!(
+            std::fs::read(dir.path().join("image/_.._photo.png")).unwrap(),
+            b"world"
+        );
+
+        // Nothing changed: no data is fetched again.
+        let report = sync(SyncOptions::default()).await;
+        assert!(report.downloaded.is_empty());
+        assert_eq!(report.unchanged, 2);
+        assert_eq!(server.lock().unwrap().data_requests, 1);
+
+        // Remote update and delete.
+        insert(
+            &server,
+            "2",
+            "../photo.png",
+            "image",
+            "2025-02-01T00:00:00Z",
+            b"world 2",
+        );
+        server.lock().unwrap().items.remove("1");
+        let report = sync(SyncOptions::default()).await;
+        assert_eq!(report.downloaded, ["2"]);
+        assert_eq!(report.removed, ["1"]);
+        assert!(!dir.path().join("mesh/scan.ply").exists());
+        assert_eq!(
+            std::fs::read(dir.path().join("image/_.._photo.png")).unwrap(),
+            b"world 2"
+        );
+
+        // Local edit and a new local file are pushed.
+        std::fs::write(dir.path().join("image/_.._photo.png"), b"edited").unwrap();
+        std::fs::create_dir_all(dir.path().join("mesh")).unwrap();
+        std::fs::write(dir.path().join("mesh/new.ply"), b"fresh").unwrap();
+        let report = sync(SyncOptions {
+            push: true,
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(report.uploaded.len(), 2);
+        assert!(report.uploaded.contains(&"2".to_string()));
+        {
+            let server = server.lock().unwrap();
+            assert_eq!(server.items["2"].3, b"edited");
+            assert_eq!(server.items["new-1"].0, "new.ply");
+            assert_eq!(server.items["new-1"].3, b"fresh");
+        }
+
+        // The uploads are recorded, so the next run is a no-op.
+        let report = sync(SyncOptions {
+            push: true,
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(
+            report,
+            SyncReport {
+                unchanged: 2,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_conflicts_instead_of_overwriting() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = Arc::new(Mutex::new(Server::default()));
+        insert(
+            &server,
+            "1",
+            "scan.ply",
+            "mesh",
+            "2025-01-01T00:00:00Z",
+            b"hello",
+        );
+        let transport = Transport::with_executor(Mock(server.clone()));
+        let options = SyncOptions {
+            push: true,
+            ..Default::default()
+        };
+        sync_dir_v1(
+            &transport,
+            "http://sync-conflict",
+            "c",
+            "t",
+            "dom",
+            dir.path(),
+            &options,
+        )
+        .await
+        .unwrap();
+
+        std::fs::write(dir.path().join("mesh/scan.ply"), b"local").unwrap();
+        insert(
+            &server,
+            "1",
+            "scan.ply",
+            "mesh",
+            "2025-02-01T00:00:00Z",
+            b"remote",
+        );
+        let report = sync_dir_v1(
+            &transport,
+            "http://sync-conflict",
+            "c",
+            "t",
+            "dom",
+            dir.path(),
+            &options,
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.conflicts, ["1"]);
+        assert_eq!(
+            std::fs::read(dir.path().join("mesh/scan.ply")).unwrap(),
+            b"local"
+        );
+        assert_eq!(server.lock().unwrap().items["1"].3, b"remote");
+
+        let err = sync_dir_v1(
+            &transport,
+            "http://sync-conflict",
+            "c",
+            "t",
+            "other",
+            dir.path(),
+            &options,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, SyncError::DomainMismatch { .. }));
+    }
+
+    #[tokio::test]
+    async fn keeps_untracked_local_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("mesh")).unwrap();
+        std::fs::write(dir.path().join("mesh/scan.ply"), b"local").unwrap();
+        let server = Arc::new(Mutex::new(Server::default()));
+        insert(
+            &server,
+            "1",
+            "scan.ply",
+            "mesh",
+            "2025-01-01T00:00:00Z",
+            b"remote",
+        );
+        let transport = Transport::with_executor(Mock(server.clone()));
+
+        let report = sync_dir_v1(
+            &transport,
+            "http://sync-untracked",
+            "c",
+            "t",
+            "dom",
+            dir.path(),
+            &SyncOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.downloaded, ["1"]);
+        assert_eq!(
+            std::fs::read(dir.path().join("mesh/scan.ply")).unwrap(),
+            b"local"
+        );
+        assert_eq!(
+            std::fs::read(dir.path().join("mesh/scan.ply.1")).unwrap(),
+            b"remote"
+        );
+    }
+
+    #[test]
+    fn sanitizes_path_components() {
+        assert_eq!(sanitize("a/b\\c:d"), "a_b_c_d");
+        assert_eq!(sanitize(".."), "_");
+        assert_eq!(sanitize(".hidden"), "_.hidden");
+        assert_eq!(sanitize("scan.ply"), "scan.ply");
+    }
+}
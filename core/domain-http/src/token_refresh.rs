@@ -0,0 +1,234 @@
+//! Renewing tokens ahead of expiry.
+//!
+//! [`DomainClient::start_token_refresh`](crate::domain_client::DomainClient::start_token_refresh)
+//! runs a background task (a tokio task natively, a timer in the browser) that renews the DDS
+//! token and the tokens of recently used domains [`TokenRefreshOptions::refresh_ahead`] before
+//! they expire, so calls never start with a token about to run out. Domains unused for
+//! [`TokenRefreshOptions::idle_timeout`] are dropped instead of renewed, and at most
+//! [`TokenRefreshOptions::max_domains`] are kept, evicting the least recently used.
+//!
+//! A download stream outliving its token is handed over to the renewed one: if the stream fails
+//! after the token it started with expired, it is reopened with the current token and continues
+//! with the items not received yet.
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use futures::channel::mpsc;
+use futures::{SinkExt, StreamExt};
+use posemesh_utils::now_unix_secs;
+
+use crate::domain_data::{DomainData, DownloadQuery};
+use crate::errors::DomainError;
+
+#[cfg(not(target_family = "wasm"))]
+use tokio::spawn;
+#[cfg(target_family = "wasm")]
+use wasm_bindgen_futures::spawn_local as spawn;
+
+/// How long before expiry tokens are renewed by default.
+pub const DEFAULT_REFRESH_AHEAD: Duration = Duration::from_secs(120);
+/// Domain tokens kept by default, with or without background refresh.
+pub const DEFAULT_MAX_CACHED_DOMAINS: usize = 64;
+/// How long a domain may go unused before its token is dropped rather than renewed.
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+// Times a download stream is reopened with a renewed token before its error is passed on.
+const MAX_HANDOFFS: usize = 3;
+
+#[derive(Debug, Clone)]
+pub struct TokenRefreshOptions {
+    /// How long before expiry tokens are renewed. Keep it well below the token lifetime.
+    pub refresh_ahead: Duration,
+    /// Domain tokens kept; the least recently used domain is dropped beyond that.
+    pub max_domains: usize,
+    /// Domains unused for this long are dropped instead of renewed.
+    pub idle_timeout: Duration,
+}
+
+impl Default for TokenRefreshOptions {
+    fn default() -> Self {
+        Self {
+            refresh_ahead: DEFAULT_REFRESH_AHEAD,
+            max_domains: DEFAULT_MAX_CACHED_DOMAINS,
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+        }
+    }
+}
+
+/// An open download and the expiry of the token it was opened with.
+pub(crate) type OpenedDownload = (u64, mpsc::Receiver<Result<DomainData, DomainError>>);
+
+#[cfg(not(target_family = "wasm"))]
+pub(crate) type ReopenFuture =
+    futures::future::BoxFuture<'static, Result<OpenedDownload, DomainError>>;
+#[cfg(target_family = "wasm")]
+pub(crate) type ReopenFuture =
+    futures::future::LocalBoxFuture<'static, Result<OpenedDownload, DomainError>>;
+
+/// Opens the download of a query again with the current token.
+#[cfg(not(target_family = "wasm"))]
+pub(crate) type Reopen = Box<dyn Fn(DownloadQuery) -> ReopenFuture + Send>;
+/// Opens the download of a query again with the current token.
+#[cfg(target_family = "wasm")]
+pub(crate) type Reopen = Box<dyn Fn(DownloadQuery) -> ReopenFuture>;
+
+/// Forwards `download`, reopening it with `reopen` when it fails after its token expired. The
+/// reopened download asks for the remaining ids, or the whole query again, and skips items
+/// already forwarded. A download of ids that all arrived ends instead.
+pub(crate) fn hand_off(
+    download: OpenedDownload,
+    query: DownloadQuery,
+    reopen: Reopen,
+) -> mpsc::Receiver<Result<DomainData, DomainError>> {
+    let (mut tx, rx) = mpsc::channel(100);
+    spawn(async move {
+        let (mut expires_at, mut items) = download;
+        let mut received = HashSet::new();
+        let mut handoffs = 0;
+        while let Some(item) = items.next().await {
+            let item = match item {
+                Ok(item) if !received.insert(item.metadata.id.clone()) => continue,
+                Err(e)
+                    if !matches!(e, DomainError::Cancelled)
+                        && expires_at <= now_unix_secs()
+                        && handoffs < MAX_HANDOFFS =>
+                {
+                    let ids: Vec<String> = query
+                        .ids
+                        .iter()
+                        .filter(|id| !received.contains(*id))
+                        .cloned()
+                        .collect();
+                    // Every requested id arrived; an empty id list would match the whole domain.
+                    if !query.ids.is_empty() && ids.is_empty() {
+                        return;
+                    }
+                    handoffs += 1;
+                    tracing::debug!("Download outlived its token, reopening with a renewed one");
+                    let remaining = DownloadQuery {
+                        ids,
+                        ..query.clone()
+                    };
+                    match reopen(remaining).await {
+                        Ok(download) => {
+                            (expires_at, items) = download;
+                            continue;
+                        }
+                        Err(e) => Err(e),
+                    }
+                }
+                item => item,
+            };
+            if tx.send(item).await.is_err() {
+                return;
+            }
+        }
+    });
+    rx
+}
+
+#[cfg(not(target_family = "wasm"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    use crate::domain_data::DomainDataMetadata;
+
+    fn item(id: &str) -> Result<DomainData, DomainError> {
+        Ok(DomainData {
+            metadata: DomainDataMetadata {
+                id: id.into(),
+                domain_id: "dom".into(),
+                name: id.into(),
+                data_type: "ply".into(),
+                size: 0,
+                created_at: String::new(),
+                updated_at: String::new(),
+                hash: None,
+            },
+            data: vec![],
+        })
+    }
+
+    fn download(
+        items: Vec<Result<DomainData, DomainError>>,
+    ) -> mpsc::Receiver<Result<DomainData, DomainError>> {
+        let (mut tx, rx) = mpsc::channel(10);
+        for item in items {
+            tx.try_send(item).unwrap();
+        }
+        rx
+    }
+
+    async fn ids(rx: mpsc::Receiver<Result<DomainData, DomainError>>) -> Vec<String> {
+        rx.map(|item| item.map_or_else(|e| format!("error: {e}"), |d| d.metadata.id))
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn reopens_expired_downloads_with_remaining_ids() {
+        let queries = Arc::new(Mutex::new(Vec::new()));
+        let reopen: Reopen = {
+            let queries = queries.clone();
+            Box::new(move |query: DownloadQuery| {
+                queries.lock().unwrap().push(query.ids.clone());
+                Box::pin(async move {
+                    Ok((now_unix_secs() + 3600, download(vec![item("2"), item("3")])))
+                })
+            })
+        };
+        let query = DownloadQuery {
+            ids: ["1", "2", "3"].map(String::from).to_vec(),
+            name: None,
+            data_type: None,
+        };
+        let expired = (
+            now_unix_secs() - 1,
+            download(vec![item("1"), Err(DomainError::InvalidContentTypeHeader)]),
+        );
+
+        assert_eq!(ids(hand_off(expired, query, reopen)).await, ["1", "2", "3"]);
+        assert_eq!(*queries.lock().unwrap(), [["2", "3"]]);
+    }
+
+    #[tokio::test]
+    async fn ends_expired_downloads_once_all_ids_arrived() {
+        let reopen: Reopen = Box::new(|_| Box::pin(async { panic!("nothing left to download") }));
+        let query = DownloadQuery {
+            ids: ["1", "2"].map(String::from).to_vec(),
+            name: None,
+            data_type: Some("ply".into()),
+        };
+        let expired = (
+            now_unix_secs() - 1,
+            download(vec![
+                item("1"),
+                item("2"),
+                Err(DomainError::InvalidContentTypeHeader),
+            ]),
+        );
+
+        assert_eq!(ids(hand_off(expired, query, reopen)).await, ["1", "2"]);
+    }
+
+    #[tokio::test]
+    async fn passes_on_errors_of_valid_tokens() {
+        let reopen: Reopen = Box::new(|_| Box::pin(async { panic!("token was still valid") }));
+        let query = DownloadQuery {
+            ids: vec![],
+            name: None,
+            data_type: Some("ply".into()),
+        };
+        let valid = (
+            now_unix_secs() + 3600,
+            download(vec![item("1"), Err(DomainError::InvalidContentTypeHeader)]),
+        );
+
+        let received = ids(hand_off(valid, query, reopen)).await;
+        assert_eq!(received[0], "1");
+        assert!(received[1].starts_with("error"));
+    }
+}
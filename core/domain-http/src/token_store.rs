@@ -0,0 +1,312 @@
+//! Persistence for user sessions, so a signed-in client survives restarts.
+//!
+//! [`AuthClient`](crate::auth::AuthClient) saves the user refresh and access tokens to its
+//! [`TokenStore`] after every login and refresh, and
+//! [`DomainClient::restore_session`](crate::domain_client::DomainClient::restore_session) signs in
+//! again from the stored refresh token without asking for the password.
+
+use std::fmt;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[error("token store: {0}")]
+pub struct TokenStoreError(pub String);
+
+impl From<std::io::Error> for TokenStoreError {
+    fn from(e: std::io::Error) -> Self {
+        Self(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for TokenStoreError {
+    fn from(e: serde_json::Error) -> Self {
+        Self(e.to_string())
+    }
+}
+
+/// Tokens of a signed-in user.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredSession {
+    pub refresh_token: String,
+    pub access_token: String,
+}
+
+impl fmt::Debug for StoredSession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StoredSession").finish_non_exhaustive()
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+pub type StoreFuture<'a, T> = futures::future::BoxFuture<'a, Result<T, TokenStoreError>>;
+#[cfg(target_family = "wasm")]
+pub type StoreFuture<'a, T> = futures::future::LocalBoxFuture<'a, Result<T, TokenStoreError>>;
+
+/// Where a client keeps its session between runs.
+#[cfg(not(target_family = "wasm"))]
+pub trait TokenStore: Send + Sync {
+    fn load(&self) -> StoreFuture<'_, Option<StoredSession>>;
+    fn save<'a>(&'a self, session: &'a StoredSession) -> StoreFuture<'a, ()>;
+    fn clear(&self) -> StoreFuture<'_, ()>;
+}
+
+/// Where a client keeps its session between runs.
+#[cfg(target_family = "wasm")]
+pub trait TokenStore {
+    fn load(&self) -> StoreFuture<'_, Option<StoredSession>>;
+    fn save<'a>(&'a self, session: &'a StoredSession) -> StoreFuture<'a, ()>;
+    fn clear(&self) -> StoreFuture<'_, ()>;
+}
+
+impl fmt::Debug for dyn TokenStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TokenStore")
+    }
+}
+
+/// Keeps the session for the lifetime of the process only.
+#[derive(Debug, Default)]
+pub struct MemoryTokenStore(Mutex<Option<StoredSession>>);
+
+impl TokenStore for MemoryTokenStore {
+    fn load(&self) -> StoreFuture<'_, Option<StoredSession>> {
+        let session = self.0.lock().unwrap().clone();
+        Box::pin(async move { Ok(session) })
+    }
+
+    fn save<'a>(&'a self, session: &'a StoredSession) -> StoreFuture<'a, ()> {
+        *self.0.lock().unwrap() = Some(session.clone());
+        Box::pin(async { Ok(()) })
+    }
+
+    fn clear(&self) -> StoreFuture<'_, ()> {
+        *self.0.lock().unwrap() = None;
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+pub use file::EncryptedFileTokenStore;
+
+#[cfg(not(target_family = "wasm"))]
+mod file {
+    use std::path::PathBuf;
+
+    use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+    use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+    use super::{StoreFuture, StoredSession, TokenStore, TokenStoreError};
+
+    const NONCE_LEN: usize = 12;
+
+    /// Stores the session in a file encrypted with ChaCha20-Poly1305. The 32-byte key should come
+    /// from the platform keychain or a secret manager, not from the same disk.
+    pub struct EncryptedFileTokenStore {
+        path: PathBuf,
+        cipher: ChaCha20Poly1305,
+    }
+
+    impl std::fmt::Debug for EncryptedFileTokenStore {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("EncryptedFileTokenStore")
+                .field("path", &self.path)
+                .finish_non_exhaustive()
+        }
+    }
+
+    impl EncryptedFileTokenStore {
+        pub fn new(path: impl Into<PathBuf>, key: &[u8; 32]) -> Self {
+            Self {
+                path: path.into(),
+                cipher: ChaCha20Poly1305::new(Key::from_slice(key)),
+            }
+        }
+
+        async fn read(&self) -> Result<Option<StoredSession>, TokenStoreError> {
+            let bytes = match tokio::fs::read(&self.path).await {
+                Ok(bytes) => bytes,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+                Err(e) => return Err(e.into()),
+            };
+            if bytes.len() < NONCE_LEN {
+                return Err(TokenStoreError("session file is truncated".into()));
+            }
+            let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+            let plaintext = self
+                .cipher
+                .decrypt(Nonce::from_slice(nonce), ciphertext)
+                .map_err(|_| TokenStoreError("cannot decrypt session file, wrong key?".into()))?;
+            Ok(Some(serde_json::from_slice(&plaintext)?))
+        }
+
+        async fn write(&self, session: &StoredSession) -> Result<(), TokenStoreError> {
+            let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+            let ciphertext = self
+                .cipher
+                .encrypt(&nonce, serde_json::to_vec(session)?.as_slice())
+                .map_err(|_| TokenStoreError("cannot encrypt session".into()))?;
+            let mut bytes = nonce.to_vec();
+            bytes.extend_from_slice(&ciphertext);
+
+            if let Some(parent) = self.path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            let tmp = self.path.with_extension("tmp");
+            tokio::fs::write(&tmp, bytes).await?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                tokio::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o600)).await?;
+            }
+            tokio::fs::rename(&tmp, &self.path).await?;
+            Ok(())
+        }
+    }
+
+    impl TokenStore for EncryptedFileTokenStore {
+        fn load(&self) -> StoreFuture<'_, Option<StoredSession>> {
+            Box::pin(self.read())
+        }
+
+        fn save<'a>(&'a self, session: &'a StoredSession) -> StoreFuture<'a, ()> {
+            Box::pin(self.write(session))
+        }
+
+        fn clear(&self) -> StoreFuture<'_, ()> {
+            Box::pin(async move {
+                match tokio::fs::remove_file(&self.path).await {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                    _ => Ok(()),
+                }
+            })
+        }
+    }
+}
+
+#[cfg(target_family = "wasm")]
+pub use js::JsTokenStore;
+
+#[cfg(target_family = "wasm")]
+mod js {
+    use wasm_bindgen::{JsCast, JsValue};
+    use wasm_bindgen_futures::JsFuture;
+    use wasm_bindgen_futures::js_sys::{self, Function, Promise, Reflect};
+
+    use super::{StoreFuture, StoredSession, TokenStore, TokenStoreError};
+
+    /// Adapts a JS storage object with `getItem(key)`, `setItem(key, value)` and
+    /// `removeItem(key)`, returning values or Promises. `localStorage` works as is, and so do
+    /// IndexedDB wrappers such as localForage.
+    pub struct JsTokenStore {
+        storage: JsValue,
+        key: String,
+    }
+
+    impl JsTokenStore {
+        pub fn new(storage: JsValue, key: &str) -> Self {
+            Self {
+                storage,
+                key: key.to_string(),
+            }
+        }
+
+        /// Uses `globalThis.localStorage`.
+        pub fn local_storage(key: &str) -> Result<Self, TokenStoreError> {
+            let storage = Reflect::get(&js_sys::global(), &JsValue::from_str("localStorage"))
+                .map_err(js_error)?;
+            if storage.is_undefined() || storage.is_null() {
+                return Err(TokenStoreError("localStorage is not available".into()));
+            }
+            Ok(Self::new(storage, key))
+        }
+
+        async fn call(&self, method: &str, args: &[JsValue]) -> Result<JsValue, TokenStoreError> {
+            let function: Function = Reflect::get(&self.storage, &JsValue::from_str(method))
+                .map_err(js_error)?
+                .dyn_into()
+                .map_err(|_| TokenStoreError(format!("storage has no {method} function")))?;
+            let args = js_sys::Array::from_iter(
+                std::iter::once(JsValue::from_str(&self.key)).chain(args.iter().cloned()),
+            );
+            let result = function.apply(&self.storage, &args).map_err(js_error)?;
+            match result.dyn_into::<Promise>() {
+                Ok(promise) => JsFuture::from(promise).await.map_err(js_error),
+                Err(value) => Ok(value),
+            }
+        }
+    }
+
+    fn js_error(e: JsValue) -> TokenStoreError {
+        TokenStoreError(
+            e.as_string()
+                .or_else(|| e.dyn_ref::<js_sys::Error>().map(|e| e.message().into()))
+                .unwrap_or_else(|| format!("{e:?}")),
+        )
+    }
+
+    impl TokenStore for JsTokenStore {
+        fn load(&self) -> StoreFuture<'_, Option<StoredSession>> {
+            Box::pin(async move {
+                match self.call("getItem", &[]).await?.as_string() {
+                    Some(json) => Ok(Some(serde_json::from_str(&json)?)),
+                    None => Ok(None),
+                }
+            })
+        }
+
+        fn save<'a>(&'a self, session: &'a StoredSession) -> StoreFuture<'a, ()> {
+            Box::pin(async move {
+                let json = serde_json::to_string(session)?;
+                self.call("setItem", &[JsValue::from_str(&json)]).await?;
+                Ok(())
+            })
+        }
+
+        fn clear(&self) -> StoreFuture<'_, ()> {
+            Box::pin(async move {
+                self.call("removeItem", &[]).await?;
+                Ok(())
+            })
+        }
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> StoredSession {
+        StoredSession {
+            refresh_token: "refresh".into(),
+            access_token: "access".into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn encrypted_file_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.bin");
+        let store = EncryptedFileTokenStore::new(&path, &[7; 32]);
+        assert_eq!(store.load().await.unwrap(), None);
+
+        store.save(&session()).await.unwrap();
+        assert!(
+            !std::fs::read(&path)
+                .unwrap()
+                .windows(7)
+                .any(|w| w == b"refresh")
+        );
+        assert_eq!(store.load().await.unwrap(), Some(session()));
+
+        let wrong_key = EncryptedFileTokenStore::new(&path, &[8; 32]);
+        assert!(wrong_key.load().await.is_err());
+
+        store.clear().await.unwrap();
+        assert_eq!(store.load().await.unwrap(), None);
+        store.clear().await.unwrap();
+    }
+}
@@ -0,0 +1,562 @@
+use std::{fmt, sync::Arc, time::Duration};
+
+#[cfg(not(target_family = "wasm"))]
+use reqwest::Body;
+use reqwest::{
+    Client, IntoUrl, Method, Request, RequestBuilder, Response,
+    header::{HeaderMap, HeaderName, HeaderValue, USER_AGENT},
+};
+
+#[cfg(target_family = "wasm")]
+use posemesh_utils::sleep;
+#[cfg(not(target_family = "wasm"))]
+use tokio::time::sleep;
+
+use crate::errors::DomainError;
+use crate::propagation::InjectHeaders;
+use crate::retry::{RetryPolicy, is_idempotent, is_retryable_status, retry_after};
+
+#[cfg(not(target_family = "wasm"))]
+pub type ExecuteFuture = futures::future::BoxFuture<'static, Result<Response, DomainError>>;
+#[cfg(target_family = "wasm")]
+pub type ExecuteFuture = futures::future::LocalBoxFuture<'static, Result<Response, DomainError>>;
+
+/// Sends a fully built request. The default implementation is a pooled `reqwest::Client`;
+/// tests can plug in their own executor to return canned responses.
+pub trait HttpExecutor: Send + Sync {
+    fn execute(&self, request: Request) -> ExecuteFuture;
+}
+
+impl HttpExecutor for Client {
+    fn execute(&self, request: Request) -> ExecuteFuture {
+        let client = self.clone();
+        Box::pin(async move { Ok(client.execute(request).await?) })
+    }
+}
+
+/// HTTP transport shared by every request a `DomainClient` makes to the domain servers,
+/// DDS and the auth API. Cloning is cheap and keeps the connection pool.
+#[derive(Clone)]
+pub struct Transport {
+    client: Client,
+    executor: Arc<dyn HttpExecutor>,
+    default_headers: HeaderMap,
+    retry_policy: RetryPolicy,
+}
+
+impl fmt::Debug for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transport")
+            .field(
+                "default_headers",
+                &self.default_headers.keys().collect::<Vec<_>>(),
+            )
+            .field("retry_policy", &self.retry_policy)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for Transport {
+    fn default() -> Self {
+        Self::from_client(Client::new())
+    }
+}
+
+impl Transport {
+    pub fn builder() -> TransportBuilder {
+        TransportBuilder::default()
+    }
+
+    /// Wraps an already configured `reqwest::Client`.
+    pub fn from_client(client: Client) -> Self {
+        Self {
+            executor: Arc::new(client.clone()),
+            client,
+            default_headers: HeaderMap::new(),
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// Routes every request through `executor` instead of the network.
+    pub fn with_executor<E: HttpExecutor + 'static>(executor: E) -> Self {
+        Self {
+            executor: Arc::new(executor),
+            ..Self::default()
+        }
+    }
+
+    /// The underlying client, for callers that need to issue requests this crate does not cover.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Starts a request with the transport's default headers applied.
+    pub fn request<U: IntoUrl>(&self, method: Method, url: U) -> RequestBuilder {
+        self.client
+            .request(method, url)
+            .headers(self.default_headers.clone())
+    }
+
+    pub fn get<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        self.request(Method::GET, url)
+    }
+
+    pub fn post<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        self.request(Method::POST, url)
+    }
+
+    pub fn put<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        self.request(Method::PUT, url)
+    }
+
+    pub fn delete<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        self.request(Method::DELETE, url)
+    }
+
+    /// Sends a request built from this transport, retrying it according to the
+    /// [`RetryPolicy`]. A retryable status is returned as a response once retries run out.
+    pub async fn send(&self, builder: RequestBuilder) -> Result<Response, DomainError> {
+        let request = builder.inject_headers().build()?;
+        self.send_attempts(request, Request::try_clone).await
+    }
+
+    /// Like [`Transport::send`], but gives every attempt a new body from `body`, so requests
+    /// with streamed bodies are retried too.
+    #[cfg(not(target_family = "wasm"))]
+    pub(crate) async fn send_with_body(
+        &self,
+        builder: RequestBuilder,
+        body: impl Fn() -> Body,
+    ) -> Result<Response, DomainError> {
+        let mut request = builder.inject_headers().build()?;
+        let template = request.try_clone();
+        *request.body_mut() = Some(body());
+        self.send_attempts(request, |_| {
+            let mut next = template.as_ref()?.try_clone()?;
+            *next.body_mut() = Some(body());
+            Some(next)
+        })
+        .await
+    }
+
+    /// Sends `request`, retrying it with the request `replay` returns for it while the
+    /// [`RetryPolicy`] allows.
+    async fn send_attempts(
+        &self,
+        mut request: Request,
+        replay: impl Fn(&Request) -> Option<Request>,
+    ) -> Result<Response, DomainError> {
+        let policy = &self.retry_policy;
+        let idempotent = is_idempotent(request.method());
+        let mut attempt = 0;
+        loop {
+            let replay = if idempotent && attempt < policy.max_retries {
+                replay(&request)
+            } else {
+                None
+            };
+            let result = self.executor.execute(request).await;
+            let Some(next) = replay else {
+                return result;
+            };
+            let delay = match &result {
+                Ok(res) if is_retryable_status(res.status()) => match retry_after(res.headers()) {
+                    Some(after) if after > policy.max_retry_after => return result,
+                    Some(after) => after,
+                    None => policy.backoff(attempt),
+                },
+                Err(err) if err.is_retryable() => policy.backoff(attempt),
+                _ => return result,
+            };
+            attempt += 1;
+            tracing::debug!(
+                attempt,
+                delay_ms = delay.as_millis() as u64,
+                outcome = %match &result {
+                    Ok(res) => res.status().to_string(),
+                    Err(err) => err.to_string(),
+                },
+                "Retrying request"
+            );
+            sleep(delay).await;
+            request = next;
+        }
+    }
+}
+
+pub(crate) trait SendVia {
+    /// Sends this request through `transport`.
+    async fn send_via(self, transport: &Transport) -> Result<Response, DomainError>;
+}
+
+impl SendVia for RequestBuilder {
+    async fn send_via(self, transport: &Transport) -> Result<Response, DomainError> {
+        transport.send(self).await
+    }
+}
+
+/// Configures a [`Transport`]. Timeouts, proxy and root certificates only apply to native
+/// targets; in the browser they are governed by `fetch`.
+#[derive(Debug, Default)]
+#[cfg_attr(target_family = "wasm", allow(dead_code))]
+pub struct TransportBuilder {
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    pool_idle_timeout: Option<Duration>,
+    proxy: Option<String>,
+    root_certificates: Vec<Vec<u8>>,
+    default_headers: Vec<(String, String)>,
+    user_agent: Option<String>,
+    retry_policy: RetryPolicy,
+}
+
+impl TransportBuilder {
+    /// Total time allowed for a request, including reading the body.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Routes all traffic through the given proxy URL (`http://`, `https://` or `socks5://`).
+    pub fn proxy(mut self, url: impl Into<String>) -> Self {
+        self.proxy = Some(url.into());
+        self
+    }
+
+    /// Trusts an extra PEM-encoded root certificate in addition to the built-in roots.
+    pub fn add_root_certificate_pem(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.root_certificates.push(pem.into());
+        self
+    }
+
+    /// Adds a header sent with every request.
+    pub fn default_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.default_headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Shorthand for setting [`RetryPolicy::max_retries`].
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retry_policy.max_retries = retries;
+        self
+    }
+
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn build(self) -> Result<Transport, DomainError> {
+        let mut default_headers = HeaderMap::new();
+        for (name, value) in &self.default_headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| DomainError::InvalidRequest("invalid default header name"))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|_| DomainError::InvalidRequest("invalid default header value"))?;
+            default_headers.append(name, value);
+        }
+        if let Some(user_agent) = &self.user_agent {
+            let value = HeaderValue::from_str(user_agent)
+                .map_err(|_| DomainError::InvalidRequest("invalid user agent"))?;
+            default_headers.insert(USER_AGENT, value);
+        }
+
+        #[allow(unused_mut)]
+        let mut builder = Client::builder();
+        #[cfg(not(target_family = "wasm"))]
+        {
+            if let Some(timeout) = self.timeout {
+                builder = builder.timeout(timeout);
+            }
+            if let Some(timeout) = self.connect_timeout {
+                builder = builder.connect_timeout(timeout);
+            }
+            if let Some(timeout) = self.pool_idle_timeout {
+                builder = builder.pool_idle_timeout(timeout);
+            }
+            if let Some(proxy) = &self.proxy {
+                builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+            }
+            for pem in &self.root_certificates {
+                builder = builder.add_root_certificate(reqwest::Certificate::from_pem(pem)?);
+            }
+        }
+
+        let mut transport = Transport::from_client(builder.build()?);
+        transport.default_headers = default_headers;
+        transport.retry_policy = self.retry_policy;
+        Ok(transport)
+    }
+}
+
+/// Canned auth API, DDS and domain server responses for the mock executors of unit tests.
+#[cfg(not(target_family = "wasm"))]
+#[cfg(test)]
+pub(crate) mod test_support {
+    use posemesh_utils::now_unix_secs;
+    use reqwest::{Request, Response};
+
+    /// An unsigned JWT expiring at `exp`; the clients only read its expiry.
+    pub(crate) fn jwt_expiring_at(exp: u64) -> String {
+        use base64::Engine as _;
+        let encode = |s: String| base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(s);
+        format!(
+            "{}.{}.sig",
+            encode(r#"{"alg":"HS256"}"#.into()),
+            encode(format!(r#"{{"exp":{exp}}}"#))
+        )
+    }
+
+    /// An unsigned JWT valid for an hour.
+    pub(crate) fn jwt() -> String {
+        jwt_expiring_at(now_unix_secs() + 3600)
+    }
+
+    /// Body of `/service/domains-access-token` on the auth API.
+    pub(crate) fn dds_token() -> serde_json::Value {
+        serde_json::json!({ "access_token": jwt() })
+    }
+
+    /// Body of `/api/v1/domains/{id}/auth` on DDS, for domain `id` on `http://{server}`.
+    pub(crate) fn domain_auth(id: &str, server: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": id, "name": id, "organization_id": "o", "domain_server_id": server,
+            "redirect_url": null, "access_token": jwt(),
+            "domain_server": {
+                "id": server, "organization_id": "o", "name": server,
+                "url": format!("http://{server}"),
+            },
+        })
+    }
+
+    /// The body of `request`, read to the end when it is streamed. The stream must not wait on
+    /// the test itself, as in-memory uploads don't.
+    pub(crate) fn body_bytes(request: &mut Request) -> Vec<u8> {
+        use http_body_util::BodyExt;
+        match request.body_mut().take() {
+            Some(body) => futures::executor::block_on(body.collect())
+                .unwrap()
+                .to_bytes()
+                .to_vec(),
+            None => Vec::new(),
+        }
+    }
+
+    pub(crate) fn json_response(status: u16, body: &serde_json::Value) -> Response {
+        Response::from(
+            http::Response::builder()
+                .status(status)
+                .header("content-type", "application/json")
+                .body(body.to_string())
+                .unwrap(),
+        )
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Recorder {
+        seen: Arc<Mutex<Vec<Request>>>,
+    }
+
+    impl HttpExecutor for Recorder {
+        fn execute(&self, request: Request) -> ExecuteFuture {
+            self.seen.lock().unwrap().push(request);
+            Box::pin(async {
+                Ok(Response::from(
+                    http::Response::builder().status(204).body("").unwrap(),
+                ))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn executor_receives_default_headers() {
+        let recorder = Recorder::default();
+        let seen = recorder.seen.clone();
+        let mut transport = Transport::with_executor(recorder);
+        transport.default_headers = Transport::builder()
+            .default_header("x-extra", "1")
+            .user_agent("posemesh-test")
+            .build()
+            .unwrap()
+            .default_headers;
+
+        let res = transport
+            .get("http://domain.invalid/api/v1/info")
+            .send_via(&transport)
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 204);
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 1);
+        assert_eq!(seen[0].headers()["x-extra"], "1");
+        assert_eq!(seen[0].headers()[USER_AGENT], "posemesh-test");
+    }
+
+    struct Counting {
+        inner: Client,
+        calls: Arc<Mutex<u32>>,
+    }
+
+    impl HttpExecutor for Counting {
+        fn execute(&self, request: Request) -> ExecuteFuture {
+            *self.calls.lock().unwrap() += 1;
+            HttpExecutor::execute(&self.inner, request)
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_only_idempotent_requests_on_connect_errors() {
+        // Bind then drop to get a local port that refuses connections.
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let url = format!("http://127.0.0.1:{port}/");
+        let calls = Arc::new(Mutex::new(0));
+        let transport = Transport::with_executor(Counting {
+            inner: Client::new(),
+            calls: calls.clone(),
+        })
+        .with_retry_policy(fast_retries(2));
+
+        assert!(transport.get(&url).send_via(&transport).await.is_err());
+        assert_eq!(*calls.lock().unwrap(), 3);
+
+        *calls.lock().unwrap() = 0;
+        assert!(transport.post(&url).send_via(&transport).await.is_err());
+        assert_eq!(*calls.lock().unwrap(), 1);
+    }
+
+    fn fast_retries(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+            ..RetryPolicy::default()
+        }
+    }
+
+    /// Answers with the queued statuses in order, then 200.
+    struct Scripted {
+        statuses: Mutex<Vec<(u16, Option<&'static str>)>>,
+        calls: Arc<Mutex<u32>>,
+    }
+
+    impl Scripted {
+        fn new(statuses: Vec<(u16, Option<&'static str>)>) -> (Self, Arc<Mutex<u32>>) {
+            let calls = Arc::new(Mutex::new(0));
+            let executor = Self {
+                statuses: Mutex::new(statuses),
+                calls: calls.clone(),
+            };
+            (executor, calls)
+        }
+    }
+
+    impl HttpExecutor for Scripted {
+        fn execute(&self, _request: Request) -> ExecuteFuture {
+            *self.calls.lock().unwrap() += 1;
+            let mut statuses = self.statuses.lock().unwrap();
+            let (status, retry_after) = if statuses.is_empty() {
+                (200, None)
+            } else {
+                statuses.remove(0)
+            };
+            let mut res = http::Response::builder().status(status);
+            if let Some(retry_after) = retry_after {
+                res = res.header("retry-after", retry_after);
+            }
+            let res = Response::from(res.body("").unwrap());
+            Box::pin(async move { Ok(res) })
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_rate_limited_get_and_honours_retry_after() {
+        let (executor, calls) = Scripted::new(vec![(429, Some("0")), (503, None)]);
+        let transport = Transport::with_executor(executor).with_retry_policy(fast_retries(3));
+
+        let res = transport
+            .get("http://domain.invalid/api/v1/info")
+            .send_via(&transport)
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+        assert_eq!(*calls.lock().unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_post_or_exhausted_or_long_retry_after() {
+        let (executor, calls) = Scripted::new(vec![(503, None)]);
+        let transport = Transport::with_executor(executor).with_retry_policy(fast_retries(3));
+        let res = transport
+            .post("http://domain.invalid/api/v1/domains/d/data")
+            .send_via(&transport)
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 503);
+        assert_eq!(*calls.lock().unwrap(), 1);
+
+        let (executor, calls) = Scripted::new(vec![(502, None), (502, None), (502, None)]);
+        let transport = Transport::with_executor(executor).with_retry_policy(fast_retries(1));
+        let res = transport
+            .delete("http://domain.invalid/api/v1/domains/d/data/1")
+            .send_via(&transport)
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 502);
+        assert_eq!(*calls.lock().unwrap(), 2);
+
+        let (executor, calls) = Scripted::new(vec![(429, Some("3600"))]);
+        let transport = Transport::with_executor(executor).with_retry_policy(fast_retries(3));
+        let res = transport
+            .get("http://domain.invalid/api/v1/info")
+            .send_via(&transport)
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 429);
+        assert_eq!(*calls.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn build_rejects_invalid_header() {
+        let err = Transport::builder()
+            .default_header("bad header", "x")
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, DomainError::InvalidRequest(_)));
+    }
+}
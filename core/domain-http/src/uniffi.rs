@@ -1,12 +1,330 @@
 use crate::domain_client::DomainClient as r_DomainClient;
 use crate::domain_client::ListDomainsQuery;
 use crate::{
-    discovery::{DomainWithServer, ListDomainsResponse},
-    domain_data::{DomainData, DomainDataMetadata, DownloadQuery, UploadDomainData},
-    errors::DomainError,
+    bulk_delete::{DeleteOptions, DeleteReport},
+    dedup::UploadReport,
+    discovery::{DomainServer, DomainWithServer, ListDomainsResponse, Portal, UpdateDomainRequest},
+    domain_data::{DomainAction, DomainData, DomainDataMetadata, DownloadQuery, UploadDomainData},
+    errors::{AuthError, DomainError},
+    listing::{ListQuery, MetadataPage},
+    oidc::oidc_token_provider,
+    portals::{PlacePortalRequest, PortalPlacement},
+    progress::{CancellationToken, TransferControl, TransferEvent},
+    reconstruction::{Job, JobRequest},
+    token_refresh::TokenRefreshOptions,
+    token_store::EncryptedFileTokenStore,
+    upload_queue::{FileQueueStore, QueuedUpload, UploadQueue as r_UploadQueue},
+    watch::{DomainDataEvent, WatchOptions},
 };
+use bytes::Bytes;
+use futures::channel::mpsc;
+use futures::stream::{self, BoxStream};
+use futures::{SinkExt, StreamExt};
 use posemesh_utils::get_runtime;
-use std::sync::Arc;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+
+/// Receives changes from [`DomainClient::watch_domain_data`] on a background thread.
+pub trait DomainDataWatcher: Send + Sync {
+    fn on_event(&self, event: DomainDataEvent);
+    fn on_error(&self, message: String);
+}
+
+/// Supplies OIDC access tokens to [`DomainClient::with_oidc_token_provider`]. Called on a
+/// blocking thread; returning `None` fails the request that needed the token.
+pub trait OidcTokenProvider: Send + Sync {
+    fn get_token(&self) -> Option<String>;
+}
+
+/// Receives progress of [`DomainClient::upload_domain_data_with_progress`] and
+/// [`DomainClient::download_domain_data_with_progress`].
+pub trait TransferListener: Send + Sync {
+    fn on_event(&self, event: TransferEvent);
+}
+
+fn transfer_control(
+    listener: Box<dyn TransferListener>,
+    cancel: Option<Arc<CancellationToken>>,
+) -> TransferControl {
+    let listener: Arc<dyn TransferListener> = listener.into();
+    let transfer = TransferControl::new().on_event(move |event| listener.on_event(event));
+    match cancel {
+        Some(cancel) => transfer.with_cancellation(cancel.as_ref().clone()),
+        None => transfer,
+    }
+}
+
+/// Receives job updates from [`DomainClient::wait_for_job`].
+pub trait JobProgressListener: Send + Sync {
+    fn on_progress(&self, job: Job);
+}
+
+/// Stops a running watch when cancelled or dropped.
+#[derive(Debug)]
+pub struct WatchHandle(tokio::task::AbortHandle);
+
+impl WatchHandle {
+    pub fn cancel(&self) {
+        self.0.abort();
+    }
+}
+
+impl Drop for WatchHandle {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Items of a download started with [`DomainClient::download_domain_data_stream`], pulled one at
+/// a time so only the current item is held in memory.
+#[derive(Debug)]
+pub struct DomainDataDownload {
+    items: Mutex<mpsc::Receiver<Result<DomainData, DomainError>>>,
+    cancel: CancellationToken,
+}
+
+impl DomainDataDownload {
+    /// Blocks until the next item arrives. Returns `None` once the download is complete.
+    pub fn next(&self) -> Result<Option<DomainData>, DomainError> {
+        let mut items = self.items.lock().unwrap();
+        get_runtime().block_on(items.next()).transpose()
+    }
+
+    /// Stops the download; a pending or later `next` fails with [`DomainError::Cancelled`].
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+}
+
+impl Drop for DomainDataDownload {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+    }
+}
+
+type UploadTask = tokio::task::JoinHandle<Result<Vec<DomainDataMetadata>, DomainError>>;
+
+/// Size of the chunks a file written to [`DomainDataUpload`] is read in.
+const FILE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// What a [`DomainDataUpload`] hands to its upload task.
+enum UploadEntry {
+    Item(UploadDomainData),
+    Stream {
+        action: DomainAction,
+        size: Option<u64>,
+        chunks: BoxStream<'static, Result<Bytes, DomainError>>,
+    },
+}
+
+/// Upload started with [`DomainClient::upload_domain_data_stream`]. Items are sent while
+/// later ones are still being written, and a full buffer blocks `write` until the server catches
+/// up. Files and chunked items are streamed as multipart uploads, so none of them is held in
+/// memory whole. Dropping it without calling `finish` cancels the upload.
+#[derive(Debug)]
+pub struct DomainDataUpload {
+    entries: Mutex<Option<mpsc::Sender<UploadEntry>>>,
+    // Chunks of the item begun last with `begin_item`.
+    chunks: Mutex<Option<mpsc::Sender<Bytes>>>,
+    task: Mutex<Option<UploadTask>>,
+}
+
+impl DomainDataUpload {
+    pub fn write(&self, data: UploadDomainData) -> Result<(), DomainError> {
+        self.send(UploadEntry::Item(data))
+    }
+
+    /// Streams the file at `path` as one item with `action`.
+    pub fn write_file(&self, path: &str, action: DomainAction) -> Result<(), DomainError> {
+        let file = std::fs::File::open(path)?;
+        let size = file.metadata()?.len();
+        let chunks = stream::try_unfold(tokio::fs::File::from_std(file), |mut file| async move {
+            let mut chunk = vec![0; FILE_CHUNK_SIZE];
+            let read = file.read(&mut chunk).await?;
+            if read == 0 {
+                return Ok(None);
+            }
+            chunk.truncate(read);
+            Ok(Some((Bytes::from(chunk), file)))
+        });
+        self.send(UploadEntry::Stream {
+            action,
+            size: Some(size),
+            chunks: chunks.boxed(),
+        })
+    }
+
+    /// Starts an item whose data follows in `write_chunk` calls. Writing anything else, or
+    /// calling `finish`, ends it.
+    pub fn begin_item(&self, action: DomainAction, size: Option<u64>) -> Result<(), DomainError> {
+        let (tx, rx) = mpsc::channel(2);
+        self.send(UploadEntry::Stream {
+            action,
+            size,
+            chunks: rx.map(Ok).boxed(),
+        })?;
+        *self.chunks.lock().unwrap() = Some(tx);
+        Ok(())
+    }
+
+    /// Appends `chunk` to the item begun last with `begin_item`.
+    pub fn write_chunk(&self, chunk: Vec<u8>) -> Result<(), DomainError> {
+        let mut chunks = self
+            .chunks
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or(DomainError::InvalidRequest("no item begun"))?;
+        if get_runtime()
+            .block_on(chunks.send(Bytes::from(chunk)))
+            .is_err()
+        {
+            return Err(self.stopped());
+        }
+        Ok(())
+    }
+
+    /// Waits for everything written so far to be uploaded.
+    pub fn finish(&self) -> Result<Vec<DomainDataMetadata>, DomainError> {
+        self.chunks.lock().unwrap().take();
+        self.entries.lock().unwrap().take();
+        let task = self
+            .task
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or(DomainError::InvalidRequest("upload already finished"))?;
+        get_runtime()
+            .block_on(task)
+            .unwrap_or(Err(DomainError::Cancelled))
+    }
+
+    fn send(&self, entry: UploadEntry) -> Result<(), DomainError> {
+        self.chunks.lock().unwrap().take();
+        let mut entries = self
+            .entries
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or(DomainError::InvalidRequest("upload already finished"))?;
+        if get_runtime().block_on(entries.send(entry)).is_err() {
+            return Err(self.stopped());
+        }
+        Ok(())
+    }
+
+    /// The reason the upload stopped early, taken from its result.
+    fn stopped(&self) -> DomainError {
+        self.finish()
+            .err()
+            .unwrap_or(DomainError::InvalidRequest("upload already finished"))
+    }
+}
+
+impl Drop for DomainDataUpload {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.get_mut().unwrap().take() {
+            task.abort();
+        }
+    }
+}
+
+/// Uploads the entries of a [`DomainDataUpload`] in order. Runs of whole items go up together as
+/// with [`r_DomainClient::upload_domain_data_stream`]; files and chunked items go up one by one
+/// with [`r_DomainClient::upload_domain_data_from_stream`].
+async fn upload_entries(
+    client: r_DomainClient,
+    domain_id: String,
+    entries: mpsc::Receiver<UploadEntry>,
+) -> Result<Vec<DomainDataMetadata>, DomainError> {
+    let mut entries = entries.peekable();
+    let mut uploaded = Vec::new();
+    while let Some(entry) = entries.next().await {
+        match entry {
+            UploadEntry::Item(data) => {
+                let (mut items, rx) = mpsc::channel(2);
+                let feed = async {
+                    let mut next = Some(data);
+                    while let Some(data) = next {
+                        if items.send(data).await.is_err() {
+                            break;
+                        }
+                        next = match Pin::new(&mut entries)
+                            .next_if(|entry| matches!(entry, UploadEntry::Item(_)))
+                            .await
+                        {
+                            Some(UploadEntry::Item(data)) => Some(data),
+                            _ => None,
+                        };
+                    }
+                    // `items` outlives the feed, so close it to let the upload finish.
+                    items.close_channel();
+                };
+                let ((), result) =
+                    futures::join!(feed, client.upload_domain_data_stream(&domain_id, rx));
+                uploaded.extend(result?);
+            }
+            UploadEntry::Stream {
+                action,
+                size,
+                chunks,
+            } => {
+                let metadata = client
+                    .upload_domain_data_from_stream(
+                        &domain_id,
+                        action,
+                        size,
+                        chunks,
+                        &TransferControl::new(),
+                    )
+                    .await?;
+                uploaded.push(metadata);
+            }
+        }
+    }
+    Ok(uploaded)
+}
+
+/// Uploads kept in files under a directory until they are flushed, so they survive restarts and
+/// connectivity loss.
+#[derive(Debug)]
+pub struct UploadQueue(r_UploadQueue);
+
+impl UploadQueue {
+    pub fn new(directory: String) -> Self {
+        Self(r_UploadQueue::new(Arc::new(FileQueueStore::new(directory))))
+    }
+
+    pub fn enqueue(
+        &self,
+        domain_id: &str,
+        data: UploadDomainData,
+    ) -> Result<QueuedUpload, DomainError> {
+        get_runtime().block_on(self.0.enqueue(domain_id, data))
+    }
+
+    pub fn items(&self) -> Result<Vec<QueuedUpload>, DomainError> {
+        get_runtime().block_on(self.0.items())
+    }
+
+    pub fn flush(&self, client: Arc<DomainClient>) -> Result<Vec<QueuedUpload>, DomainError> {
+        get_runtime().block_on(self.0.flush(&client.0))
+    }
+
+    pub fn retry_failed(&self) -> Result<u32, DomainError> {
+        get_runtime().block_on(self.0.retry_failed())
+    }
+
+    pub fn remove(&self, id: &str) -> Result<bool, DomainError> {
+        get_runtime().block_on(self.0.remove(id))
+    }
+
+    pub fn clear_done(&self) -> Result<u32, DomainError> {
+        get_runtime().block_on(self.0.clear_done())
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct DomainClient(r_DomainClient);
@@ -49,6 +367,34 @@ pub fn new_with_user_credential(
     })
 }
 
+fn session_file_store(
+    path: &str,
+    key: Vec<u8>,
+) -> Result<Arc<EncryptedFileTokenStore>, DomainError> {
+    let key: [u8; 32] = key
+        .try_into()
+        .map_err(|_| DomainError::InvalidRequest("Session key must be 32 bytes"))?;
+    Ok(Arc::new(EncryptedFileTokenStore::new(path, &key)))
+}
+
+pub fn restore_session(
+    api_url: &str,
+    dds_url: &str,
+    client_id: &str,
+    session_file: &str,
+    key: Vec<u8>,
+) -> Result<Option<Arc<DomainClient>>, DomainError> {
+    let store = session_file_store(session_file, key)?;
+    get_runtime().block_on(async move {
+        let mut dc = r_DomainClient::new(api_url, dds_url, client_id);
+        dc.set_token_store(store).await;
+        Ok(dc
+            .restore_session()
+            .await?
+            .then(|| Arc::new(DomainClient(dc))))
+    })
+}
+
 impl DomainClient {
     pub fn new(api_url: &str, dds_url: &str, client_id: &str) -> Self {
         Self(r_DomainClient::new(api_url, dds_url, client_id))
@@ -59,6 +405,54 @@ impl DomainClient {
         Arc::new(DomainClient(dc))
     }
 
+    pub fn with_oidc_token_provider(&self, provider: Box<dyn OidcTokenProvider>) -> Arc<Self> {
+        let provider: Arc<dyn OidcTokenProvider> = provider.into();
+        let dc = self
+            .0
+            .with_oidc_token_provider(oidc_token_provider(move || {
+                let provider = provider.clone();
+                async move {
+                    tokio::task::spawn_blocking(move || provider.get_token())
+                        .await
+                        .ok()
+                        .flatten()
+                        .ok_or_else(|| {
+                            AuthError::TokenProvider("the provider returned no token".into()).into()
+                        })
+                }
+            }));
+        Arc::new(DomainClient(dc))
+    }
+
+    pub fn with_session_file(&self, path: &str, key: Vec<u8>) -> Result<Arc<Self>, DomainError> {
+        let store = session_file_store(path, key)?;
+        let mut dc = self.0.clone();
+        get_runtime().block_on(dc.set_token_store(store));
+        Ok(Arc::new(DomainClient(dc)))
+    }
+
+    pub fn logout(&self) -> Result<(), DomainError> {
+        get_runtime().block_on(self.0.clone().logout())
+    }
+
+    pub fn start_token_refresh(
+        &self,
+        refresh_ahead_secs: u64,
+        max_domains: u32,
+        idle_timeout_secs: u64,
+    ) {
+        let options = TokenRefreshOptions {
+            refresh_ahead: Duration::from_secs(refresh_ahead_secs),
+            max_domains: max_domains as usize,
+            idle_timeout: Duration::from_secs(idle_timeout_secs),
+        };
+        get_runtime().block_on(self.0.start_token_refresh(options));
+    }
+
+    pub fn stop_token_refresh(&self) {
+        self.0.stop_token_refresh();
+    }
+
     pub fn download_domain_data(
         &self,
         domain_id: &str,
@@ -67,6 +461,173 @@ impl DomainClient {
         get_runtime().block_on(async move { self.0.download_domain_data(domain_id, query).await })
     }
 
+    pub fn download_domain_data_with_progress(
+        &self,
+        domain_id: &str,
+        query: &DownloadQuery,
+        listener: Box<dyn TransferListener>,
+        cancel: Option<Arc<CancellationToken>>,
+    ) -> Result<Vec<DomainData>, DomainError> {
+        let transfer = transfer_control(listener, cancel);
+        get_runtime().block_on(async move {
+            self.0
+                .download_domain_data_with_progress(domain_id, query, &transfer)
+                .await
+        })
+    }
+
+    pub fn download_domain_data_stream(
+        &self,
+        domain_id: &str,
+        query: &DownloadQuery,
+    ) -> Result<Arc<DomainDataDownload>, DomainError> {
+        let cancel = CancellationToken::new();
+        let transfer = TransferControl::new().with_cancellation(cancel.clone());
+        let items = get_runtime().block_on(async move {
+            self.0
+                .download_domain_data_stream_with_progress(domain_id, query, &transfer)
+                .await
+        })?;
+        Ok(Arc::new(DomainDataDownload {
+            items: Mutex::new(items),
+            cancel,
+        }))
+    }
+
+    pub fn download_domain_data_metadata(
+        &self,
+        domain_id: &str,
+        query: &DownloadQuery,
+    ) -> Result<Vec<DomainDataMetadata>, DomainError> {
+        get_runtime().block_on(async move { self.0.download_metadata(domain_id, query).await })
+    }
+
+    pub fn download_domain_data_by_id(
+        &self,
+        domain_id: &str,
+        id: &str,
+    ) -> Result<Vec<u8>, DomainError> {
+        get_runtime()
+            .block_on(async move { self.0.download_domain_data_by_id(domain_id, id).await })
+    }
+
+    pub fn delete_domain_data_by_id(&self, domain_id: &str, id: &str) -> Result<(), DomainError> {
+        get_runtime().block_on(async move { self.0.delete_domain_data_by_id(domain_id, id).await })
+    }
+
+    pub fn delete_domain_data(
+        &self,
+        domain_id: &str,
+        ids: Vec<String>,
+        dry_run: bool,
+    ) -> Result<DeleteReport, DomainError> {
+        let options = DeleteOptions {
+            dry_run,
+            ..Default::default()
+        };
+        get_runtime()
+            .block_on(async move { self.0.delete_domain_data(domain_id, &ids, &options).await })
+    }
+
+    pub fn delete_domain_data_by_query(
+        &self,
+        domain_id: &str,
+        query: &DownloadQuery,
+        dry_run: bool,
+    ) -> Result<DeleteReport, DomainError> {
+        let options = DeleteOptions {
+            dry_run,
+            ..Default::default()
+        };
+        get_runtime().block_on(async move {
+            self.0
+                .delete_domain_data_by_query(domain_id, query, &options)
+                .await
+        })
+    }
+
+    pub fn list_domain_data_metadata_page(
+        &self,
+        domain_id: &str,
+        query: &ListQuery,
+    ) -> Result<MetadataPage, DomainError> {
+        get_runtime().block_on(async move { self.0.list_metadata_page(domain_id, query).await })
+    }
+
+    pub fn watch_domain_data(
+        &self,
+        domain_id: &str,
+        query: ListQuery,
+        poll_interval_ms: u64,
+        watcher: Box<dyn DomainDataWatcher>,
+    ) -> Arc<WatchHandle> {
+        let client = self.0.clone();
+        let domain_id = domain_id.to_string();
+        let options = WatchOptions {
+            poll_interval: Duration::from_millis(poll_interval_ms),
+            ..Default::default()
+        };
+        let task = get_runtime().spawn(async move {
+            let events = client.watch_domain_data(&domain_id, query, options);
+            futures::pin_mut!(events);
+            while let Some(event) = events.next().await {
+                match event {
+                    Ok(event) => watcher.on_event(event),
+                    Err(e) => watcher.on_error(e.to_string()),
+                }
+            }
+        });
+        Arc::new(WatchHandle(task.abort_handle()))
+    }
+
+    pub fn submit_job_request(
+        &self,
+        domain_id: &str,
+        request: &JobRequest,
+    ) -> Result<Job, DomainError> {
+        get_runtime()
+            .block_on(async move { self.0.submit_job_request_v1(domain_id, request).await })
+    }
+
+    pub fn get_job(&self, domain_id: &str, job_id: &str) -> Result<Job, DomainError> {
+        get_runtime().block_on(async move { self.0.get_job(domain_id, job_id).await })
+    }
+
+    pub fn list_jobs(&self, domain_id: &str) -> Result<Vec<Job>, DomainError> {
+        get_runtime().block_on(async move { self.0.list_jobs(domain_id).await })
+    }
+
+    pub fn cancel_job(&self, domain_id: &str, job_id: &str) -> Result<(), DomainError> {
+        get_runtime().block_on(async move { self.0.cancel_job(domain_id, job_id).await })
+    }
+
+    pub fn get_job_results(
+        &self,
+        domain_id: &str,
+        job_id: &str,
+    ) -> Result<Vec<String>, DomainError> {
+        get_runtime().block_on(async move { self.0.get_job_results(domain_id, job_id).await })
+    }
+
+    pub fn wait_for_job(
+        &self,
+        domain_id: &str,
+        job_id: &str,
+        poll_interval_ms: u64,
+        listener: Box<dyn JobProgressListener>,
+    ) -> Result<Job, DomainError> {
+        get_runtime().block_on(async move {
+            self.0
+                .wait_for_job(
+                    domain_id,
+                    job_id,
+                    Duration::from_millis(poll_interval_ms),
+                    |job| listener.on_progress(job.clone()),
+                )
+                .await
+        })
+    }
+
     pub fn upload_domain_data(
         &self,
         domain_id: &str,
@@ -77,6 +638,41 @@ impl DomainClient {
         Ok(res)
     }
 
+    pub fn upload_domain_data_dedup(
+        &self,
+        domain_id: &str,
+        data: Vec<UploadDomainData>,
+    ) -> Result<UploadReport, DomainError> {
+        get_runtime()
+            .block_on(async move { self.0.upload_domain_data_dedup(domain_id, data).await })
+    }
+
+    pub fn upload_domain_data_stream(&self, domain_id: &str) -> Arc<DomainDataUpload> {
+        // Small buffer so a fast writer cannot queue up much more than the server accepts.
+        let (tx, rx) = mpsc::channel(2);
+        let task = get_runtime().spawn(upload_entries(self.0.clone(), domain_id.to_string(), rx));
+        Arc::new(DomainDataUpload {
+            entries: Mutex::new(Some(tx)),
+            chunks: Mutex::new(None),
+            task: Mutex::new(Some(task)),
+        })
+    }
+
+    pub fn upload_domain_data_with_progress(
+        &self,
+        domain_id: &str,
+        data: Vec<UploadDomainData>,
+        listener: Box<dyn TransferListener>,
+        cancel: Option<Arc<CancellationToken>>,
+    ) -> Result<Vec<DomainDataMetadata>, DomainError> {
+        let transfer = transfer_control(listener, cancel);
+        get_runtime().block_on(async move {
+            self.0
+                .upload_domain_data_with_progress(domain_id, data, &transfer)
+                .await
+        })
+    }
+
     pub fn create_domain(
         &self,
         name: &str,
@@ -94,6 +690,63 @@ impl DomainClient {
         Ok(res)
     }
 
+    pub fn get_domain(&self, domain_id: &str) -> Result<DomainWithServer, DomainError> {
+        get_runtime().block_on(self.0.get_domain(domain_id))
+    }
+
+    pub fn update_domain(
+        &self,
+        domain_id: &str,
+        update: UpdateDomainRequest,
+    ) -> Result<DomainWithServer, DomainError> {
+        get_runtime().block_on(self.0.update_domain(domain_id, &update))
+    }
+
+    pub fn move_domain(
+        &self,
+        domain_id: &str,
+        domain_server_id: &str,
+    ) -> Result<DomainWithServer, DomainError> {
+        get_runtime().block_on(self.0.move_domain(domain_id, domain_server_id))
+    }
+
+    pub fn list_domain_servers(&self, org: &str) -> Result<Vec<DomainServer>, DomainError> {
+        get_runtime().block_on(self.0.list_domain_servers(org))
+    }
+
+    pub fn get_domain_server(&self, domain_server_id: &str) -> Result<DomainServer, DomainError> {
+        get_runtime().block_on(self.0.get_domain_server(domain_server_id))
+    }
+
+    pub fn list_domain_portals(&self, domain_id: &str) -> Result<Vec<Portal>, DomainError> {
+        get_runtime().block_on(self.0.list_domain_portals(domain_id))
+    }
+
+    pub fn list_portals(&self, domain_id: &str) -> Result<Vec<PortalPlacement>, DomainError> {
+        get_runtime().block_on(self.0.list_portals(domain_id))
+    }
+
+    pub fn get_portal(
+        &self,
+        domain_id: &str,
+        short_id: &str,
+    ) -> Result<PortalPlacement, DomainError> {
+        get_runtime().block_on(self.0.get_portal(domain_id, short_id))
+    }
+
+    pub fn place_portal(
+        &self,
+        domain_id: &str,
+        short_id: &str,
+        request: PlacePortalRequest,
+    ) -> Result<PortalPlacement, DomainError> {
+        get_runtime().block_on(self.0.place_portal(domain_id, short_id, &request))
+    }
+
+    pub fn remove_portal(&self, domain_id: &str, short_id: &str) -> Result<(), DomainError> {
+        get_runtime().block_on(self.0.remove_portal(domain_id, short_id))
+    }
+
     pub fn delete_domain(&self, domain_id: &str) -> Result<(), DomainError> {
         let res = get_runtime().block_on(async move { self.0.delete_domain(domain_id).await })?;
         Ok(res)
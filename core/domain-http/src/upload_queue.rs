@@ -0,0 +1,592 @@
+//! Durable queue for uploads made while offline.
+//!
+//! [`UploadQueue::enqueue`] writes the item to a [`QueueStore`] and returns at once;
+//! [`UploadQueue::flush`] later sends everything pending through
+//! [`DomainClient::upload_domain_data`], so retries and multipart uploads apply as usual. The
+//! store keeps the state of every item, so nothing is lost when the app is killed in between.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+
+use futures::lock::Mutex;
+use posemesh_utils::now_unix_secs;
+use serde::{Deserialize, Serialize};
+
+use crate::domain_client::DomainClient;
+use crate::domain_data::{DomainAction, DomainDataMetadata, UploadDomainData};
+use crate::errors::DomainError;
+
+const INDEX_KEY: &str = "upload-queue.json";
+
+fn data_key(id: &str) -> String {
+    format!("upload-{id}.bin")
+}
+
+#[cfg(not(target_family = "wasm"))]
+pub type QueueFuture<'a, T> = futures::future::BoxFuture<'a, io::Result<T>>;
+#[cfg(target_family = "wasm")]
+pub type QueueFuture<'a, T> = futures::future::LocalBoxFuture<'a, io::Result<T>>;
+
+/// Byte storage behind an [`UploadQueue`]. Keys are short ASCII names without path separators.
+#[cfg(not(target_family = "wasm"))]
+pub trait QueueStore: Send + Sync {
+    fn get<'a>(&'a self, key: &'a str) -> QueueFuture<'a, Option<Vec<u8>>>;
+    fn set<'a>(&'a self, key: &'a str, value: &'a [u8]) -> QueueFuture<'a, ()>;
+    fn remove<'a>(&'a self, key: &'a str) -> QueueFuture<'a, ()>;
+}
+
+/// Byte storage behind an [`UploadQueue`]. Keys are short ASCII names without path separators.
+#[cfg(target_family = "wasm")]
+pub trait QueueStore {
+    fn get<'a>(&'a self, key: &'a str) -> QueueFuture<'a, Option<Vec<u8>>>;
+    fn set<'a>(&'a self, key: &'a str, value: &'a [u8]) -> QueueFuture<'a, ()>;
+    fn remove<'a>(&'a self, key: &'a str) -> QueueFuture<'a, ()>;
+}
+
+impl fmt::Debug for dyn QueueStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("QueueStore")
+    }
+}
+
+/// Keeps the queue for the lifetime of the process only.
+#[derive(Debug, Default)]
+pub struct MemoryQueueStore(std::sync::Mutex<HashMap<String, Vec<u8>>>);
+
+impl QueueStore for MemoryQueueStore {
+    fn get<'a>(&'a self, key: &'a str) -> QueueFuture<'a, Option<Vec<u8>>> {
+        let value = self.0.lock().unwrap().get(key).cloned();
+        Box::pin(async move { Ok(value) })
+    }
+
+    fn set<'a>(&'a self, key: &'a str, value: &'a [u8]) -> QueueFuture<'a, ()> {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), value.to_vec());
+        Box::pin(async { Ok(()) })
+    }
+
+    fn remove<'a>(&'a self, key: &'a str) -> QueueFuture<'a, ()> {
+        self.0.lock().unwrap().remove(key);
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+pub use file::FileQueueStore;
+
+#[cfg(not(target_family = "wasm"))]
+mod file {
+    use std::io;
+    use std::path::PathBuf;
+
+    use super::{QueueFuture, QueueStore};
+
+    /// Keeps every key in its own file under a directory, created on first write.
+    #[derive(Debug, Clone)]
+    pub struct FileQueueStore {
+        dir: PathBuf,
+    }
+
+    impl FileQueueStore {
+        pub fn new(dir: impl Into<PathBuf>) -> Self {
+            Self { dir: dir.into() }
+        }
+
+        async fn write(&self, key: &str, value: &[u8]) -> io::Result<()> {
+            tokio::fs::create_dir_all(&self.dir).await?;
+            let path = self.dir.join(key);
+            let tmp = self.dir.join(format!("{key}.tmp"));
+            tokio::fs::write(&tmp, value).await?;
+            tokio::fs::rename(&tmp, &path).await
+        }
+    }
+
+    impl QueueStore for FileQueueStore {
+        fn get<'a>(&'a self, key: &'a str) -> QueueFuture<'a, Option<Vec<u8>>> {
+            Box::pin(async move {
+                match tokio::fs::read(self.dir.join(key)).await {
+                    Ok(bytes) => Ok(Some(bytes)),
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+                    Err(e) => Err(e),
+                }
+            })
+        }
+
+        fn set<'a>(&'a self, key: &'a str, value: &'a [u8]) -> QueueFuture<'a, ()> {
+            Box::pin(self.write(key, value))
+        }
+
+        fn remove<'a>(&'a self, key: &'a str) -> QueueFuture<'a, ()> {
+            Box::pin(async move {
+                match tokio::fs::remove_file(self.dir.join(key)).await {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+                    _ => Ok(()),
+                }
+            })
+        }
+    }
+}
+
+#[cfg(target_family = "wasm")]
+pub use js::JsQueueStore;
+
+#[cfg(target_family = "wasm")]
+mod js {
+    use std::io;
+
+    use wasm_bindgen::prelude::*;
+    use wasm_bindgen::{JsCast, JsValue};
+    use wasm_bindgen_futures::JsFuture;
+    use wasm_bindgen_futures::js_sys::{self, Function, Promise, Reflect, Uint8Array};
+
+    use super::{QueueFuture, QueueStore};
+
+    #[wasm_bindgen(inline_js = r#"
+export function openIndexedDbStore(name) {
+    const db = new Promise((resolve, reject) => {
+        const request = indexedDB.open(name, 1);
+        request.onupgradeneeded = () => request.result.createObjectStore("items");
+        request.onsuccess = () => resolve(request.result);
+        request.onerror = () => reject(request.error);
+    });
+    const run = (mode, op) => db.then((db) => new Promise((resolve, reject) => {
+        const tx = db.transaction("items", mode);
+        const request = op(tx.objectStore("items"));
+        tx.oncomplete = () => resolve(request.result ?? null);
+        tx.onerror = () => reject(tx.error);
+        tx.onabort = () => reject(tx.error);
+    }));
+    return {
+        getItem: (key) => run("readonly", (store) => store.get(key)),
+        setItem: (key, value) => run("readwrite", (store) => store.put(value, key)),
+        removeItem: (key) => run("readwrite", (store) => store.delete(key)),
+    };
+}
+"#)]
+    extern "C" {
+        #[wasm_bindgen(js_name = "openIndexedDbStore")]
+        fn open_indexed_db_store(name: &str) -> JsValue;
+    }
+
+    /// Adapts a JS storage object with `getItem(key)`, `setItem(key, value)` and
+    /// `removeItem(key)`, returning values or Promises. Values are `Uint8Array`s, so the storage
+    /// must hold binary data: IndexedDB or a wrapper such as localForage, not `localStorage`.
+    pub struct JsQueueStore {
+        storage: JsValue,
+    }
+
+    impl JsQueueStore {
+        pub fn new(storage: JsValue) -> Self {
+            Self { storage }
+        }
+
+        /// Uses an object store in the IndexedDB database `name`, created when missing.
+        pub fn indexed_db(name: &str) -> Result<Self, io::Error> {
+            let indexed_db = Reflect::get(&js_sys::global(), &JsValue::from_str("indexedDB"))
+                .map_err(js_error)?;
+            if indexed_db.is_undefined() || indexed_db.is_null() {
+                return Err(io::Error::other("IndexedDB is not available"));
+            }
+            Ok(Self::new(open_indexed_db_store(name)))
+        }
+
+        async fn call(&self, method: &str, args: &[JsValue]) -> io::Result<JsValue> {
+            let function: Function = Reflect::get(&self.storage, &JsValue::from_str(method))
+                .map_err(js_error)?
+                .dyn_into()
+                .map_err(|_| io::Error::other(format!("storage has no {method} function")))?;
+            let args = js_sys::Array::from_iter(args.iter().cloned());
+            let result = function.apply(&self.storage, &args).map_err(js_error)?;
+            match result.dyn_into::<Promise>() {
+                Ok(promise) => JsFuture::from(promise).await.map_err(js_error),
+                Err(value) => Ok(value),
+            }
+        }
+    }
+
+    fn js_error(e: JsValue) -> io::Error {
+        io::Error::other(
+            e.as_string()
+                .or_else(|| e.dyn_ref::<js_sys::Error>().map(|e| e.message().into()))
+                .unwrap_or_else(|| format!("{e:?}")),
+        )
+    }
+
+    impl QueueStore for JsQueueStore {
+        fn get<'a>(&'a self, key: &'a str) -> QueueFuture<'a, Option<Vec<u8>>> {
+            Box::pin(async move {
+                let value = self.call("getItem", &[JsValue::from_str(key)]).await?;
+                if value.is_undefined() || value.is_null() {
+                    return Ok(None);
+                }
+                match value.dyn_into::<Uint8Array>() {
+                    Ok(bytes) => Ok(Some(bytes.to_vec())),
+                    Err(_) => Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("{key} is not a Uint8Array"),
+                    )),
+                }
+            })
+        }
+
+        fn set<'a>(&'a self, key: &'a str, value: &'a [u8]) -> QueueFuture<'a, ()> {
+            Box::pin(async move {
+                let value = Uint8Array::from(value);
+                self.call("setItem", &[JsValue::from_str(key), value.into()])
+                    .await?;
+                Ok(())
+            })
+        }
+
+        fn remove<'a>(&'a self, key: &'a str) -> QueueFuture<'a, ()> {
+            Box::pin(async move {
+                self.call("removeItem", &[JsValue::from_str(key)]).await?;
+                Ok(())
+            })
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueuedUploadState {
+    /// Waiting for the next flush.
+    Pending,
+    /// Being sent by a flush. Items left in this state by a flush that never finished, e.g.
+    /// because the app was killed, are sent again by the next one.
+    Uploading,
+    /// Uploaded; `result` holds the server metadata.
+    Done,
+    /// Rejected by the server; sent again only after [`UploadQueue::retry_failed`].
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedUpload {
+    /// Queue-local id, not the id of the domain data.
+    pub id: String,
+    pub domain_id: String,
+    pub action: DomainAction,
+    pub size: u64,
+    pub state: QueuedUploadState,
+    /// Flushes that tried to send the item.
+    pub attempts: u32,
+    /// Error of the last attempt, if it failed.
+    pub error: Option<String>,
+    pub result: Option<DomainDataMetadata>,
+    /// Unix seconds.
+    pub enqueued_at: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct QueueIndex {
+    next_id: u64,
+    items: Vec<QueuedUpload>,
+}
+
+/// Uploads kept in a [`QueueStore`] until they are flushed. Clones share the same queue.
+#[derive(Debug, Clone)]
+pub struct UploadQueue {
+    store: Arc<dyn QueueStore>,
+    // Guards read-modify-write of the index.
+    index: Arc<Mutex<()>>,
+    // Held for a whole flush, so items are never sent twice at once.
+    flush: Arc<Mutex<()>>,
+}
+
+impl UploadQueue {
+    pub fn new(store: Arc<dyn QueueStore>) -> Self {
+        Self {
+            store,
+            index: Arc::new(Mutex::new(())),
+            flush: Arc::new(Mutex::new(())),
+        }
+    }
+
+    async fn load(&self) -> Result<QueueIndex, DomainError> {
+        match self.store.get(INDEX_KEY).await? {
+            Some(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e).into()),
+            None => Ok(QueueIndex::default()),
+        }
+    }
+
+    async fn save(&self, index: &QueueIndex) -> Result<(), DomainError> {
+        let bytes = serde_json::to_vec(index).map_err(io::Error::other)?;
+        self.store.set(INDEX_KEY, &bytes).await?;
+        Ok(())
+    }
+
+    /// Applies `update` to the item `id` and saves it, unless it was removed meanwhile.
+    async fn update(
+        &self,
+        id: &str,
+        update: impl FnOnce(&mut QueuedUpload),
+    ) -> Result<Option<QueuedUpload>, DomainError> {
+        let _guard = self.index.lock().await;
+        let mut index = self.load().await?;
+        let Some(item) = index.items.iter_mut().find(|item| item.id == id) else {
+            return Ok(None);
+        };
+        update(item);
+        let item = item.clone();
+        self.save(&index).await?;
+        Ok(Some(item))
+    }
+
+    /// Stores `data` for upload to `domain_id` with the next flush.
+    pub async fn enqueue(
+        &self,
+        domain_id: &str,
+        data: UploadDomainData,
+    ) -> Result<QueuedUpload, DomainError> {
+        let _guard = self.index.lock().await;
+        let mut index = self.load().await?;
+        let id = index.next_id.to_string();
+        // The payload goes first, so the index never lists an item without one.
+        self.store.set(&data_key(&id), &data.data).await?;
+        let item = QueuedUpload {
+            id,
+            domain_id: domain_id.to_string(),
+            action: data.action,
+            size: data.data.len() as u64,
+            state: QueuedUploadState::Pending,
+            attempts: 0,
+            error: None,
+            result: None,
+            enqueued_at: now_unix_secs(),
+        };
+        index.next_id += 1;
+        index.items.push(item.clone());
+        self.save(&index).await?;
+        Ok(item)
+    }
+
+    /// All items in the order they were enqueued.
+    pub async fn items(&self) -> Result<Vec<QueuedUpload>, DomainError> {
+        Ok(self.load().await?.items)
+    }
+
+    /// Uploads pending items one at a time and returns the queue afterwards. Items the server
+    /// rejects are marked failed and the flush goes on. A retryable error, such as a lost
+    /// connection, leaves the item pending and stops the flush with that error, since the
+    /// remaining items would most likely fail the same way.
+    pub async fn flush(&self, client: &DomainClient) -> Result<Vec<QueuedUpload>, DomainError> {
+        let _flush = self.flush.lock().await;
+        let ids: Vec<String> = self
+            .items()
+            .await?
+            .into_iter()
+            .filter(|item| {
+                matches!(
+                    item.state,
+                    QueuedUploadState::Pending | QueuedUploadState::Uploading
+                )
+            })
+            .map(|item| item.id)
+            .collect();
+
+        for id in ids {
+            let Some(item) = self
+                .update(&id, |item| {
+                    item.state = QueuedUploadState::Uploading;
+                    item.attempts += 1;
+                })
+                .await?
+            else {
+                continue;
+            };
+            let Some(data) = self.store.get(&data_key(&id)).await? else {
+                self.update(&id, |item| {
+                    item.state = QueuedUploadState::Failed;
+                    item.error = Some("queued data is missing".into());
+                })
+                .await?;
+                continue;
+            };
+            let upload = UploadDomainData {
+                action: item.action,
+                data,
+            };
+            match client
+                .upload_domain_data(&item.domain_id, vec![upload])
+                .await
+            {
+                Ok(metadata) => {
+                    self.update(&id, |item| {
+                        item.state = QueuedUploadState::Done;
+                        item.error = None;
+                        item.result = metadata.into_iter().next();
+                    })
+                    .await?;
+                    self.store.remove(&data_key(&id)).await?;
+                }
+                Err(e) => {
+                    let retryable = e.is_retryable() || matches!(e, DomainError::Cancelled);
+                    self.update(&id, |item| {
+                        item.state = if retryable {
+                            QueuedUploadState::Pending
+                        } else {
+                            QueuedUploadState::Failed
+                        };
+                        item.error = Some(e.to_string());
+                    })
+                    .await?;
+                    if retryable {
+                        return Err(e);
+                    }
+                }
+            }
+        }
+        self.items().await
+    }
+
+    /// Marks failed items pending again and returns how many there were.
+    pub async fn retry_failed(&self) -> Result<u32, DomainError> {
+        let _guard = self.index.lock().await;
+        let mut index = self.load().await?;
+        let mut count = 0;
+        for item in &mut index.items {
+            if item.state == QueuedUploadState::Failed {
+                item.state = QueuedUploadState::Pending;
+                count += 1;
+            }
+        }
+        if count > 0 {
+            self.save(&index).await?;
+        }
+        Ok(count)
+    }
+
+    /// Drops an item and its data, whatever its state. Returns `false` if there is no such item.
+    pub async fn remove(&self, id: &str) -> Result<bool, DomainError> {
+        let _guard = self.index.lock().await;
+        let mut index = self.load().await?;
+        let before = index.items.len();
+        index.items.retain(|item| item.id != id);
+        if index.items.len() == before {
+            return Ok(false);
+        }
+        self.save(&index).await?;
+        self.store.remove(&data_key(id)).await?;
+        Ok(true)
+    }
+
+    /// Forgets uploaded items and returns how many there were.
+    pub async fn clear_done(&self) -> Result<u32, DomainError> {
+        let _guard = self.index.lock().await;
+        let mut index = self.load().await?;
+        let before = index.items.len();
+        index
+            .items
+            .retain(|item| item.state != QueuedUploadState::Done);
+        let removed = (before - index.items.len()) as u32;
+        if removed > 0 {
+            self.save(&index).await?;
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use crate::retry::RetryPolicy;
+    use crate::transport::test_support::{body_bytes, dds_token, domain_auth, json_response, jwt};
+    use crate::transport::{ExecuteFuture, HttpExecutor, Transport};
+
+    /// Auth API, DDS and a domain server that is unreachable while `offline` is set and rejects
+    /// data named `bad`.
+    struct Servers {
+        offline: Arc<AtomicBool>,
+    }
+
+    impl HttpExecutor for Servers {
+        fn execute(&self, mut request: reqwest::Request) -> ExecuteFuture {
+            let body = String::from_utf8_lossy(&body_bytes(&mut request)).into_owned();
+            let (status, body) = match request.url().path() {
+                "/service/domains-access-token" => (200, dds_token()),
+                "/api/v1/domains/dom/auth" => (200, domain_auth("dom", "domain")),
+                path if path.ends_with("/info") => (404, serde_json::json!({})),
+                _ if self.offline.load(Ordering::SeqCst) => {
+                    (503, serde_json::json!({ "message": "unavailable" }))
+                }
+                _ if body.contains("\"bad\"") => (400, serde_json::json!({ "message": "bad" })),
+                _ => {
+                    let data = [serde_json::json!({
+                        "id": "remote", "domain_id": "dom", "name": "scan", "data_type": "ply",
+                        "size": 4, "created_at": "2025-01-01T00:00:00Z",
+                        "updated_at": "2025-01-01T00:00:00Z",
+                    })];
+                    (200, serde_json::json!({ "data": data }))
+                }
+            };
+            let res = json_response(status, &body);
+            Box::pin(async move { Ok(res) })
+        }
+    }
+
+    fn create(name: &str) -> UploadDomainData {
+        UploadDomainData {
+            action: DomainAction::Create {
+                name: name.into(),
+                data_type: "ply".into(),
+            },
+            data: b"data".to_vec(),
+        }
+    }
+
+    fn states(items: &[QueuedUpload]) -> Vec<QueuedUploadState> {
+        items.iter().map(|item| item.state).collect()
+    }
+
+    #[tokio::test]
+    async fn flushes_once_back_online() {
+        let offline = Arc::new(AtomicBool::new(true));
+        let transport = Transport::with_executor(Servers {
+            offline: offline.clone(),
+        })
+        .with_retry_policy(RetryPolicy::none());
+        let client =
+            DomainClient::new_with_transport("http://api", "http://dds", "client", transport)
+                .with_oidc_access_token(&jwt());
+
+        let dir = tempfile::tempdir().unwrap();
+        let queue = UploadQueue::new(Arc::new(FileQueueStore::new(dir.path())));
+        queue.enqueue("dom", create("scan")).await.unwrap();
+        queue.enqueue("dom", create("bad")).await.unwrap();
+
+        assert!(queue.flush(&client).await.unwrap_err().is_retryable());
+        let items = queue.items().await.unwrap();
+        assert_eq!(
+            states(&items),
+            [QueuedUploadState::Pending, QueuedUploadState::Pending]
+        );
+        assert_eq!(items[0].attempts, 1);
+        assert_eq!(items[1].attempts, 0);
+
+        // A new queue over the same directory picks up where the first one stopped.
+        offline.store(false, Ordering::SeqCst);
+        let queue = UploadQueue::new(Arc::new(FileQueueStore::new(dir.path())));
+        let items = queue.flush(&client).await.unwrap();
+        assert_eq!(
+            states(&items),
+            [QueuedUploadState::Done, QueuedUploadState::Failed]
+        );
+        assert_eq!(items[0].result.as_ref().unwrap().id, "remote");
+        assert!(items[1].error.is_some());
+        assert!(!dir.path().join(data_key(&items[0].id)).exists());
+
+        assert_eq!(queue.clear_done().await.unwrap(), 1);
+        assert_eq!(queue.retry_failed().await.unwrap(), 1);
+        assert!(queue.remove(&items[1].id).await.unwrap());
+        assert!(queue.items().await.unwrap().is_empty());
+    }
+}
@@ -1,15 +1,29 @@
+use crate::bulk_delete::{DEFAULT_DELETE_CONCURRENCY, DeleteOptions};
+use crate::discovery::UpdateDomainRequest as r_UpdateDomainRequest;
 use crate::domain_client::DomainClient as r_DomainClient;
 use crate::domain_client::ListDomainsQuery as r_ListDomainsQuery;
 use crate::domain_data::{
-    DownloadQuery as r_DownloadQuery, UploadDomainData as r_UploadDomainData,
+    DomainAction, DownloadQuery as r_DownloadQuery, UploadDomainData as r_UploadDomainData,
 };
+use crate::errors::{AuthError, DomainError};
+use crate::listing::ListQuery as r_ListQuery;
+use crate::oidc::oidc_token_provider;
+use crate::portals::PlacePortalRequest as r_PlacePortalRequest;
+use crate::progress::TransferControl;
 use crate::reconstruction::JobRequest as r_JobRequest;
+use crate::token_refresh::TokenRefreshOptions;
+use crate::token_store::{JsTokenStore, TokenStore};
+use crate::upload_queue::{JsQueueStore, QueueStore, UploadQueue as r_UploadQueue};
+use crate::watch::WatchOptions;
+use bytes::Bytes;
+use futures::Stream;
 use serde_wasm_bindgen::{from_value, to_value};
+use std::sync::Arc;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::{JsError, JsValue};
 use wasm_bindgen_futures::{
     future_to_promise,
-    js_sys::{Promise, Uint8Array},
+    js_sys::{self, Promise, Uint8Array},
 };
 use wasm_streams::readable::sys;
 
@@ -18,11 +32,52 @@ const TS_APPEND_CONTENT: &'static str = r#"
 
 export type DownloadQuery = { ids: string[], name: string | null, data_type: string | null };
 export type UploadDomainData = { id?: string, name?: string, data_type?: string, data: Uint8Array };
-export type DomainDataMetadata = { id: string, name: string, data_type: string, size: number, created_at: string, updated_at: string };
+/** Data read while uploading, so it never has to be in memory at once. `name` defaults to the file name, `size` to the blob size. */
+export type UploadDomainDataSource = { id?: string, name?: string, data_type?: string, data: Blob | ReadableStream<Uint8Array>, size?: number };
+export type DomainDataMetadata = { id: string, name: string, data_type: string, size: number, created_at: string, updated_at: string, hash?: string };
 export type DomainData = { metadata: DomainDataMetadata, data: Uint8Array };
 export type DomainServer = { id: string, url: string, organization_id: string, name: string };
 export type DomainWithServer = { id: string, name: string, organization_id: string, domain_server_id: string, redirect_url: string | null, domain_server: DomainServer };
-export type JobRequest = { data_ids: string[], processing_type: string, server_api_key: string, server_url: string };
+/** Changes to a domain; fields left out are kept and an empty `redirect_url` removes it. */
+export type UpdateDomainRequest = { name?: string, redirect_url?: string };
+export type Portal = { id: string, short_id: string, organization_id?: string, created_at?: string, updated_at?: string };
+/** Metres, in the domain's coordinate system. Compatible with the posemesh SDK `Pose`. */
+export type Vector3 = { x: number, y: number, z: number };
+export type Quaternion = { x: number, y: number, z: number, w: number };
+export type Pose = { position: Vector3, rotation: Quaternion };
+/** A portal placed in a domain; `size` is the edge length of the printed portal in metres. */
+export type PortalPlacement = { id: string, short_id: string, pose: Pose, size: number, created_at?: string, updated_at?: string };
+export type PlacePortalRequest = { pose: Pose, size: number };
+/**
+ * Error thrown by DomainClient methods. `name` identifies the kind of failure; error responses
+ * from the server also carry the HTTP status and the parsed error body.
+ */
+export interface DomainError extends Error {
+    name: "ReqwestError" | "AukiErrorResponse" | "NotFound" | "Forbidden" | "Conflict" | "PayloadTooLarge" | "QuotaExceeded" | "TokenExpired" | "ServerError" | "InvalidContentTypeHeader" | "StreamError" | "StreamCancelled" | "AuthError" | "InvalidRequest" | "Cancelled" | "Io";
+    retryable: boolean;
+    status?: number;
+    code?: string;
+    serverMessage?: string;
+    requestId?: string;
+}
+/** Progress reported while uploading or downloading. Byte and item counts are cumulative. */
+export type TransferEvent =
+    | { type: "bytes_sent", sent: number, total: number | null }
+    | { type: "bytes_received", received: number }
+    | { type: "items_done", done: number, total: number | null }
+    | { type: "part", name: string, part_number: number, total_parts: number | null, status: "uploading" | "uploaded" | "failed" };
+export type MetadataSort = "created_at_asc" | "created_at_desc" | "updated_at_asc" | "updated_at_desc" | "name_asc" | "name_desc" | "size_asc" | "size_desc";
+/** Filters and paging for listing metadata. Timestamps are RFC 3339 strings; bounds are exclusive. */
+export type ListQuery = { limit?: number | null, cursor?: string | null, sort?: MetadataSort | null, created_after?: string | null, created_before?: string | null, updated_after?: string | null, updated_before?: string | null, name_prefix?: string | null, data_types?: string[] };
+export type DomainDataEvent = { kind: "created" | "updated" | "deleted", id: string, metadata: DomainDataMetadata | null };
+/** An item of an `UploadQueue`. `id` is local to the queue; `result` is set once uploaded. */
+export type QueuedUpload = { id: string, domain_id: string, action: { id: string } | { name: string, data_type: string }, size: number, state: "pending" | "uploading" | "done" | "failed", attempts: number, error?: string, result?: DomainDataMetadata, enqueued_at: number };
+/** Result of a bulk delete. `deleted` lists what would be deleted in a dry run; `missing` the requested ids not found. */
+export type DeleteReport = { dry_run: boolean, deleted: DomainDataMetadata[], failed: { metadata: DomainDataMetadata, error: string }[], missing: string[] };
+export type MetadataPage = { data: DomainDataMetadata[], next_cursor: string | null };
+export type JobRequest = { data_ids: string[], processing_type?: string, server_api_key: string, server_url: string };
+export type JobStatus = "pending" | "running" | "succeeded" | "failed" | "cancelled" | "unknown";
+export type Job = { job_id: string, status: JobStatus, processing_type: string | null, progress: number | null, data_ids: string[], result_data_ids: string[], error: string | null, created_at: string | null, updated_at: string | null };
 /**
  * ListDomainsQuery specifies the parameters for listing domains the caller has access to.
  *
@@ -99,6 +154,101 @@ export function signInWithUserCredential(
 
 "#;
 
+/// Converts a `DomainError` into a JS `Error` named after the variant (e.g. `NotFound`), with
+/// `retryable` and, for error responses, `status`, `code`, `serverMessage` and `requestId`.
+fn domain_error_to_js(err: DomainError) -> JsValue {
+    let js_err = js_sys::Error::new(&err.to_string());
+    js_err.set_name(err.kind());
+    let set = |key: &str, value: JsValue| {
+        let _ = js_sys::Reflect::set(&js_err, &JsValue::from_str(key), &value);
+    };
+    set("retryable", JsValue::from_bool(err.is_retryable()));
+    if let Some(resp) = err.response() {
+        set("status", JsValue::from(resp.status.as_u16()));
+        for (key, value) in [
+            ("code", &resp.code),
+            ("serverMessage", &resp.message),
+            ("requestId", &resp.request_id),
+        ] {
+            if let Some(value) = value {
+                set(key, JsValue::from_str(value));
+            }
+        }
+    }
+    js_err.into()
+}
+
+/// Builds a `TransferControl` that passes events to `on_progress` and is cancelled once `signal`,
+/// an `AbortSignal`, aborts.
+fn transfer_control(on_progress: Option<js_sys::Function>, signal: JsValue) -> TransferControl {
+    let mut transfer = TransferControl::new();
+    if let Some(callback) = on_progress {
+        transfer = transfer.on_event(move |event| {
+            if let Ok(event) = to_value(&event) {
+                let _ = callback.call1(&JsValue::NULL, &event);
+            }
+        });
+    }
+    if signal.is_undefined() || signal.is_null() {
+        return transfer;
+    }
+    let cancel = transfer.cancellation().clone();
+    let aborted = js_sys::Reflect::get(&signal, &JsValue::from_str("aborted"))
+        .ok()
+        .and_then(|aborted| aborted.as_bool())
+        .unwrap_or(false);
+    if aborted {
+        cancel.cancel();
+    } else if let Ok(add_listener) =
+        js_sys::Reflect::get(&signal, &JsValue::from_str("addEventListener"))
+            .and_then(|f| f.dyn_into::<js_sys::Function>())
+    {
+        let listener = Closure::once_into_js(move || cancel.cancel());
+        let _ = add_listener.call2(&signal, &JsValue::from_str("abort"), &listener);
+    }
+    transfer
+}
+
+fn delete_options(dry_run: Option<bool>, concurrency: Option<u32>) -> DeleteOptions {
+    DeleteOptions {
+        dry_run: dry_run.unwrap_or_default(),
+        concurrency: concurrency.map_or(DEFAULT_DELETE_CONCURRENCY, |c| c as usize),
+    }
+}
+
+/// Reads a `Blob` (or `File`) or a `ReadableStream` of `Uint8Array`s chunk by chunk. Returns the
+/// size too when it is known up front.
+fn js_byte_stream(
+    data: &JsValue,
+) -> Result<(Option<u64>, impl Stream<Item = Result<Bytes, DomainError>>), JsValue> {
+    use futures::StreamExt;
+    let get = |key: &str| {
+        js_sys::Reflect::get(data, &JsValue::from_str(key)).unwrap_or(JsValue::UNDEFINED)
+    };
+    let (size, stream) = if get("getReader").is_function() {
+        (None, data.clone())
+    } else if let Ok(stream) = get("stream").dyn_into::<js_sys::Function>() {
+        (
+            get("size").as_f64().map(|size| size as u64),
+            stream.call0(data)?,
+        )
+    } else {
+        return Err(JsError::new("data must be a Blob, File or ReadableStream").into());
+    };
+    let chunks =
+        wasm_streams::ReadableStream::from_raw(stream.unchecked_into::<sys::ReadableStream>())
+            .into_stream()
+            .map(|chunk| match chunk {
+                Ok(chunk) => Ok(Bytes::from(Uint8Array::new(&chunk).to_vec())),
+                Err(e) => Err(DomainError::Io(std::io::Error::other(
+                    e.as_string()
+                        .or_else(|| e.dyn_ref::<js_sys::Error>().map(|e| e.message().into()))
+                        .unwrap_or_else(|| format!("{e:?}")),
+                ))),
+            });
+    Ok((size, chunks))
+}
+
 /// WASM wrapper for DomainClient that provides JavaScript bindings
 ///
 /// This struct wraps the Rust DomainClient and exposes its functionality
@@ -130,7 +280,7 @@ pub fn sign_in_with_app_credential(
             Ok(domain_client) => Ok(JsValue::from(DomainClient {
                 domain_client: domain_client,
             })),
-            Err(e) => Err(JsError::new(&e.to_string()).into()),
+            Err(e) => Err(domain_error_to_js(e)),
         }
     };
     future_to_promise(future)
@@ -159,7 +309,7 @@ pub fn sign_in_with_user_credential(
             Ok(domain_client) => Ok(JsValue::from(DomainClient {
                 domain_client: domain_client,
             })),
-            Err(e) => Err(JsError::new(&e.to_string()).into()),
+            Err(e) => Err(domain_error_to_js(e)),
         }
     };
     future_to_promise(future)
@@ -220,6 +370,151 @@ impl DomainClient {
         }
     }
 
+    /// Returns a new DomainClient that asks `provider` for an OIDC access token whenever the
+    /// current one is about to expire or a request is rejected with 401. Make sure to call
+    /// .free() to free the memory when you are done with the client.
+    ///
+    /// # Arguments
+    /// * `provider` - A function returning the token, or a Promise of it.
+    ///
+    /// # Example
+    /// ```javascript
+    /// const client_with_provider = client.withOIDCTokenProvider(async () => {
+    ///     const { access_token } = await myOidcRefresh();
+    ///     return access_token;
+    /// });
+    /// ```
+    #[wasm_bindgen(js_name = "withOIDCTokenProvider")]
+    pub fn with_oidc_token_provider(&self, provider: js_sys::Function) -> Self {
+        let provider = oidc_token_provider(move || {
+            let result = provider.call0(&JsValue::NULL);
+            async move {
+                let provider_error = |e: JsValue| -> DomainError {
+                    AuthError::TokenProvider(e.as_string().unwrap_or_else(|| format!("{e:?}")))
+                        .into()
+                };
+                let mut value = result.map_err(provider_error)?;
+                if let Some(promise) = value.dyn_ref::<Promise>() {
+                    value = wasm_bindgen_futures::JsFuture::from(promise.clone())
+                        .await
+                        .map_err(provider_error)?;
+                }
+                value.as_string().ok_or_else(|| {
+                    AuthError::TokenProvider("the provider did not return a string".into()).into()
+                })
+            }
+        });
+        Self {
+            domain_client: self.domain_client.with_oidc_token_provider(provider),
+        }
+    }
+
+    /// Returns a new DomainClient that saves the user session under `key` in `storage` after
+    /// every login and token refresh, starting with the current session. Make sure to call
+    /// .free() to free the memory when you are done with the client.
+    ///
+    /// # Arguments
+    /// * `storage` - An object with `getItem`, `setItem` and `removeItem`, returning values or
+    ///   Promises, such as `localStorage` or an IndexedDB wrapper like localForage. Defaults to
+    ///   `localStorage` when `undefined`.
+    /// * `key` - The storage key of the session.
+    ///
+    /// # Returns
+    /// * `Promise<DomainClient>` - Resolves to the client with the store attached.
+    ///
+    /// # Example
+    /// ```javascript
+    /// const client = await new DomainClient(apiUrl, ddsUrl, clientId).withTokenStore(localStorage, "posemesh-session");
+    /// if (!(await client.restoreSession())) {
+    ///     // sign in with the user credential and attach the store again
+    /// }
+    /// ```
+    #[wasm_bindgen(js_name = "withTokenStore")]
+    pub fn with_token_store(&self, storage: JsValue, key: String) -> Promise {
+        let mut domain_client = self.domain_client.clone();
+        let future = async move {
+            let store = if storage.is_undefined() {
+                JsTokenStore::local_storage(&key).map_err(|e| JsError::new(&e.to_string()))?
+            } else {
+                JsTokenStore::new(storage, &key)
+            };
+            // Nothing in wasm crosses threads, the Arc only matches the native signature.
+            #[allow(clippy::arc_with_non_send_sync)]
+            let store: Arc<dyn TokenStore> = Arc::new(store);
+            domain_client.set_token_store(store).await;
+            Ok(JsValue::from(DomainClient { domain_client }))
+        };
+        future_to_promise(future)
+    }
+
+    /// Signs in with the session saved in the token store, without the user's password.
+    ///
+    /// # Returns
+    /// * `Promise<boolean>` - Resolves to `false` when no usable session is saved.
+    #[wasm_bindgen(js_name = "restoreSession")]
+    pub fn restore_session(&self) -> Promise {
+        let mut domain_client = self.domain_client.clone();
+        let future = async move {
+            match domain_client.restore_session().await {
+                Ok(restored) => Ok(JsValue::from_bool(restored)),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        };
+        future_to_promise(future)
+    }
+
+    /// Revokes the user session on the server and forgets it in memory and in the token store.
+    /// Local state is cleared even when the returned Promise rejects.
+    #[wasm_bindgen(js_name = "logout")]
+    pub fn logout(&self) -> Promise {
+        let mut domain_client = self.domain_client.clone();
+        let future = async move {
+            match domain_client.logout().await {
+                Ok(()) => Ok(JsValue::UNDEFINED),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        };
+        future_to_promise(future)
+    }
+
+    /// Renews the DDS token and the tokens of recently used domains on a timer before they
+    /// expire, for this client and its clones. Omitted arguments keep their defaults.
+    ///
+    /// # Arguments
+    /// * `refresh_ahead_secs` - How long before expiry tokens are renewed.
+    /// * `max_domains` - Domain tokens kept; the least recently used domain is dropped beyond that.
+    /// * `idle_timeout_secs` - Domains unused for this long are dropped instead of renewed.
+    #[wasm_bindgen(js_name = "startTokenRefresh")]
+    pub fn start_token_refresh(
+        &self,
+        refresh_ahead_secs: Option<u32>,
+        max_domains: Option<u32>,
+        idle_timeout_secs: Option<u32>,
+    ) -> Promise {
+        let domain_client = self.domain_client.clone();
+        let defaults = TokenRefreshOptions::default();
+        let options = TokenRefreshOptions {
+            refresh_ahead: refresh_ahead_secs.map_or(defaults.refresh_ahead, |secs| {
+                std::time::Duration::from_secs(secs as u64)
+            }),
+            max_domains: max_domains.map_or(defaults.max_domains, |max| max as usize),
+            idle_timeout: idle_timeout_secs.map_or(defaults.idle_timeout, |secs| {
+                std::time::Duration::from_secs(secs as u64)
+            }),
+        };
+        let future = async move {
+            domain_client.start_token_refresh(options).await;
+            Ok(JsValue::UNDEFINED)
+        };
+        future_to_promise(future)
+    }
+
+    /// Stops the timer started by `startTokenRefresh`.
+    #[wasm_bindgen(js_name = "stopTokenRefresh")]
+    pub fn stop_token_refresh(&self) {
+        self.domain_client.stop_token_refresh();
+    }
+
     /// Downloads metadata for domain data matching the query.
     ///
     /// # Arguments
@@ -251,12 +546,147 @@ impl DomainClient {
                     Ok(value) => Ok(value),
                     Err(e) => Err(JsError::new(&e.to_string()).into()),
                 },
-                Err(e) => Err(JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        };
+        future_to_promise(future)
+    }
+
+    /// Lists one page of domain data metadata.
+    ///
+    /// # Arguments
+    /// * `domain_id` - The ID of the domain.
+    /// * `query` - The `ListQuery` filters and paging parameters.
+    ///
+    /// # Returns
+    /// * `Promise<MetadataPage>` - Resolves to the page; pass `next_cursor` as `query.cursor` for the next one.
+    ///
+    /// # Example
+    /// ```javascript
+    /// let page: MetadataPage = await client.listDomainDataMetadataPage("domain-123", { limit: 100, sort: "created_at_desc" });
+    /// ```
+    #[wasm_bindgen(js_name = "listDomainDataMetadataPage")]
+    pub fn list_domain_data_metadata_page(&self, domain_id: String, query: JsValue) -> Promise {
+        let domain_client = self.domain_client.clone();
+        let future = async move {
+            let query =
+                from_value::<r_ListQuery>(query).map_err(|e| JsError::new(&e.to_string()))?;
+            match domain_client.list_metadata_page(&domain_id, &query).await {
+                Ok(page) => to_value(&page).map_err(|e| JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
             }
         };
         future_to_promise(future)
     }
 
+    /// Lists all domain data metadata matching the query as a stream, fetching pages on demand.
+    ///
+    /// # Arguments
+    /// * `domain_id` - The ID of the domain.
+    /// * `query` - The `ListQuery` filters and paging parameters.
+    ///
+    /// # Returns
+    /// * `ReadableStream<DomainDataMetadata>` - Usable with `for await`.
+    ///
+    /// # Example
+    /// ```javascript
+    /// for await (const item of client.listDomainDataMetadata("domain-123", { data_types: ["mesh"] })) {
+    ///     console.log(item.name);
+    /// }
+    /// ```
+    #[wasm_bindgen(js_name = "listDomainDataMetadata")]
+    pub fn list_domain_data_metadata(
+        &self,
+        domain_id: String,
+        query: JsValue,
+    ) -> sys::ReadableStream {
+        use futures::{SinkExt, StreamExt};
+        use wasm_bindgen_futures::spawn_local;
+        let (mut tx, rx) = futures::channel::mpsc::unbounded::<Result<JsValue, JsValue>>();
+        let domain_client = self.domain_client.clone();
+        spawn_local(async move {
+            let query = match from_value::<r_ListQuery>(query) {
+                Ok(q) => q,
+                Err(e) => {
+                    tx.send(Err(JsError::new(&e.to_string()).into())).await.ok();
+                    return;
+                }
+            };
+            let items = domain_client.list_metadata(&domain_id, query);
+            futures::pin_mut!(items);
+            while let Some(item) = items.next().await {
+                let value = match item {
+                    Ok(item) => to_value(&item).map_err(|e| JsError::new(&e.to_string()).into()),
+                    Err(e) => Err(domain_error_to_js(e)),
+                };
+                let failed = value.is_err();
+                if tx.send(value).await.is_err() || failed {
+                    return;
+                }
+            }
+        });
+
+        wasm_streams::ReadableStream::into_raw(wasm_streams::ReadableStream::from_stream(rx))
+    }
+
+    /// Watches a domain for created, updated and deleted data. Uses server events when the domain
+    /// server supports them and otherwise polls the metadata listing. The stream ends with an
+    /// error when a request fails; cancel it (or `break` out of the loop) to stop watching.
+    ///
+    /// # Arguments
+    /// * `domain_id` - The ID of the domain.
+    /// * `query` - The `ListQuery` filters for the data to watch.
+    /// * `poll_interval_ms` - Delay between polls, 10 seconds when not set.
+    ///
+    /// # Returns
+    /// * `ReadableStream<DomainDataEvent>` - Usable with `for await`.
+    ///
+    /// # Example
+    /// ```javascript
+    /// for await (const event of client.watchDomainData("domain-123", { data_types: ["mesh"] })) {
+    ///     console.log(event.kind, event.id);
+    /// }
+    /// ```
+    #[wasm_bindgen(js_name = "watchDomainData")]
+    pub fn watch_domain_data(
+        &self,
+        domain_id: String,
+        query: JsValue,
+        poll_interval_ms: Option<u32>,
+    ) -> sys::ReadableStream {
+        use futures::{SinkExt, StreamExt};
+        use wasm_bindgen_futures::spawn_local;
+        let (mut tx, rx) = futures::channel::mpsc::unbounded::<Result<JsValue, JsValue>>();
+        let domain_client = self.domain_client.clone();
+        let mut options = WatchOptions::default();
+        if let Some(ms) = poll_interval_ms {
+            options.poll_interval = std::time::Duration::from_millis(ms as u64);
+        }
+        spawn_local(async move {
+            let query = match from_value::<r_ListQuery>(query) {
+                Ok(q) => q,
+                Err(e) => {
+                    tx.send(Err(JsError::new(&e.to_string()).into())).await.ok();
+                    return;
+                }
+            };
+            let events = domain_client.watch_domain_data(&domain_id, query, options);
+            futures::pin_mut!(events);
+            while let Some(event) = events.next().await {
+                let value = match event {
+                    Ok(event) => to_value(&event).map_err(|e| JsError::new(&e.to_string()).into()),
+                    Err(e) => Err(domain_error_to_js(e)),
+                };
+                let failed = value.is_err();
+                if tx.send(value).await.is_err() || failed {
+                    return;
+                }
+            }
+        });
+
+        wasm_streams::ReadableStream::into_raw(wasm_streams::ReadableStream::from_stream(rx))
+    }
+
     /// Downloads domain data matching the query, including the data bytes.
     ///
     /// # Arguments
@@ -284,7 +714,7 @@ impl DomainClient {
             let query = parse.unwrap();
             let res = domain_client.download_domain_data(&domain_id, &query).await;
             if let Err(e) = res {
-                return Err(JsError::new(&e.to_string()).into());
+                return Err(domain_error_to_js(e));
             }
             let response = res.unwrap();
 
@@ -293,6 +723,42 @@ impl DomainClient {
         future_to_promise(future)
     }
 
+    /// Same as `downloadDomainData`, calling `onProgress` with a `TransferEvent` as bytes and
+    /// items arrive. Aborting `signal` rejects the promise with a `Cancelled` error.
+    ///
+    /// # Example
+    /// ```javascript
+    /// const controller = new AbortController();
+    /// let data: DomainData[] = await client.downloadDomainDataWithProgress(
+    ///     "domain-123",
+    ///     { ids: [], name: null, data_type: "data type" },
+    ///     (event: TransferEvent) => console.log(event),
+    ///     controller.signal
+    /// );
+    /// ```
+    #[wasm_bindgen(js_name = "downloadDomainDataWithProgress")]
+    pub fn download_domain_data_with_progress(
+        &self,
+        domain_id: String,
+        query: JsValue,
+        on_progress: Option<js_sys::Function>,
+        signal: JsValue,
+    ) -> Promise {
+        let domain_client = self.domain_client.clone();
+        let transfer = transfer_control(on_progress, signal);
+        future_to_promise(async move {
+            let query =
+                from_value::<r_DownloadQuery>(query).map_err(|e| JsError::new(&e.to_string()))?;
+            let res = domain_client
+                .download_domain_data_with_progress(&domain_id, &query, &transfer)
+                .await;
+            match res {
+                Ok(data) => to_value(&data).map_err(|e| JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        })
+    }
+
     /// Downloads domain data as a readable stream, matching the query.
     ///
     /// # Arguments
@@ -344,7 +810,7 @@ impl DomainClient {
                             }
                         },
                         Err(e) => {
-                            tx.send(Err(JsError::new(&e.to_string()).into())).await.ok();
+                            tx.send(Err(domain_error_to_js(e))).await.ok();
                         }
                     }
                 }
@@ -390,7 +856,7 @@ impl DomainClient {
                             Ok(value) => Ok(value),
                             Err(e) => Err(JsError::new(&e.to_string()).into()),
                         },
-                        Err(e) => Err(JsError::new(&e.to_string()).into()),
+                        Err(e) => Err(domain_error_to_js(e)),
                     }
                 }
                 Err(e) => Err(JsError::new(&e.to_string()).into()),
@@ -399,6 +865,115 @@ impl DomainClient {
         future_to_promise(future)
     }
 
+    /// Same as `uploadDomainData`, calling `onProgress` with a `TransferEvent` as data is
+    /// accepted by the server. Aborting `signal` stops the upload and rejects the promise with a
+    /// `Cancelled` error; multipart uploads in flight are aborted on the server.
+    ///
+    /// # Example
+    /// ```javascript
+    /// const controller = new AbortController();
+    /// let result: DomainDataMetadata[] = await client.uploadDomainDataWithProgress(
+    ///     "domain-123",
+    ///     [{ name: "test", data_type: "test", data: new Uint8Array([1, 2, 3]) }],
+    ///     (event: TransferEvent) => {
+    ///         if (event.type === "bytes_sent") console.log(event.sent, event.total);
+    ///     },
+    ///     controller.signal
+    /// );
+    /// ```
+    #[wasm_bindgen(js_name = "uploadDomainDataWithProgress")]
+    pub fn upload_domain_data_with_progress(
+        &self,
+        domain_id: String,
+        data: JsValue,
+        on_progress: Option<js_sys::Function>,
+        signal: JsValue,
+    ) -> Promise {
+        let domain_client = self.domain_client.clone();
+        let transfer = transfer_control(on_progress, signal);
+        future_to_promise(async move {
+            let upload = from_value::<Vec<r_UploadDomainData>>(data)
+                .map_err(|e| JsError::new(&e.to_string()))?;
+            let res = domain_client
+                .upload_domain_data_with_progress(&domain_id, upload, &transfer)
+                .await;
+            match res {
+                Ok(data) => to_value(&data).map_err(|e| JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        })
+    }
+
+    /// Uploads one item from a `File`, `Blob` or `ReadableStream` without reading it into memory:
+    /// the data is cut into multipart parts as it is read. Give `id` to update existing data, or
+    /// `name` and `data_type` to create it. `onProgress` receives a `TransferEvent` per part and
+    /// aborting `signal` aborts the upload and rejects with a `Cancelled` error.
+    ///
+    /// # Arguments
+    /// * `domain_id` - The ID of the domain.
+    /// * `item`: `UploadDomainDataSource` - The data and where to store it.
+    ///
+    /// # Returns
+    /// * `Promise<DomainDataMetadata>` - Resolves to the metadata of the uploaded data.
+    ///
+    /// # Example
+    /// ```javascript
+    /// const file = input.files[0];
+    /// const controller = new AbortController();
+    /// let result: DomainDataMetadata = await client.uploadDomainDataStream(
+    ///     "domain-123",
+    ///     { data_type: "video", data: file },
+    ///     (event: TransferEvent) => {
+    ///         if (event.type === "bytes_sent") progressBar.value = event.sent / file.size;
+    ///     },
+    ///     controller.signal
+    /// );
+    /// ```
+    #[wasm_bindgen(js_name = "uploadDomainDataStream")]
+    pub fn upload_domain_data_stream(
+        &self,
+        domain_id: String,
+        item: JsValue,
+        on_progress: Option<js_sys::Function>,
+        signal: JsValue,
+    ) -> Promise {
+        let domain_client = self.domain_client.clone();
+        let transfer = transfer_control(on_progress, signal);
+        future_to_promise(async move {
+            let get = |value: &JsValue, key: &str| {
+                js_sys::Reflect::get(value, &JsValue::from_str(key)).unwrap_or(JsValue::UNDEFINED)
+            };
+            let data = get(&item, "data");
+            let (blob_size, chunks) = js_byte_stream(&data)?;
+            let size = get(&item, "size")
+                .as_f64()
+                .map(|size| size as u64)
+                .or(blob_size);
+            let action = match (
+                get(&item, "id").as_string(),
+                get(&item, "name")
+                    .as_string()
+                    .or_else(|| get(&data, "name").as_string()),
+                get(&item, "data_type").as_string(),
+            ) {
+                (Some(id), _, _) => DomainAction::Update { id },
+                (None, Some(name), Some(data_type)) => DomainAction::Create { name, data_type },
+                _ => {
+                    return Err(
+                        JsError::new("either id, or name and data_type are required").into(),
+                    );
+                }
+            };
+            let res = domain_client
+                .upload_domain_data_from_stream(&domain_id, action, size, chunks, &transfer)
+                .await;
+            match res {
+                Ok(meta) => to_value(&meta).map_err(|e| JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        })
+    }
+
     /// Downloads the raw data bytes for a specific domain data object by its ID.
     ///
     /// # Arguments
@@ -424,7 +999,7 @@ impl DomainClient {
                 .await;
             match res {
                 Ok(data) => Ok(JsValue::from(Uint8Array::from(data.as_slice()))),
-                Err(e) => Err(JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
             }
         };
         future_to_promise(future)
@@ -455,12 +1030,82 @@ impl DomainClient {
                 .await;
             match res {
                 Ok(()) => Ok(JsValue::undefined()),
-                Err(e) => Err(JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
             }
         };
         future_to_promise(future)
     }
 
+    /// Deletes many domain data objects, up to `concurrency` (default 8) at a time. Objects that
+    /// fail are reported and do not stop the others.
+    ///
+    /// # Arguments
+    /// * `domain_id` - The ID of the domain.
+    /// * `ids` - The IDs of the data objects to delete.
+    /// * `dry_run` - Only report what would be deleted.
+    ///
+    /// # Returns
+    /// * `Promise<DeleteReport>` - Resolves to what was deleted, what failed and the IDs not found.
+    ///
+    /// # Example
+    /// ```javascript
+    /// let report: DeleteReport = await client.deleteDomainData("domain-123", ["a", "b"], true);
+    /// ```
+    #[wasm_bindgen(js_name = "deleteDomainData")]
+    pub fn delete_domain_data(
+        &self,
+        domain_id: String,
+        ids: JsValue,
+        dry_run: Option<bool>,
+        concurrency: Option<u32>,
+    ) -> Promise {
+        let domain_client = self.domain_client.clone();
+        let options = delete_options(dry_run, concurrency);
+        future_to_promise(async move {
+            let ids = from_value::<Vec<String>>(ids).map_err(|e| JsError::new(&e.to_string()))?;
+            match domain_client
+                .delete_domain_data(&domain_id, &ids, &options)
+                .await
+            {
+                Ok(report) => to_value(&report).map_err(|e| JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        })
+    }
+
+    /// Same as `deleteDomainData` for every data object matching `query`. A query without ids,
+    /// name or data type is rejected.
+    ///
+    /// # Example
+    /// ```javascript
+    /// let report: DeleteReport = await client.deleteDomainDataByQuery(
+    ///     "domain-123",
+    ///     { ids: [], name: null, data_type: "runner_output" }
+    /// );
+    /// ```
+    #[wasm_bindgen(js_name = "deleteDomainDataByQuery")]
+    pub fn delete_domain_data_by_query(
+        &self,
+        domain_id: String,
+        query: JsValue,
+        dry_run: Option<bool>,
+        concurrency: Option<u32>,
+    ) -> Promise {
+        let domain_client = self.domain_client.clone();
+        let options = delete_options(dry_run, concurrency);
+        future_to_promise(async move {
+            let query =
+                from_value::<r_DownloadQuery>(query).map_err(|e| JsError::new(&e.to_string()))?;
+            match domain_client
+                .delete_domain_data_by_query(&domain_id, &query, &options)
+                .await
+            {
+                Ok(report) => to_value(&report).map_err(|e| JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        })
+    }
+
     /// Triggers a reconstruction job
     ///
     /// # Arguments
@@ -468,15 +1113,16 @@ impl DomainClient {
     /// * `request` - The `JobRequest` object containing reconstruction job parameters.
     ///
     /// # Returns
-    /// * `Promise`
+    /// * `Promise<Job>` - Resolves to the submitted job, including its `job_id`.
     ///
     /// # Example
     /// ```javascript
-    /// let result: string = await client.submitJobV1(
+    /// let job: Job = await client.submitJobV1(
     ///     "domain-123",
     ///     {
     ///         data_ids: ["data-id-1", "data-id-2"],
-    ///         server_url: "https://processing-server.example.com" // reconstruction server url
+    ///         server_url: "https://processing-server.example.com", // reconstruction server url
+    ///         server_api_key: reconstructionServerKey,
     ///     } as JobRequest
     /// );
     /// ```
@@ -490,14 +1136,8 @@ impl DomainClient {
                         .submit_job_request_v1(&domain_id, &process_request)
                         .await;
                     match res {
-                        Ok(response) => {
-                            let body = response
-                                .text()
-                                .await
-                                .map_err(|e| JsError::new(&e.to_string()))?;
-                            Ok(JsValue::from_str(&body))
-                        }
-                        Err(e) => Err(JsError::new(&e.to_string()).into()),
+                        Ok(job) => to_value(&job).map_err(|e| JsError::new(&e.to_string()).into()),
+                        Err(e) => Err(domain_error_to_js(e)),
                     }
                 }
                 Err(e) => Err(JsError::new(&e.to_string()).into()),
@@ -506,6 +1146,122 @@ impl DomainClient {
         future_to_promise(future)
     }
 
+    /// Gets the current state of a reconstruction job.
+    ///
+    /// # Arguments
+    /// * `domain_id` - The ID of the domain.
+    /// * `job_id` - The ID returned by `submitJobV1`.
+    ///
+    /// # Returns
+    /// * `Promise<Job>`
+    #[wasm_bindgen(js_name = "getJob")]
+    pub fn get_job(&self, domain_id: String, job_id: String) -> Promise {
+        let domain_client = self.domain_client.clone();
+        future_to_promise(async move {
+            match domain_client.get_job(&domain_id, &job_id).await {
+                Ok(job) => to_value(&job).map_err(|e| JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        })
+    }
+
+    /// Lists the reconstruction jobs of a domain.
+    ///
+    /// # Arguments
+    /// * `domain_id` - The ID of the domain.
+    ///
+    /// # Returns
+    /// * `Promise<Job[]>`
+    #[wasm_bindgen(js_name = "listJobs")]
+    pub fn list_jobs(&self, domain_id: String) -> Promise {
+        let domain_client = self.domain_client.clone();
+        future_to_promise(async move {
+            match domain_client.list_jobs(&domain_id).await {
+                Ok(jobs) => to_value(&jobs).map_err(|e| JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        })
+    }
+
+    /// Cancels a reconstruction job.
+    ///
+    /// # Arguments
+    /// * `domain_id` - The ID of the domain.
+    /// * `job_id` - The ID of the job to cancel.
+    ///
+    /// # Returns
+    /// * `Promise<void>`
+    #[wasm_bindgen(js_name = "cancelJob")]
+    pub fn cancel_job(&self, domain_id: String, job_id: String) -> Promise {
+        let domain_client = self.domain_client.clone();
+        future_to_promise(async move {
+            match domain_client.cancel_job(&domain_id, &job_id).await {
+                Ok(()) => Ok(JsValue::undefined()),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        })
+    }
+
+    /// Gets the ids of the data written by a finished job.
+    ///
+    /// # Arguments
+    /// * `domain_id` - The ID of the domain.
+    /// * `job_id` - The ID of the job.
+    ///
+    /// # Returns
+    /// * `Promise<string[]>`
+    #[wasm_bindgen(js_name = "getJobResults")]
+    pub fn get_job_results(&self, domain_id: String, job_id: String) -> Promise {
+        let domain_client = self.domain_client.clone();
+        future_to_promise(async move {
+            match domain_client.get_job_results(&domain_id, &job_id).await {
+                Ok(ids) => to_value(&ids).map_err(|e| JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        })
+    }
+
+    /// Waits until a job succeeded, failed or was cancelled.
+    ///
+    /// # Arguments
+    /// * `domain_id` - The ID of the domain.
+    /// * `job_id` - The ID of the job.
+    /// * `poll_interval_ms` - Delay between status checks, 5 seconds when not set.
+    /// * `on_progress` - Optional callback receiving the `Job` whenever its status or progress changes.
+    ///
+    /// # Returns
+    /// * `Promise<Job>` - Resolves to the finished job; check `status` for the outcome.
+    ///
+    /// # Example
+    /// ```javascript
+    /// const job = await client.waitForJob("domain-123", jobId, 2000, (job: Job) => console.log(job.status, job.progress));
+    /// ```
+    #[wasm_bindgen(js_name = "waitForJob")]
+    pub fn wait_for_job(
+        &self,
+        domain_id: String,
+        job_id: String,
+        poll_interval_ms: Option<u32>,
+        on_progress: Option<js_sys::Function>,
+    ) -> Promise {
+        let domain_client = self.domain_client.clone();
+        let poll_interval =
+            std::time::Duration::from_millis(poll_interval_ms.unwrap_or(5000) as u64);
+        future_to_promise(async move {
+            let res = domain_client
+                .wait_for_job(&domain_id, &job_id, poll_interval, |job| {
+                    if let (Some(callback), Ok(job)) = (&on_progress, to_value(job)) {
+                        let _ = callback.call1(&JsValue::NULL, &job);
+                    }
+                })
+                .await;
+            match res {
+                Ok(job) => to_value(&job).map_err(|e| JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        })
+    }
+
     /// # ListDomains returns a list of domains the caller has access to.
     ///
     /// # Arguments
@@ -534,7 +1290,7 @@ impl DomainClient {
                     Ok(value) => Ok(value),
                     Err(e) => Err(JsError::new(&e.to_string()).into()),
                 },
-                Err(e) => Err(JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
             }
         };
         future_to_promise(future)
@@ -573,12 +1329,206 @@ impl DomainClient {
                     Ok(value) => Ok(value),
                     Err(e) => Err(JsError::new(&e.to_string()).into()),
                 },
-                Err(e) => Err(JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
             }
         };
         future_to_promise(future)
     }
 
+    /// Gets a domain with its domain server.
+    ///
+    /// # Returns
+    /// * `Promise<DomainWithServer>` - Resolves to the domain.
+    ///
+    /// # Example
+    /// ```javascript
+    /// let domain: DomainWithServer = await client.getDomain("domain-123");
+    /// ```
+    #[wasm_bindgen(js_name = "getDomain")]
+    pub fn get_domain(&self, domain_id: String) -> Promise {
+        let domain_client = self.domain_client.clone();
+        future_to_promise(async move {
+            match domain_client.get_domain(&domain_id).await {
+                Ok(domain) => to_value(&domain).map_err(|e| JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        })
+    }
+
+    /// Renames a domain or changes its redirect URL. Fields left out are kept; an empty
+    /// `redirect_url` removes it.
+    ///
+    /// # Arguments
+    /// * `domain_id` - The ID of the domain.
+    /// * `update` - The `UpdateDomainRequest` with the changes.
+    ///
+    /// # Returns
+    /// * `Promise<DomainWithServer>` - Resolves to the updated domain.
+    ///
+    /// # Example
+    /// ```javascript
+    /// let domain: DomainWithServer = await client.updateDomain("domain-123", { name: "Lobby" });
+    /// ```
+    #[wasm_bindgen(js_name = "updateDomain")]
+    pub fn update_domain(&self, domain_id: String, update: JsValue) -> Promise {
+        let domain_client = self.domain_client.clone();
+        future_to_promise(async move {
+            let update = from_value::<r_UpdateDomainRequest>(update)
+                .map_err(|e| JsError::new(&e.to_string()))?;
+            match domain_client.update_domain(&domain_id, &update).await {
+                Ok(domain) => to_value(&domain).map_err(|e| JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        })
+    }
+
+    /// Moves a domain and its data to another domain server.
+    ///
+    /// # Returns
+    /// * `Promise<DomainWithServer>` - Resolves to the domain with its new domain server.
+    ///
+    /// # Example
+    /// ```javascript
+    /// let domain: DomainWithServer = await client.moveDomain("domain-123", "domain-server-456");
+    /// ```
+    #[wasm_bindgen(js_name = "moveDomain")]
+    pub fn move_domain(&self, domain_id: String, domain_server_id: String) -> Promise {
+        let domain_client = self.domain_client.clone();
+        future_to_promise(async move {
+            match domain_client
+                .move_domain(&domain_id, &domain_server_id)
+                .await
+            {
+                Ok(domain) => to_value(&domain).map_err(|e| JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        })
+    }
+
+    /// Lists the domain servers available to an organization.
+    ///
+    /// # Arguments
+    /// * `org` - `"own"`, `"all"` or an organization ID.
+    ///
+    /// # Returns
+    /// * `Promise<DomainServer[]>` - Resolves to the domain servers.
+    #[wasm_bindgen(js_name = "listDomainServers")]
+    pub fn list_domain_servers(&self, org: String) -> Promise {
+        let domain_client = self.domain_client.clone();
+        future_to_promise(async move {
+            match domain_client.list_domain_servers(&org).await {
+                Ok(servers) => to_value(&servers).map_err(|e| JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        })
+    }
+
+    /// Gets one domain server.
+    ///
+    /// # Returns
+    /// * `Promise<DomainServer>` - Resolves to the domain server.
+    #[wasm_bindgen(js_name = "getDomainServer")]
+    pub fn get_domain_server(&self, domain_server_id: String) -> Promise {
+        let domain_client = self.domain_client.clone();
+        future_to_promise(async move {
+            match domain_client.get_domain_server(&domain_server_id).await {
+                Ok(server) => to_value(&server).map_err(|e| JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        })
+    }
+
+    /// Lists the portals attached to a domain.
+    ///
+    /// # Returns
+    /// * `Promise<Portal[]>` - Resolves to the portals.
+    #[wasm_bindgen(js_name = "listDomainPortals")]
+    pub fn list_domain_portals(&self, domain_id: String) -> Promise {
+        let domain_client = self.domain_client.clone();
+        future_to_promise(async move {
+            match domain_client.list_domain_portals(&domain_id).await {
+                Ok(portals) => to_value(&portals).map_err(|e| JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        })
+    }
+
+    /// Lists the portals placed in a domain with their poses.
+    ///
+    /// # Returns
+    /// * `Promise<PortalPlacement[]>` - Resolves to the placed portals.
+    #[wasm_bindgen(js_name = "listPortals")]
+    pub fn list_portals(&self, domain_id: String) -> Promise {
+        let domain_client = self.domain_client.clone();
+        future_to_promise(async move {
+            match domain_client.list_portals(&domain_id).await {
+                Ok(portals) => to_value(&portals).map_err(|e| JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        })
+    }
+
+    /// Gets the placement of one portal.
+    ///
+    /// # Returns
+    /// * `Promise<PortalPlacement>` - Resolves to the placement; rejects with `NotFound` if the
+    ///   portal is not placed in the domain.
+    #[wasm_bindgen(js_name = "getPortal")]
+    pub fn get_portal(&self, domain_id: String, short_id: String) -> Promise {
+        let domain_client = self.domain_client.clone();
+        future_to_promise(async move {
+            match domain_client.get_portal(&domain_id, &short_id).await {
+                Ok(portal) => to_value(&portal).map_err(|e| JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        })
+    }
+
+    /// Places a portal in a domain, or moves it if it is placed already.
+    ///
+    /// # Arguments
+    /// * `domain_id` - The ID of the domain.
+    /// * `short_id` - The ID printed on the portal.
+    /// * `request` - The `PlacePortalRequest` with the pose and the printed size in metres.
+    ///
+    /// # Returns
+    /// * `Promise<PortalPlacement>` - Resolves to the stored placement.
+    ///
+    /// # Example
+    /// ```javascript
+    /// let portal: PortalPlacement = await client.placePortal("domain-123", "ABC123", {
+    ///     pose: { position: { x: 0, y: 1.2, z: -3 }, rotation: { x: 0, y: 0, z: 0, w: 1 } },
+    ///     size: 0.1,
+    /// });
+    /// ```
+    #[wasm_bindgen(js_name = "placePortal")]
+    pub fn place_portal(&self, domain_id: String, short_id: String, request: JsValue) -> Promise {
+        let domain_client = self.domain_client.clone();
+        future_to_promise(async move {
+            let request = from_value::<r_PlacePortalRequest>(request)
+                .map_err(|e| JsError::new(&e.to_string()))?;
+            match domain_client
+                .place_portal(&domain_id, &short_id, &request)
+                .await
+            {
+                Ok(portal) => to_value(&portal).map_err(|e| JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        })
+    }
+
+    /// Removes the placement of a portal from a domain.
+    #[wasm_bindgen(js_name = "removePortal")]
+    pub fn remove_portal(&self, domain_id: String, short_id: String) -> Promise {
+        let domain_client = self.domain_client.clone();
+        future_to_promise(async move {
+            match domain_client.remove_portal(&domain_id, &short_id).await {
+                Ok(()) => Ok(JsValue::undefined()),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        })
+    }
+
     /// Deletes a domain
     ///
     /// # Arguments
@@ -598,13 +1548,142 @@ impl DomainClient {
             let res = domain_client.delete_domain(&domain_id).await;
             match res {
                 Ok(()) => Ok(JsValue::undefined()),
-                Err(e) => Err(JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
             }
         };
         future_to_promise(future)
     }
 }
 
+/// Uploads kept in IndexedDB until they are flushed, so they survive reloads and connectivity
+/// loss. Make sure to call .free() to free the memory when you are done with the queue.
+#[wasm_bindgen]
+pub struct UploadQueue {
+    queue: r_UploadQueue,
+}
+
+#[wasm_bindgen]
+impl UploadQueue {
+    /// Opens the queue kept in the IndexedDB database `name` (default `posemesh-upload-queue`),
+    /// or in `storage`: an object with `getItem`, `setItem` and `removeItem` that can hold
+    /// `Uint8Array` values, such as a localForage instance.
+    ///
+    /// # Example
+    /// ```javascript
+    /// const queue = new UploadQueue();
+    /// await queue.enqueue("domain-123", { name: "scan", data_type: "ply", data: bytes });
+    /// window.addEventListener("online", () => queue.flush(client));
+    /// ```
+    #[wasm_bindgen(constructor)]
+    pub fn new(name: Option<String>, storage: JsValue) -> Result<UploadQueue, JsError> {
+        let store = if storage.is_undefined() || storage.is_null() {
+            JsQueueStore::indexed_db(name.as_deref().unwrap_or("posemesh-upload-queue"))
+                .map_err(|e| JsError::new(&e.to_string()))?
+        } else {
+            JsQueueStore::new(storage)
+        };
+        // Nothing in wasm crosses threads, the Arc only matches the native signature.
+        #[allow(clippy::arc_with_non_send_sync)]
+        let store: Arc<dyn QueueStore> = Arc::new(store);
+        Ok(Self {
+            queue: r_UploadQueue::new(store),
+        })
+    }
+
+    /// Stores one `UploadDomainData` for upload to `domain_id` with the next flush.
+    ///
+    /// # Returns
+    /// * `Promise<QueuedUpload>` - Resolves once the item is stored.
+    #[wasm_bindgen(js_name = "enqueue")]
+    pub fn enqueue(&self, domain_id: String, item: JsValue) -> Promise {
+        let queue = self.queue.clone();
+        future_to_promise(async move {
+            let item =
+                from_value::<r_UploadDomainData>(item).map_err(|e| JsError::new(&e.to_string()))?;
+            match queue.enqueue(&domain_id, item).await {
+                Ok(queued) => to_value(&queued).map_err(|e| JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        })
+    }
+
+    /// # Returns
+    /// * `Promise<QueuedUpload[]>` - Resolves to all items in the order they were enqueued.
+    #[wasm_bindgen(js_name = "items")]
+    pub fn items(&self) -> Promise {
+        let queue = self.queue.clone();
+        future_to_promise(async move {
+            match queue.items().await {
+                Ok(items) => to_value(&items).map_err(|e| JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        })
+    }
+
+    /// Uploads pending items one at a time with `client`. Items the server rejects are marked
+    /// failed and the flush goes on; a retryable error, e.g. while offline, leaves the item
+    /// pending and rejects the promise with that error.
+    ///
+    /// # Returns
+    /// * `Promise<QueuedUpload[]>` - Resolves to the queue after the flush.
+    #[wasm_bindgen(js_name = "flush")]
+    pub fn flush(&self, client: &DomainClient) -> Promise {
+        let queue = self.queue.clone();
+        let domain_client = client.domain_client.clone();
+        future_to_promise(async move {
+            match queue.flush(&domain_client).await {
+                Ok(items) => to_value(&items).map_err(|e| JsError::new(&e.to_string()).into()),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        })
+    }
+
+    /// Marks failed items pending again.
+    ///
+    /// # Returns
+    /// * `Promise<number>` - Resolves to the number of items marked.
+    #[wasm_bindgen(js_name = "retryFailed")]
+    pub fn retry_failed(&self) -> Promise {
+        let queue = self.queue.clone();
+        future_to_promise(async move {
+            match queue.retry_failed().await {
+                Ok(count) => Ok(JsValue::from(count)),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        })
+    }
+
+    /// Drops an item and its data, whatever its state.
+    ///
+    /// # Returns
+    /// * `Promise<boolean>` - Resolves to `false` if there is no such item.
+    #[wasm_bindgen(js_name = "remove")]
+    pub fn remove(&self, id: String) -> Promise {
+        let queue = self.queue.clone();
+        future_to_promise(async move {
+            match queue.remove(&id).await {
+                Ok(removed) => Ok(JsValue::from_bool(removed)),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        })
+    }
+
+    /// Forgets uploaded items.
+    ///
+    /// # Returns
+    /// * `Promise<number>` - Resolves to the number of items forgotten.
+    #[wasm_bindgen(js_name = "clearDone")]
+    pub fn clear_done(&self) -> Promise {
+        let queue = self.queue.clone();
+        future_to_promise(async move {
+            match queue.clear_done().await {
+                Ok(count) => Ok(JsValue::from(count)),
+                Err(e) => Err(domain_error_to_js(e)),
+            }
+        })
+    }
+}
+
 /// Initializes the WASM module with logging and error handling
 ///
 /// This function is automatically called when the WASM module is loaded.
@@ -0,0 +1,584 @@
+//! Change feed for domain data.
+//!
+//! A watch first asks the domain server for a server-sent event stream at
+//! `GET /api/v1/domains/{id}/data/events`. Servers without that endpoint are watched by polling
+//! the metadata listing and diffing it against the previous poll, so only metadata is transferred
+//! and no data is downloaded.
+
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::{Stream, StreamExt, TryStreamExt, stream};
+#[cfg(target_family = "wasm")]
+use posemesh_utils::sleep;
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use serde::{Deserialize, Serialize};
+#[cfg(not(target_family = "wasm"))]
+use tokio::time::sleep;
+
+use crate::domain_data::{DomainDataMetadata, is_unsupported_endpoint_status};
+use crate::errors::{AukiErrorResponse, DomainError};
+use crate::listing::{ListQuery, list_metadata_page_v1, paginate};
+use crate::transport::{SendVia, Transport};
+
+#[cfg(not(target_family = "wasm"))]
+type ByteStream = futures::stream::BoxStream<'static, Result<Bytes, reqwest::Error>>;
+#[cfg(target_family = "wasm")]
+type ByteStream = futures::stream::LocalBoxStream<'static, Result<Bytes, reqwest::Error>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DomainDataEventKind {
+    Created,
+    Updated,
+    Deleted,
+}
+
+/// A change to one domain data item. `metadata` is `None` for deletions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DomainDataEvent {
+    pub kind: DomainDataEventKind,
+    pub id: String,
+    pub metadata: Option<DomainDataMetadata>,
+}
+
+impl DomainDataEvent {
+    fn changed(kind: DomainDataEventKind, metadata: DomainDataMetadata) -> Self {
+        Self {
+            kind,
+            id: metadata.id.clone(),
+            metadata: Some(metadata),
+        }
+    }
+
+    fn deleted(id: String) -> Self {
+        Self {
+            kind: DomainDataEventKind::Deleted,
+            id,
+            metadata: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    /// Delay between metadata polls, and before reconnecting after an error.
+    pub poll_interval: Duration,
+    /// Try the server event stream before falling back to polling.
+    pub server_events: bool,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(10),
+            server_events: true,
+        }
+    }
+}
+
+/// Where to reach a domain; re-resolved on every reconnect and poll so tokens can be refreshed.
+#[derive(Debug, Clone)]
+pub struct DomainEndpoint {
+    pub url: String,
+    pub access_token: String,
+}
+
+enum Mode {
+    /// Not connected; try the event stream if enabled.
+    Connect,
+    Events {
+        body: ByteStream,
+        buffer: Vec<u8>,
+    },
+    Poll,
+}
+
+struct Watcher<'a, A> {
+    transport: &'a Transport,
+    client_id: &'a str,
+    domain_id: &'a str,
+    query: ListQuery,
+    options: WatchOptions,
+    endpoint: A,
+    mode: Mode,
+    pending: VecDeque<DomainDataEvent>,
+    last_event_id: Option<String>,
+    /// `updated_at` by id as of the last poll; `None` until the first poll.
+    snapshot: Option<HashMap<String, String>>,
+    backoff: bool,
+}
+
+impl<A, Fut> Watcher<'_, A>
+where
+    A: FnMut() -> Fut,
+    Fut: Future<Output = Result<DomainEndpoint, DomainError>>,
+{
+    async fn next(&mut self) -> Result<DomainDataEvent, DomainError> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(event);
+            }
+            if std::mem::take(&mut self.backoff) {
+                sleep(self.options.poll_interval).await;
+            }
+            let result = match &mut self.mode {
+                Mode::Connect if self.options.server_events => self.connect().await,
+                Mode::Connect => {
+                    self.mode = Mode::Poll;
+                    Ok(())
+                }
+                Mode::Events { body, buffer } => {
+                    match read_event(body, buffer).await {
+                        Ok(Some(event)) => {
+                            self.handle_event(event);
+                            Ok(())
+                        }
+                        // The server closed the stream; resume from the last event id after
+                        // `poll_interval`, so a stream closed right away doesn't spin.
+                        Ok(None) => {
+                            self.mode = Mode::Connect;
+                            self.backoff = true;
+                            Ok(())
+                        }
+                        Err(e) => {
+                            self.mode = Mode::Connect;
+                            Err(e.into())
+                        }
+                    }
+                }
+                Mode::Poll => self.poll().await,
+            };
+            if let Err(e) = result {
+                self.backoff = true;
+                return Err(e);
+            }
+        }
+    }
+
+    async fn connect(&mut self) -> Result<(), DomainError> {
+        let endpoint = (self.endpoint)().await?;
+        let mut request = self
+            .transport
+            .get(format!(
+                "{}/api/v1/domains/{}/data/events",
+                endpoint.url, self.domain_id
+            ))
+            .bearer_auth(&endpoint.access_token)
+            .header(ACCEPT, "text/event-stream")
+            .header("posemesh-client-id", self.client_id)
+            .query(&self.query.params());
+        if let Some(id) = &self.last_event_id {
+            request = request.header("Last-Event-ID", id);
+        }
+        let response = request.send_via(self.transport).await?;
+
+        let is_event_stream = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|ct| ct.to_str().ok())
+            .is_some_and(|ct| ct.starts_with("text/event-stream"));
+        if response.status().is_success() && is_event_stream {
+            self.mode = Mode::Events {
+                body: response.bytes_stream().boxed_stream(),
+                buffer: Vec::new(),
+            };
+            Ok(())
+        } else if response.status().is_success()
+            || is_unsupported_endpoint_status(response.status())
+        {
+            tracing::debug!("Domain server has no event stream, polling metadata instead");
+            self.mode = Mode::Poll;
+            Ok(())
+        } else {
+            Err(
+                AukiErrorResponse::from_response(response, "Failed to watch domain data.")
+                    .await
+                    .into(),
+            )
+        }
+    }
+
+    fn handle_event(&mut self, event: SseEvent) {
+        if event.id.is_some() {
+            self.last_event_id = event.id;
+        }
+        let kind = match event.event.as_str() {
+            "created" => DomainDataEventKind::Created,
+            "updated" => DomainDataEventKind::Updated,
+            "deleted" => DomainDataEventKind::Deleted,
+            // Keep-alives and future event types.
+            _ => return,
+        };
+        if kind == DomainDataEventKind::Deleted {
+            #[derive(Deserialize)]
+            struct Deleted {
+                id: String,
+            }
+            match serde_json::from_str::<Deleted>(&event.data) {
+                Ok(deleted) => self.pending.push_back(DomainDataEvent::deleted(deleted.id)),
+                Err(e) => tracing::warn!("Ignoring malformed delete event: {e}"),
+            }
+            return;
+        }
+        match serde_json::from_str::<DomainDataMetadata>(&event.data) {
+            Ok(metadata) if self.query.matches(&metadata) => {
+                self.pending
+                    .push_back(DomainDataEvent::changed(kind, metadata));
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Ignoring malformed {} event: {e}", event.event),
+        }
+    }
+
+    async fn poll(&mut self) -> Result<(), DomainError> {
+        if self.snapshot.is_some() {
+            sleep(self.options.poll_interval).await;
+        }
+        let endpoint = (self.endpoint)().await?;
+        let query = ListQuery {
+            cursor: None,
+            ..self.query.clone()
+        };
+        let (transport, client_id, domain_id) = (self.transport, self.client_id, self.domain_id);
+        let current: Vec<DomainDataMetadata> = paginate(query, |page| {
+            let endpoint = &endpoint;
+            async move {
+                list_metadata_page_v1(
+                    transport,
+                    &endpoint.url,
+                    client_id,
+                    &endpoint.access_token,
+                    domain_id,
+                    &page,
+                )
+                .await
+            }
+        })
+        .try_collect()
+        .await?;
+
+        let next: HashMap<String, String> = current
+            .iter()
+            .map(|m| (m.id.clone(), m.updated_at.clone()))
+            .collect();
+        // The first poll only records the baseline.
+        if let Some(previous) = self.snapshot.replace(next) {
+            self.pending.extend(diff(&previous, current));
+        }
+        Ok(())
+    }
+}
+
+/// Events turning `previous` (`updated_at` by id) into `current`.
+fn diff(
+    previous: &HashMap<String, String>,
+    current: Vec<DomainDataMetadata>,
+) -> Vec<DomainDataEvent> {
+    let mut seen = std::collections::HashSet::new();
+    let mut events = Vec::new();
+    for metadata in current {
+        seen.insert(metadata.id.clone());
+        match previous.get(&metadata.id) {
+            None => events.push(DomainDataEvent::changed(
+                DomainDataEventKind::Created,
+                metadata,
+            )),
+            Some(updated_at) if *updated_at != metadata.updated_at => events.push(
+                DomainDataEvent::changed(DomainDataEventKind::Updated, metadata),
+            ),
+            Some(_) => {}
+        }
+    }
+    let mut deleted: Vec<_> = previous
+        .keys()
+        .filter(|id| !seen.contains(*id))
+        .cloned()
+        .collect();
+    deleted.sort();
+    events.extend(deleted.into_iter().map(DomainDataEvent::deleted));
+    events
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct SseEvent {
+    event: String,
+    data: String,
+    id: Option<String>,
+}
+
+/// Parses one server-sent event block (without the terminating blank line).
+fn parse_event(block: &str) -> SseEvent {
+    let mut event = SseEvent {
+        event: "message".to_string(),
+        ..Default::default()
+    };
+    let mut data = Vec::new();
+    for line in block.lines() {
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => event.event = value.to_string(),
+            "data" => data.push(value),
+            "id" => event.id = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    event.data = data.join("\n");
+    event
+}
+
+/// Reads the next event, or `None` when the stream ends.
+async fn read_event(
+    body: &mut ByteStream,
+    buffer: &mut Vec<u8>,
+) -> Result<Option<SseEvent>, reqwest::Error> {
+    loop {
+        if let Some(end) = buffer.windows(2).position(|w| w == b"\n\n") {
+            let block = String::from_utf8_lossy(&buffer[..end]).into_owned();
+            buffer.drain(..end + 2);
+            return Ok(Some(parse_event(&block)));
+        }
+        match body.next().await {
+            Some(chunk) => {
+                let chunk = chunk?;
+                buffer.extend(chunk.iter().filter(|b| **b != b'\r'));
+            }
+            None => return Ok(None),
+        }
+    }
+}
+
+trait BoxedStream: Sized {
+    fn boxed_stream(self) -> ByteStream;
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl<S: Stream<Item = Result<Bytes, reqwest::Error>> + Send + 'static> BoxedStream for S {
+    fn boxed_stream(self) -> ByteStream {
+        self.boxed()
+    }
+}
+
+#[cfg(target_family = "wasm")]
+impl<S: Stream<Item = Result<Bytes, reqwest::Error>> + 'static> BoxedStream for S {
+    fn boxed_stream(self) -> ByteStream {
+        self.boxed_local()
+    }
+}
+
+/// Streams changes to the domain data matching `query` until dropped. Errors are yielded and
+/// the watch carries on after `poll_interval`, reconnecting with `endpoint` for fresh tokens.
+pub fn watch_v1<'a, A, Fut>(
+    transport: &'a Transport,
+    client_id: &'a str,
+    domain_id: &'a str,
+    query: ListQuery,
+    options: WatchOptions,
+    endpoint: A,
+) -> impl Stream<Item = Result<DomainDataEvent, DomainError>> + 'a
+where
+    A: FnMut() -> Fut + 'a,
+    Fut: Future<Output = Result<DomainEndpoint, DomainError>> + 'a,
+{
+    let watcher = Watcher {
+        transport,
+        client_id,
+        domain_id,
+        query,
+        options,
+        endpoint,
+        mode: Mode::Connect,
+        pending: VecDeque::new(),
+        last_event_id: None,
+        snapshot: None,
+        backoff: false,
+    };
+    stream::unfold(watcher, |mut watcher| async move {
+        let item = watcher.next().await;
+        Some((item, watcher))
+    })
+}
+
+#[cfg(not(target_family = "wasm"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::test_support::json_response;
+    use crate::transport::{ExecuteFuture, HttpExecutor};
+    use reqwest::{Request, Response};
+    use std::sync::{Arc, Mutex};
+
+    fn meta(id: &str, updated_at: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": id, "domain_id": "dom", "name": id, "data_type": "mesh",
+            "size": 1, "created_at": "2025-01-01T00:00:00Z", "updated_at": updated_at,
+        })
+    }
+
+    fn endpoint() -> impl FnMut() -> futures::future::Ready<Result<DomainEndpoint, DomainError>> {
+        || {
+            futures::future::ready(Ok(DomainEndpoint {
+                url: "http://domain".into(),
+                access_token: "t".into(),
+            }))
+        }
+    }
+
+    /// No event stream; each metadata poll returns the next snapshot.
+    struct Polls(Arc<Mutex<VecDeque<serde_json::Value>>>);
+
+    impl HttpExecutor for Polls {
+        fn execute(&self, request: Request) -> ExecuteFuture {
+            let (status, body) = if request.url().path().ends_with("/events") {
+                (404, serde_json::json!({ "error": "not found" }))
+            } else {
+                let mut polls = self.0.lock().unwrap();
+                let data = if polls.len() > 1 {
+                    polls.pop_front().unwrap()
+                } else {
+                    polls[0].clone()
+                };
+                (200, serde_json::json!({ "data": data }))
+            };
+            let res = json_response(status, &body);
+            Box::pin(async move { Ok(res) })
+        }
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_metadata_diff() {
+        let polls = VecDeque::from([
+            serde_json::json!([meta("1", "a"), meta("2", "a")]),
+            serde_json::json!([meta("1", "b"), meta("3", "a")]),
+        ]);
+        let transport = Transport::with_executor(Polls(Arc::new(Mutex::new(polls))));
+        let options = WatchOptions {
+            poll_interval: Duration::from_millis(1),
+            ..Default::default()
+        };
+        let events: Vec<_> = watch_v1(
+            &transport,
+            "c",
+            "dom",
+            ListQuery::default(),
+            options,
+            endpoint(),
+        )
+        .take(3)
+        .map(|e| {
+            let e = e.unwrap();
+            (e.kind, e.id)
+        })
+        .collect()
+        .await;
+        assert_eq!(
+            events,
+            [
+                (DomainDataEventKind::Updated, "1".to_string()),
+                (DomainDataEventKind::Created, "3".to_string()),
+                (DomainDataEventKind::Deleted, "2".to_string()),
+            ]
+        );
+    }
+
+    struct Events;
+
+    impl HttpExecutor for Events {
+        fn execute(&self, request: Request) -> ExecuteFuture {
+            assert!(request.url().path().ends_with("/data/events"));
+            let body = format!(
+                ": keep-alive\n\nevent: created\nid: 7\ndata: {}\n\nevent: ping\ndata: {{}}\n\r\nevent: deleted\ndata: {{\"id\": \"2\"}}\n\n",
+                meta("1", "a")
+            );
+            let res = Response::from(
+                http::Response::builder()
+                    .status(200)
+                    .header("content-type", "text/event-stream")
+                    .body(body)
+                    .unwrap(),
+            );
+            Box::pin(async move { Ok(res) })
+        }
+    }
+
+    #[tokio::test]
+    async fn reads_server_events() {
+        let transport = Transport::with_executor(Events);
+        let events: Vec<_> = watch_v1(
+            &transport,
+            "c",
+            "dom",
+            ListQuery::default(),
+            WatchOptions::default(),
+            endpoint(),
+        )
+        .take(2)
+        .map(|e| e.unwrap())
+        .collect()
+        .await;
+        assert_eq!(events[0].kind, DomainDataEventKind::Created);
+        assert_eq!(events[0].metadata.as_ref().unwrap().id, "1");
+        assert_eq!(events[1].kind, DomainDataEventKind::Deleted);
+        assert_eq!(events[1].id, "2");
+    }
+
+    /// Closes the event stream right away `closes` times, then sends an event.
+    struct Closes(Mutex<usize>);
+
+    impl HttpExecutor for Closes {
+        fn execute(&self, _request: Request) -> ExecuteFuture {
+            let mut closes = self.0.lock().unwrap();
+            let body = if *closes > 0 {
+                *closes -= 1;
+                String::new()
+            } else {
+                format!("event: created\ndata: {}\n\n", meta("1", "a"))
+            };
+            let res = Response::from(
+                http::Response::builder()
+                    .status(200)
+                    .header("content-type", "text/event-stream")
+                    .body(body)
+                    .unwrap(),
+            );
+            Box::pin(async move { Ok(res) })
+        }
+    }
+
+    #[tokio::test]
+    async fn waits_before_reconnecting_closed_streams() {
+        let transport = Transport::with_executor(Closes(Mutex::new(2)));
+        let options = WatchOptions {
+            poll_interval: Duration::from_millis(50),
+            ..Default::default()
+        };
+        let started = std::time::Instant::now();
+        let mut events = Box::pin(watch_v1(
+            &transport,
+            "c",
+            "dom",
+            ListQuery::default(),
+            options,
+            endpoint(),
+        ));
+        let event = events.next().await.unwrap().unwrap();
+        assert_eq!(event.id, "1");
+        assert!(started.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn parses_multiline_data() {
+        let event = parse_event("event: updated\nid:9\ndata: a\ndata: b");
+        assert_eq!(
+            event,
+            SseEvent {
+                event: "updated".into(),
+                data: "a\nb".into(),
+                id: Some("9".into()),
+            }
+        );
+    }
+}